@@ -0,0 +1,342 @@
+//! Annotations for source code.
+//!
+//! Comments, doc comments and docstrings are extracted with tree-sitter,
+//! everything else is markup. Comment markers are stripped and the content is
+//! annotated like Markdown, which is used by most doc comment formats.
+
+use serde::{Deserialize, Serialize};
+
+/// Settings for checking the comments of a programming language
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(default)]
+pub struct CodeSettings {
+    /// Check the comments of this language
+    pub enabled: bool,
+    /// Ordinary comments, e.g. `//` and `/* */`
+    pub comments: bool,
+    /// Doc comments, e.g. `///`, `//!` and `/** */`
+    pub doc_comments: bool,
+    /// Strings that document a module, class or function (Python)
+    pub docstrings: bool,
+}
+
+impl Default for CodeSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            comments: true,
+            doc_comments: true,
+            docstrings: true,
+        }
+    }
+}
+
+#[cfg(feature = "code")]
+pub use parser::{annotate, grammar};
+
+#[cfg(feature = "code")]
+mod parser {
+    use std::ops::Range;
+
+    use anyhow::{Context, anyhow};
+    use regex::Regex;
+    use tree_sitter::{Language, Node, Parser};
+
+    use super::CodeSettings;
+    use crate::annotated::{AnnotatedText, markdown};
+    use crate::settings::Settings;
+    use crate::source::SourceFile;
+
+    /// Tree-sitter grammar and the node kinds of comments
+    pub struct Grammar {
+        ids: &'static [&'static str],
+        language: fn() -> Language,
+        comments: &'static [&'static str],
+        /// Strings that are the first statement of a module, class or function
+        docstrings: bool,
+    }
+
+    const GRAMMARS: &[Grammar] = &[
+        Grammar {
+            ids: &["rust"],
+            language: || tree_sitter_rust::LANGUAGE.into(),
+            comments: &["line_comment", "block_comment"],
+            docstrings: false,
+        },
+        Grammar {
+            ids: &["python"],
+            language: || tree_sitter_python::LANGUAGE.into(),
+            comments: &["comment"],
+            docstrings: true,
+        },
+        Grammar {
+            ids: &["c", "cpp"],
+            language: || tree_sitter_c::LANGUAGE.into(),
+            comments: &["comment"],
+            docstrings: false,
+        },
+        Grammar {
+            ids: &["javascript", "javascriptreact"],
+            language: || tree_sitter_javascript::LANGUAGE.into(),
+            comments: &["comment"],
+            docstrings: false,
+        },
+        Grammar {
+            ids: &["typescript"],
+            language: || tree_sitter_typescript::LANGUAGE_TYPESCRIPT.into(),
+            comments: &["comment"],
+            docstrings: false,
+        },
+        Grammar {
+            ids: &["typescriptreact"],
+            language: || tree_sitter_typescript::LANGUAGE_TSX.into(),
+            comments: &["comment"],
+            docstrings: false,
+        },
+    ];
+
+    pub fn grammar(language_id: &str) -> Option<&'static Grammar> {
+        GRAMMARS.iter().find(|g| g.ids.contains(&language_id))
+    }
+
+    pub fn annotate(
+        source: &SourceFile,
+        lines: Range<usize>,
+        settings: &Settings,
+        language_id: &str,
+    ) -> anyhow::Result<(Range<usize>, AnnotatedText)> {
+        let ((start, end), _) = source
+            .line_range(lines)
+            .ok_or_else(|| anyhow!("Invalid Line"))?;
+        let changed = start.byte..end.byte;
+
+        let grammar = grammar(language_id).context("Unsupported language")?;
+        let code = settings.code(language_id);
+        if !code.enabled {
+            return Ok((changed, AnnotatedText::new()));
+        }
+        let comments = comments(source.text(), grammar, &code)?;
+
+        // Check the comments that overlap with the changed lines
+        let touched = group(source.text(), &comments)
+            .into_iter()
+            .filter(|group| group.start <= changed.end && changed.start <= group.end)
+            .reduce(|a, b| a.start.min(b.start)..a.end.max(b.end));
+        let Some(range) = touched else {
+            return Ok((changed, AnnotatedText::new()));
+        };
+        Ok((range.clone(), parse(source.text(), &comments).slice(range)))
+    }
+
+    /// Groups adjacent ranges that are only separated by whitespace without an
+    /// empty line
+    fn group(text: &str, ranges: &[Range<usize>]) -> Vec<Range<usize>> {
+        let mut groups: Vec<Range<usize>> = Vec::new();
+        for range in ranges {
+            if let Some(last) = groups.last_mut() {
+                let gap = &text[last.end..range.start];
+                if gap.trim().is_empty() && gap.matches('\n').count() <= 1 {
+                    last.end = range.end;
+                    continue;
+                }
+            }
+            groups.push(range.clone());
+        }
+        groups
+    }
+
+    /// Returns the ranges of the comments and docstrings that are checked
+    fn comments(
+        text: &str,
+        grammar: &Grammar,
+        settings: &CodeSettings,
+    ) -> anyhow::Result<Vec<Range<usize>>> {
+        let mut parser = Parser::new();
+        parser.set_language(&(grammar.language)())?;
+        let tree = parser.parse(text, None).context("Parsing failed")?;
+
+        let mut ranges = Vec::new();
+        let mut stack = vec![tree.root_node()];
+        while let Some(node) = stack.pop() {
+            if grammar.comments.contains(&node.kind()) {
+                let checked = if is_doc_comment(&text[node.byte_range()]) {
+                    settings.doc_comments
+                } else {
+                    settings.comments
+                };
+                if checked {
+                    ranges.push(node.byte_range());
+                }
+            } else if grammar.docstrings && is_docstring(node) {
+                if !settings.docstrings {
+                    continue;
+                }
+                // Only the content without the quotes
+                let content = (0..node.child_count())
+                    .filter_map(|i| node.child(i))
+                    .filter(|c| c.kind() == "string_content")
+                    .map(|c| c.byte_range())
+                    .reduce(|a, b| a.start..b.end);
+                ranges.extend(content);
+            } else {
+                let mut cursor = node.walk();
+                stack.extend(node.children(&mut cursor));
+            }
+        }
+        ranges.sort_by_key(|r| r.start);
+        Ok(ranges)
+    }
+
+    /// Doc comments of Rust, Doxygen and JSDoc, but not separator lines
+    /// like `////` or `/****`
+    fn is_doc_comment(comment: &str) -> bool {
+        let doc = |marker: &str, separator: &str| {
+            comment.starts_with(marker) && !comment.starts_with(separator)
+        };
+        doc("///", "////")
+            || (doc("/**", "/***") && comment != "/**/")
+            || comment.starts_with("//!")
+            || comment.starts_with("/*!")
+    }
+
+    fn is_docstring(node: Node) -> bool {
+        let Some(statement) = node.parent() else {
+            return false;
+        };
+        node.kind() == "string"
+            && statement.kind() == "expression_statement"
+            && statement.named_child_count() == 1
+            && statement.parent().is_some_and(|body| {
+                matches!(body.kind(), "module" | "block")
+                    && body.named_child(0).is_some_and(|first| first == statement)
+            })
+    }
+
+    fn parse(text: &str, comments: &[Range<usize>]) -> AnnotatedText {
+        let prefix = Regex::new(r"^[ \t]*(?://[/!]?|/\*+!?|\*+(?:[ \t]|$)|#+!?)?[ \t]?").unwrap();
+        let suffix = Regex::new(r"[ \t]*\*+/[ \t]*$").unwrap();
+        let groups = group(text, comments);
+
+        let mut annot = AnnotatedText::new();
+        let mut last = 0;
+        for comment in comments {
+            if last < comment.start {
+                // Comments of the same group continue the paragraph
+                let continued = groups
+                    .iter()
+                    .any(|g| g.start < last && comment.end <= g.end);
+                let interpret_as = if continued { " " } else { "\n\n" };
+                annot.add_markup(
+                    text[last..comment.start].to_string(),
+                    interpret_as.to_string(),
+                );
+            }
+            for line in text[comment.clone()].split_inclusive('\n') {
+                let content = line.trim_end_matches(['\r', '\n']);
+                let start = prefix.find(content).map_or(0, |m| m.end());
+                let end = suffix
+                    .find(&content[start..])
+                    .map_or(content.len(), |m| start + m.start());
+                annot.add_markup(line[..start].to_string(), String::new());
+                annot.add_text(line[start..end].to_string());
+                annot.add_markup(content[end..].to_string(), String::new());
+                annot.add_text(line[content.len()..].to_string());
+            }
+            last = comment.end;
+        }
+        if last < text.len() {
+            annot.add_markup(text[last..].to_string(), String::new());
+        }
+        markdown::inline(&mut annot);
+        annot
+    }
+
+    #[cfg(test)]
+    mod test {
+        use super::*;
+
+        fn text(annot: &AnnotatedText) -> String {
+            annot.text_parts().map(|(_, t)| t).collect()
+        }
+
+        #[test]
+        fn rust() {
+            let code = "/// Returns teh `value`\n\
+                /// of the item.\n\
+                fn value() -> u32 {\n    \
+                    let s = \"not checked\";\n    \
+                    /* A block\n     \
+                     * comment */\n    \
+                    42 // trailing\n\
+                }\n";
+            let grammar = grammar("rust").unwrap();
+            let comments = comments(code, grammar, &CodeSettings::default()).unwrap();
+            assert_eq!(comments.len(), 4);
+            let annot = parse(code, &comments);
+            assert_eq!(annot.len(), code.len());
+            assert_eq!(
+                text(&annot),
+                "Returns teh \nof the item.\nA block\ncommenttrailing"
+            );
+
+            let source = SourceFile::new(code.to_string());
+            let settings = Settings::default();
+            let (range, annot) = annotate(&source, 1..2, &settings, "rust").unwrap();
+            assert_eq!(&code[range], "/// Returns teh `value`\n/// of the item.\n");
+            assert_eq!(text(&annot), "Returns teh \nof the item.\n");
+            let (_, annot) = annotate(&source, 3..4, &settings, "rust").unwrap();
+            assert!(annot.is_empty());
+        }
+
+        #[test]
+        fn python() {
+            let code = "def f():\n    \
+                \"\"\"Teh docstring.\"\"\"\n    \
+                x = \"no docstring\"\n    \
+                # A comment\n";
+            let grammar = grammar("python").unwrap();
+            let comments = comments(code, grammar, &CodeSettings::default()).unwrap();
+            let annot = parse(code, &comments);
+            assert_eq!(annot.len(), code.len());
+            assert_eq!(text(&annot), "Teh docstring.A comment");
+        }
+
+        #[test]
+        fn kinds() {
+            let code = "//! Module\n\
+                /// Doc\n\
+                /** Block doc */\n\
+                // Comment\n\
+                fn f() {}\n";
+            let rust = grammar("rust").unwrap();
+            let checked = |settings: CodeSettings| {
+                let comments = comments(code, rust, &settings).unwrap();
+                text(&parse(code, &comments))
+            };
+            let settings = CodeSettings {
+                comments: false,
+                ..Default::default()
+            };
+            assert_eq!(checked(settings), "Module\nDoc\nBlock doc");
+            let settings = CodeSettings {
+                doc_comments: false,
+                ..Default::default()
+            };
+            assert_eq!(checked(settings), "Comment");
+            assert!(!is_doc_comment("//// Separator"));
+            assert!(!is_doc_comment("/*****/"));
+
+            let code = "def f():\n    \"\"\"Docstring.\"\"\"\n    # Comment\n";
+            let comments = comments(
+                code,
+                grammar("python").unwrap(),
+                &CodeSettings {
+                    docstrings: false,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+            assert_eq!(text(&parse(code, &comments)), "Comment");
+        }
+    }
+}
@@ -0,0 +1,101 @@
+//! Annotations for git commit messages.
+//!
+//! Comments, the diff below the scissors line and trailers like
+//! `Signed-off-by:` are markup. The subject line is checked as a separate
+//! paragraph.
+
+use std::ops::Range;
+
+use regex::Regex;
+
+use crate::settings::Settings;
+use crate::source::SourceFile;
+
+use super::{AnnotatedText, paragraph};
+
+const SCISSORS: &str = "# ------------------------ >8 ------------------------";
+
+pub fn annotate(
+    source: &SourceFile,
+    lines: Range<usize>,
+    _settings: &Settings,
+) -> anyhow::Result<(Range<usize>, AnnotatedText)> {
+    // The subject and trailers depend on the whole message
+    let (range, _) = paragraph(source, lines)?;
+    Ok((range.clone(), parse(source.text()).slice(range)))
+}
+
+pub fn parse(msg: &str) -> AnnotatedText {
+    let trailer = Regex::new(r"^[\w-]+:[ \t]|^[ \t]+\S").unwrap();
+
+    let lines = msg.split_inclusive('\n').collect::<Vec<_>>();
+    let end = lines
+        .iter()
+        .position(|l| content(l) == SCISSORS)
+        .unwrap_or(lines.len());
+    let comment = |i: usize| lines[i].starts_with('#');
+
+    // The last paragraph are trailers if all of its lines look like trailers
+    let mut trailers = end;
+    for i in (0..end).rev().filter(|i| !comment(*i)) {
+        if content(lines[i]).trim().is_empty() {
+            if trailers < end {
+                break;
+            }
+        } else if trailer.is_match(lines[i]) {
+            trailers = i;
+        } else {
+            trailers = end;
+            break;
+        }
+    }
+    let subject = (0..end).find(|i| !comment(*i) && !lines[*i].trim().is_empty());
+    if subject.is_some_and(|s| s >= trailers) {
+        trailers = end;
+    }
+
+    let mut annot = AnnotatedText::new();
+    for (i, line) in lines.iter().enumerate() {
+        if i >= end || comment(i) || (i >= trailers && !line.trim().is_empty()) {
+            annot.add_markup(line.to_string(), String::new());
+        } else if Some(i) == subject {
+            let text = content(line);
+            annot.add_text(text.to_string());
+            annot.add_markup(line[text.len()..].to_string(), "\n\n".to_string());
+        } else {
+            annot.add_text(line.to_string());
+        }
+    }
+    annot
+}
+
+fn content(line: &str) -> &str {
+    line.trim_end_matches(['\r', '\n'])
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn git_commit() {
+        let msg = "# Comment before\n\
+            Fix teh parser\n\
+            Body: with a colon.\n\
+            \n\
+            Signed-off-by: A <a@example.org>\n\
+            Co-authored-by: B <b@example.org>\n\
+            # Please enter the commit message\n\
+            # ------------------------ >8 ------------------------\n\
+            diff --git a/x b/x\n";
+        let annot = parse(msg);
+        assert_eq!(annot.len(), msg.len());
+        let text = annot.text_parts().map(|(_, t)| t).collect::<String>();
+        assert_eq!(text, "Fix teh parserBody: with a colon.\n\n");
+
+        // A single paragraph is never a trailer
+        let annot = parse("Fixes: the parser\n");
+        let text = annot.text_parts().map(|(_, t)| t).collect::<String>();
+        assert_eq!(text, "Fixes: the parser");
+    }
+}
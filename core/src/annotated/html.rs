@@ -0,0 +1,216 @@
+//! Annotations for HTML documents.
+//!
+//! Tags, comments, `<script>` and `<style>` blocks are converted into markup
+//! and entities are interpreted as the characters they represent, while the
+//! text nodes keep their offsets.
+
+use std::ops::Range;
+
+use crate::settings::Settings;
+use crate::source::SourceFile;
+
+use super::{AnnotatedText, paragraph};
+
+/// Tags that separate paragraphs
+const BLOCK_TAGS: &[&str] = &[
+    "address",
+    "article",
+    "aside",
+    "blockquote",
+    "body",
+    "br",
+    "dd",
+    "div",
+    "dl",
+    "dt",
+    "figcaption",
+    "figure",
+    "footer",
+    "form",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "head",
+    "header",
+    "hr",
+    "html",
+    "li",
+    "main",
+    "nav",
+    "ol",
+    "p",
+    "pre",
+    "section",
+    "table",
+    "td",
+    "th",
+    "title",
+    "tr",
+    "ul",
+];
+/// Tags whose content is not text
+const RAW_TAGS: &[&str] = &["script", "style"];
+
+pub fn annotate(
+    source: &SourceFile,
+    lines: Range<usize>,
+    _settings: &Settings,
+) -> anyhow::Result<(Range<usize>, AnnotatedText)> {
+    // Tags and scripts may span multiple paragraphs, so parse the whole document
+    let (range, _) = paragraph(source, lines)?;
+    Ok((range.clone(), parse(source.text()).slice(range)))
+}
+
+pub fn parse(html: &str) -> AnnotatedText {
+    let mut annot = AnnotatedText::new();
+    let mut text_start = 0;
+    let mut i = 0;
+    while let Some(rest) = html.get(i..).filter(|r| !r.is_empty()) {
+        let (len, interpret_as) = if rest.starts_with("<!--") {
+            (
+                rest.find("-->").map_or(rest.len(), |end| end + 3),
+                String::new(),
+            )
+        } else if rest.starts_with('<')
+            && rest[1..].starts_with(|c: char| c.is_ascii_alphabetic() || matches!(c, '/' | '!'))
+        {
+            (element(rest), String::new())
+        } else if let Some((len, c)) = rest.starts_with('&').then(|| entity(rest)).flatten() {
+            (len, c.to_string())
+        } else {
+            i += rest.chars().next().map_or(1, char::len_utf8);
+            continue;
+        };
+        let name = tag_name(&rest[..len]);
+        let interpret_as = if BLOCK_TAGS.contains(&name.as_str()) {
+            "\n\n".to_string()
+        } else {
+            interpret_as
+        };
+
+        if text_start < i {
+            annot.add_text(html[text_start..i].to_string());
+        }
+        annot.add_markup(html[i..i + len].to_string(), interpret_as);
+        i += len;
+        text_start = i;
+    }
+    if text_start < html.len() {
+        annot.add_text(html[text_start..].to_string());
+    }
+    annot
+}
+
+/// Length of the tag, including the content of script and style blocks
+fn element(rest: &str) -> usize {
+    let end = tag_end(rest);
+    let name = tag_name(&rest[..end]);
+    if !RAW_TAGS.contains(&name.as_str()) || rest[..end].ends_with("/>") {
+        return end;
+    }
+    let Some(close) = rest[end..]
+        .to_ascii_lowercase()
+        .find(&format!("</{name}"))
+        .map(|i| end + i)
+    else {
+        return rest.len();
+    };
+    close + tag_end(&rest[close..])
+}
+
+/// Finds the end of the tag, skipping quoted attribute values
+fn tag_end(tag: &str) -> usize {
+    let mut quote = None;
+    for (i, c) in tag.char_indices().skip(1) {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), c) if q == c => quote = None,
+            (None, '>') => return i + 1,
+            _ => {}
+        }
+    }
+    tag.len()
+}
+
+/// Lowercase name of opening and closing tags
+fn tag_name(tag: &str) -> String {
+    tag.strip_prefix('<')
+        .map(|t| t.trim_start_matches('/'))
+        .unwrap_or_default()
+        .chars()
+        .take_while(char::is_ascii_alphanumeric)
+        .collect::<String>()
+        .to_ascii_lowercase()
+}
+
+/// Decodes named and numeric character references
+fn entity(rest: &str) -> Option<(usize, char)> {
+    let (end, _) = rest.char_indices().take(32).find(|(_, c)| *c == ';')?;
+    let name = &rest[1..end];
+    let c = if let Some(hex) = name.strip_prefix("#x").or(name.strip_prefix("#X")) {
+        char::from_u32(u32::from_str_radix(hex, 16).ok()?)?
+    } else if let Some(dec) = name.strip_prefix('#') {
+        char::from_u32(dec.parse().ok()?)?
+    } else {
+        match name {
+            "amp" => '&',
+            "lt" => '<',
+            "gt" => '>',
+            "quot" => '"',
+            "apos" => '\'',
+            "nbsp" => '\u{a0}',
+            "shy" => '\u{ad}',
+            "ndash" => '–',
+            "mdash" => '—',
+            "hellip" => '…',
+            "lsquo" => '‘',
+            "rsquo" => '’',
+            "ldquo" => '“',
+            "rdquo" => '”',
+            "bdquo" => '„',
+            "laquo" => '«',
+            "raquo" => '»',
+            "auml" => 'ä',
+            "ouml" => 'ö',
+            "uuml" => 'ü',
+            "Auml" => 'Ä',
+            "Ouml" => 'Ö',
+            "Uuml" => 'Ü',
+            "szlig" => 'ß',
+            "eacute" => 'é',
+            "egrave" => 'è',
+            "copy" => '©',
+            _ => return None,
+        }
+    };
+    Some((end + 1, c))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn html() {
+        let html = "<html>\n\
+            <style>\n\np { color: red; }\n</style>\n\
+            <p class=\"a > b\">Fish &amp; chips</p>\n\
+            \n\
+            <SCRIPT>if (a < b) {}</script><!-- Teh comment -->\n\
+            <p>Caf&eacute; &#x263A; <b>bold</b></p>\n";
+        let annot = parse(html);
+        assert_eq!(annot.len(), html.len());
+        let text = annot.text_parts().map(|(_, t)| t).collect::<String>();
+        assert_eq!(text, "\n\nFish  chips\n\n\nCaf  bold\n");
+
+        let source = SourceFile::new(html.to_string());
+        let (range, annot) = annotate(&source, 3..4, &Settings::default()).unwrap();
+        assert!(html[range.clone()].starts_with("p { color: red; }\n</style>\n<p"));
+        assert_eq!(annot.len(), range.len());
+        let text = annot.text_parts().map(|(_, t)| t).collect::<String>();
+        assert_eq!(text, "\nFish  chips\n");
+    }
+}
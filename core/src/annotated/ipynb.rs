@@ -0,0 +1,384 @@
+//! Annotations for Jupyter notebooks (`.ipynb`).
+//!
+//! Only the sources of markdown cells are checked. The raw JSON around them,
+//! code cells and outputs are markup and escape sequences are interpreted
+//! as the characters they represent, so the offsets map directly into the
+//! JSON document. The interpreted cells are then annotated like Markdown
+//! files.
+
+use std::ops::Range;
+
+use anyhow::{Result, anyhow, bail};
+
+use crate::settings::Settings;
+use crate::source::SourceFile;
+
+use super::{AnnotatedText, Annotation, markdown};
+
+pub fn annotate(
+    source: &SourceFile,
+    lines: Range<usize>,
+    _settings: &Settings,
+) -> Result<(Range<usize>, AnnotatedText)> {
+    let ((start, end), _) = source
+        .line_range(lines)
+        .ok_or_else(|| anyhow!("Invalid Line"))?;
+    let changed = start.byte..end.byte;
+
+    let cells = markdown_cells(source.text())?;
+    // Check the cells that overlap with the changed lines
+    let touched = cells
+        .iter()
+        .filter(|cell| cell.span.start <= changed.end && changed.start <= cell.span.end)
+        .map(|cell| cell.span.clone())
+        .reduce(|a, b| a.start.min(b.start)..a.end.max(b.end));
+    let Some(range) = touched else {
+        return Ok((changed, AnnotatedText::new()));
+    };
+    let annot = with_markdown(&parse(source.text(), &cells));
+    Ok((range.clone(), annot.slice(range)))
+}
+
+/// Source strings of a markdown cell
+#[derive(Debug)]
+struct Cell {
+    /// Ranges of the string contents (without quotes)
+    strings: Vec<Range<usize>>,
+    /// Range from the first to the last string
+    span: Range<usize>,
+}
+
+fn parse(json: &str, cells: &[Cell]) -> AnnotatedText {
+    let mut annot = AnnotatedText::new();
+    let mut last = 0;
+    for cell in cells {
+        for (i, string) in cell.strings.iter().enumerate() {
+            if last < string.start {
+                // Lines of a cell are separate strings that include the line break
+                let interpret_as = if i == 0 { "\n\n" } else { "" };
+                annot.add_markup(
+                    json[last..string.start].to_string(),
+                    interpret_as.to_string(),
+                );
+            }
+            unescape(&json[string.clone()], &mut annot);
+            last = string.end;
+        }
+    }
+    if last < json.len() {
+        annot.add_markup(json[last..].to_string(), String::new());
+    }
+    annot
+}
+
+/// Applies the Markdown annotator to the interpreted text of the JSON
+/// annotation. Its markup replaces the underlying text and escape sequences,
+/// which keeps the offsets into the JSON document.
+fn with_markdown(json: &AnnotatedText) -> AnnotatedText {
+    // Pieces of the JSON and the interpreted text they start at
+    let mut md = String::new();
+    let mut pieces = Vec::new();
+    for part in &json.annotation {
+        pieces.push((md.len(), part));
+        match part {
+            Annotation::Text { text } => md.push_str(text),
+            Annotation::Markup { interpret_as, .. } => md.push_str(interpret_as),
+        }
+    }
+    // Parts of the Markdown annotation with their end in the interpreted text
+    let mut end = 0;
+    let parts = markdown::parse(&md)
+        .annotation
+        .into_iter()
+        .map(|part| {
+            end += match &part {
+                Annotation::Text { text } => text.len(),
+                Annotation::Markup { markup, .. } => markup.len(),
+            };
+            (end, part)
+        })
+        .collect::<Vec<_>>();
+
+    let mut annot = AnnotatedText::new();
+    let mut j = 0;
+    // Index of the last Markdown markup whose interpretation was added
+    let mut interpreted = None;
+    let mut add =
+        |annot: &mut AnnotatedText, j: usize, piece: &Annotation| match (&parts[j].1, piece) {
+            (Annotation::Text { .. }, Annotation::Text { text }) => annot.add_text(text.clone()),
+            (Annotation::Text { .. }, markup) => annot.annotation.push(markup.clone()),
+            (Annotation::Markup { interpret_as, .. }, piece) => {
+                let markup = match piece {
+                    Annotation::Text { text } => text,
+                    Annotation::Markup { markup, .. } => markup,
+                };
+                let interpret_as = if interpreted != Some(j) {
+                    interpreted = Some(j);
+                    interpret_as.clone()
+                } else {
+                    String::new()
+                };
+                annot.add_markup(markup.clone(), interpret_as);
+            }
+        };
+    for (start, piece) in pieces {
+        match piece {
+            // Text may be split up by the Markdown annotation
+            Annotation::Text { text } => {
+                let mut pos = start;
+                while pos < start + text.len() {
+                    while j + 1 < parts.len() && parts[j].0 <= pos {
+                        j += 1;
+                    }
+                    let end = parts[j].0.clamp(pos + 1, start + text.len());
+                    let text = text[pos - start..end - start].to_string();
+                    add(&mut annot, j, &Annotation::Text { text });
+                    pos = end;
+                }
+            }
+            markup => {
+                while j + 1 < parts.len() && parts[j].0 <= start {
+                    j += 1;
+                }
+                if parts.is_empty() {
+                    annot.annotation.push(markup.clone());
+                } else {
+                    add(&mut annot, j, markup);
+                }
+            }
+        }
+    }
+    annot
+}
+
+/// Adds the string contents, interpreting the escape sequences
+fn unescape(string: &str, annot: &mut AnnotatedText) {
+    let mut text_start = 0;
+    let mut i = 0;
+    while let Some(pos) = string[i..].find('\\').map(|p| i + p) {
+        let (len, c) = match string.as_bytes().get(pos + 1) {
+            Some(b'n') => (2, Some('\n')),
+            Some(b't') => (2, Some('\t')),
+            Some(b'r') => (2, Some('\r')),
+            Some(b'"') => (2, Some('"')),
+            Some(b'\\') => (2, Some('\\')),
+            Some(b'/') => (2, Some('/')),
+            Some(b'u') => unicode_escape(&string[pos..]),
+            _ => (1, None),
+        };
+        if text_start < pos {
+            annot.add_text(string[text_start..pos].to_string());
+        }
+        annot.add_markup(
+            string[pos..pos + len].to_string(),
+            c.map(String::from).unwrap_or_default(),
+        );
+        i = pos + len;
+        text_start = i;
+    }
+    if text_start < string.len() {
+        annot.add_text(string[text_start..].to_string());
+    }
+}
+
+/// Decodes `\uXXXX` escapes, including surrogate pairs
+fn unicode_escape(escape: &str) -> (usize, Option<char>) {
+    let hex = |s: Option<&str>| s.and_then(|s| u32::from_str_radix(s, 16).ok());
+    let Some(high) = hex(escape.get(2..6)) else {
+        return (2, None);
+    };
+    if (0xd800..0xdc00).contains(&high)
+        && escape.get(6..8) == Some("\\u")
+        && let Some(low) = hex(escape.get(8..12))
+    {
+        let c = 0x10000 + ((high - 0xd800) << 10) + (low.wrapping_sub(0xdc00) & 0x3ff);
+        return (12, char::from_u32(c));
+    }
+    (6, char::from_u32(high))
+}
+
+/// Finds the source strings of the markdown cells in the raw JSON
+fn markdown_cells(json: &str) -> Result<Vec<Cell>> {
+    let mut scanner = Scanner { json, pos: 0 };
+    let mut cells = Vec::new();
+    scanner.value(&mut Vec::new(), &mut |path, string| {
+        if let [Key::Field("cells"), Key::Index(i), Key::Field("source"), ..] = path {
+            if cells.len() <= *i {
+                cells.resize_with(*i + 1, || (false, Vec::new()));
+            }
+            cells[*i].1.push(string);
+        } else if let [Key::Field("cells"), Key::Index(i), Key::Field("cell_type")] = path {
+            if cells.len() <= *i {
+                cells.resize_with(*i + 1, || (false, Vec::new()));
+            }
+            cells[*i].0 = &json[string] == "markdown";
+        }
+    })?;
+    Ok(cells
+        .into_iter()
+        .filter(|(markdown, strings)| *markdown && !strings.is_empty())
+        .map(|(_, strings)| Cell {
+            span: strings[0].start..strings[strings.len() - 1].end,
+            strings,
+        })
+        .collect())
+}
+
+#[derive(Debug)]
+enum Key<'a> {
+    Field(&'a str),
+    Index(usize),
+}
+
+/// Minimal JSON scanner that reports the ranges of string values
+struct Scanner<'a> {
+    json: &'a str,
+    pos: usize,
+}
+
+impl<'a> Scanner<'a> {
+    fn value(
+        &mut self,
+        path: &mut Vec<Key<'a>>,
+        on_string: &mut impl FnMut(&[Key<'a>], Range<usize>),
+    ) -> Result<()> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some(b'{') => {
+                self.pos += 1;
+                loop {
+                    self.skip_whitespace();
+                    if self.eat(b'}') {
+                        break;
+                    }
+                    let key = self.string()?;
+                    self.skip_whitespace();
+                    if !self.eat(b':') {
+                        bail!("Expected ':' at {}", self.pos);
+                    }
+                    path.push(Key::Field(&self.json[key]));
+                    self.value(path, on_string)?;
+                    path.pop();
+                    self.skip_whitespace();
+                    if !self.eat(b',') && self.peek() != Some(b'}') {
+                        bail!("Expected ',' at {}", self.pos);
+                    }
+                }
+            }
+            Some(b'[') => {
+                self.pos += 1;
+                for i in 0.. {
+                    self.skip_whitespace();
+                    if self.eat(b']') {
+                        break;
+                    }
+                    path.push(Key::Index(i));
+                    self.value(path, on_string)?;
+                    path.pop();
+                    self.skip_whitespace();
+                    if !self.eat(b',') && self.peek() != Some(b']') {
+                        bail!("Expected ',' at {}", self.pos);
+                    }
+                }
+            }
+            Some(b'"') => {
+                let string = self.string()?;
+                on_string(path, string);
+            }
+            Some(_) => {
+                // Numbers, booleans and null
+                let len = self.json[self.pos..]
+                    .find(|c: char| matches!(c, ',' | '}' | ']') || c.is_whitespace())
+                    .unwrap_or(self.json.len() - self.pos);
+                if len == 0 {
+                    bail!("Unexpected character at {}", self.pos);
+                }
+                self.pos += len;
+            }
+            None => bail!("Unexpected end of JSON"),
+        }
+        Ok(())
+    }
+
+    /// Returns the range of the string contents
+    fn string(&mut self) -> Result<Range<usize>> {
+        if !self.eat(b'"') {
+            bail!("Expected string at {}", self.pos);
+        }
+        let start = self.pos;
+        let bytes = self.json.as_bytes();
+        while let Some(&c) = bytes.get(self.pos) {
+            match c {
+                b'\\' => self.pos += 2,
+                b'"' => {
+                    self.pos += 1;
+                    return Ok(start..self.pos - 1);
+                }
+                _ => self.pos += 1,
+            }
+        }
+        bail!("Unterminated string")
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(|c| c.is_ascii_whitespace()) {
+            self.pos += 1;
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.json.as_bytes().get(self.pos).copied()
+    }
+
+    fn eat(&mut self, c: u8) -> bool {
+        let found = self.peek() == Some(c);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn notebook() {
+        let json = r##"{
+ "cells": [
+  {
+   "cell_type": "markdown",
+   "metadata": {},
+   "source": [
+    "# Teh \"Title\"\n",
+    "See the [dcos](https://exmaple.org/wrold) and **this**.\n",
+    "Caf\u00e9 \ud83d\ude00 text"
+   ]
+  },
+  {
+   "cell_type": "code",
+   "execution_count": 1,
+   "outputs": [{"data": {"image/png": "iVBORw0KGgo"}}],
+   "source": ["print(\"wrold\")"]
+  }
+ ],
+ "nbformat": 4
+}
+"##;
+        let source = SourceFile::new(json.to_string());
+        let (range, annot) = annotate(&source, 7..8, &Settings::default()).unwrap();
+        assert_eq!(annot.len(), range.len());
+        assert!(json[range.clone()].starts_with("# Teh"));
+        assert!(json[range].ends_with("text"));
+        let text = annot.text_parts().map(|(_, t)| t).collect::<String>();
+        assert_eq!(text, "Teh TitleSee the dcos and this.Caf  text");
+        let json = serde_json::to_string(&annot).unwrap();
+        assert!(json.contains(r#"{"markup":"\\u00e9","interpretAs":"é"}"#));
+        assert!(json.contains(r#"{"markup":"\\ud83d\\ude00","interpretAs":"😀"}"#));
+
+        // Code cells are not checked
+        let (_, annot) = annotate(&source, 16..17, &Settings::default()).unwrap();
+        assert!(annot.is_empty());
+    }
+}
@@ -0,0 +1,368 @@
+//! Annotations for LaTeX documents.
+//!
+//! Commands, comments, math and the content of environments like
+//! `lstlisting` are markup, while the arguments of unknown commands are
+//! checked. Like in ltex, the handling of commands and environments can be
+//! configured, e.g. `"\\cite[]{}": "dummy"` or `"\\todo{}": "ignore"`.
+
+use std::collections::HashMap;
+use std::ops::Range;
+
+use serde::{Deserialize, Serialize};
+
+use crate::settings::Settings;
+use crate::source::SourceFile;
+
+use super::{AnnotatedText, paragraph};
+
+/// Configuration of commands and environments
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct LatexSettings {
+    /// Commands with their arguments (e.g. `\cite[]{}`) and how they are checked
+    pub commands: HashMap<String, LatexAction>,
+    /// Environment names and how their content is checked
+    pub environments: HashMap<String, LatexAction>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum LatexAction {
+    /// Skip the command, check its mandatory arguments
+    #[default]
+    Default,
+    /// Skip the command with its arguments or the environment
+    Ignore,
+    /// Replace the command with a dummy word
+    Dummy,
+    /// Replace the command with a plural dummy word
+    PluralDummy,
+    /// Replace the command with a dummy word starting with a vowel
+    VowelDummy,
+}
+
+impl LatexAction {
+    fn interpret_as(self) -> &'static str {
+        match self {
+            Self::Default | Self::Ignore => "",
+            Self::Dummy => "Dummy",
+            Self::PluralDummy => "Dummies",
+            Self::VowelDummy => "Ina",
+        }
+    }
+}
+
+const COMMANDS: &[(&str, LatexAction)] = &[
+    ("\\autoref{}", LatexAction::Dummy),
+    ("\\cite[][]{}", LatexAction::Dummy),
+    ("\\citep[][]{}", LatexAction::Dummy),
+    ("\\citet[][]{}", LatexAction::Dummy),
+    ("\\cref{}", LatexAction::Dummy),
+    ("\\Cref{}", LatexAction::Dummy),
+    ("\\eqref{}", LatexAction::Dummy),
+    ("\\pageref{}", LatexAction::Dummy),
+    ("\\ref{}", LatexAction::Dummy),
+    ("\\url{}", LatexAction::Dummy),
+    ("\\bibliography{}", LatexAction::Ignore),
+    ("\\bibliographystyle{}", LatexAction::Ignore),
+    ("\\documentclass[]{}", LatexAction::Ignore),
+    ("\\hspace{}", LatexAction::Ignore),
+    ("\\include{}", LatexAction::Ignore),
+    ("\\includegraphics[]{}", LatexAction::Ignore),
+    ("\\input{}", LatexAction::Ignore),
+    ("\\label{}", LatexAction::Ignore),
+    ("\\newcommand{}[][]{}", LatexAction::Ignore),
+    ("\\renewcommand{}[][]{}", LatexAction::Ignore),
+    ("\\setlength{}{}", LatexAction::Ignore),
+    ("\\usepackage[]{}", LatexAction::Ignore),
+    ("\\vspace{}", LatexAction::Ignore),
+];
+
+const ENVIRONMENTS: &[&str] = &[
+    "align",
+    "align*",
+    "displaymath",
+    "equation",
+    "equation*",
+    "gather",
+    "gather*",
+    "lstlisting",
+    "math",
+    "minted",
+    "multline",
+    "tikzpicture",
+    "verbatim",
+];
+
+pub fn annotate(
+    source: &SourceFile,
+    lines: Range<usize>,
+    settings: &Settings,
+) -> anyhow::Result<(Range<usize>, AnnotatedText)> {
+    // Environments may span multiple paragraphs, so parse the whole document
+    let (range, _) = paragraph(source, lines)?;
+    let mut latex = settings.latex.clone();
+    if settings.skip_quotes("latex").blockquotes {
+        for env in ["quote", "quotation"] {
+            latex
+                .environments
+                .entry(env.into())
+                .or_insert(LatexAction::Ignore);
+        }
+    }
+    Ok((range.clone(), parse(source.text(), &latex).slice(range)))
+}
+
+pub fn parse(latex: &str, settings: &LatexSettings) -> AnnotatedText {
+    // Arguments and actions by command name, the settings override the defaults
+    let mut commands = HashMap::new();
+    let signatures = COMMANDS
+        .iter()
+        .map(|(s, a)| (*s, *a))
+        .chain(settings.commands.iter().map(|(s, a)| (s.as_str(), *a)));
+    for (signature, action) in signatures {
+        let signature = signature.trim_start_matches('\\');
+        let args = signature.find(['{', '[']).unwrap_or(signature.len());
+        commands.insert(&signature[..args], (&signature[args..], action));
+    }
+    let environment = |name: &str| {
+        settings
+            .environments
+            .get(name)
+            .copied()
+            .unwrap_or(if ENVIRONMENTS.contains(&name) {
+                LatexAction::Ignore
+            } else {
+                LatexAction::Default
+            })
+    };
+
+    let mut parser = Parser {
+        text: latex,
+        annot: AnnotatedText::new(),
+        last: 0,
+    };
+    let bytes = latex.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' => {
+                let end = latex[i..].find('\n').map_or(latex.len(), |e| i + e);
+                i = parser.markup(i..end, "");
+            }
+            b'{' | b'}' => i = parser.markup(i..i + 1, ""),
+            b'~' => i = parser.markup(i..i + 1, " "),
+            b'$' => {
+                let display = latex[i..].starts_with("$$");
+                let (delim, interpret_as) = if display { ("$$", "") } else { ("$", "Dummy") };
+                let start = i + delim.len();
+                let end =
+                    find_unescaped(latex, start, delim).map_or(latex.len(), |e| e + delim.len());
+                i = parser.markup(i..end, interpret_as);
+            }
+            b'\\' => {
+                let name_end = latex[i + 1..]
+                    .find(|c: char| !c.is_ascii_alphabetic())
+                    .map_or(latex.len(), |e| i + 1 + e);
+                if name_end == i + 1 {
+                    // Escaped characters and math
+                    let Some(c) = latex[i + 1..].chars().next() else {
+                        i = parser.markup(i..latex.len(), "");
+                        continue;
+                    };
+                    let end = i + 1 + c.len_utf8();
+                    i = match c {
+                        '(' | '[' => {
+                            let delim = if c == '(' { "\\)" } else { "\\]" };
+                            let close = latex[end..]
+                                .find(delim)
+                                .map_or(latex.len(), |e| end + e + 2);
+                            parser.markup(i..close, if c == '(' { "Dummy" } else { "" })
+                        }
+                        '\\' => parser.markup(i..end, "\n"),
+                        ',' | ' ' => parser.markup(i..end, " "),
+                        c if c.is_ascii_punctuation() => parser.markup(i..end, &c.to_string()),
+                        _ => parser.markup(i..end, ""),
+                    };
+                    continue;
+                }
+                let mut end = name_end;
+                if latex[end..].starts_with('*') {
+                    end += 1;
+                }
+                let name = &latex[i + 1..name_end];
+
+                if name == "begin" || name == "end" {
+                    let Some(arg) = argument(latex, end, b'{') else {
+                        i = parser.markup(i..end, "");
+                        continue;
+                    };
+                    let env = &latex[arg.start + 1..arg.end - 1];
+                    let mut end = arg.end;
+                    if name == "begin" && environment(env) == LatexAction::Ignore {
+                        let close = format!("\\end{{{env}}}");
+                        end = latex[end..]
+                            .find(&close)
+                            .map_or(latex.len(), |e| end + e + close.len());
+                    } else if name == "begin" {
+                        while let Some(arg) = argument(latex, end, b'[') {
+                            end = arg.end;
+                        }
+                    }
+                    i = parser.markup(i..end, "\n\n");
+                    continue;
+                }
+
+                let (args, action) = commands
+                    .get(name)
+                    .copied()
+                    .unwrap_or(("", LatexAction::Default));
+                if action == LatexAction::Default {
+                    // The optional arguments are skipped, the others checked
+                    while let Some(arg) = argument(latex, end, b'[') {
+                        end = arg.end;
+                    }
+                    i = parser.markup(i..end, "");
+                } else {
+                    for open in args.bytes().filter(|c| matches!(c, b'{' | b'[')) {
+                        match argument(latex, end, open) {
+                            Some(arg) => end = arg.end,
+                            None if open == b'[' => {}
+                            None => break,
+                        }
+                    }
+                    i = parser.markup(i..end, action.interpret_as());
+                }
+            }
+            _ => {
+                let end = latex[i..]
+                    .find(['%', '{', '}', '~', '$', '\\'])
+                    .map_or(latex.len(), |e| i + e);
+                i = end;
+            }
+        }
+    }
+    parser.finish()
+}
+
+/// Collects the text between the markup
+struct Parser<'a> {
+    text: &'a str,
+    annot: AnnotatedText,
+    last: usize,
+}
+
+impl Parser<'_> {
+    /// Adds the range as markup and the text before it, returns its end
+    fn markup(&mut self, range: Range<usize>, interpret_as: &str) -> usize {
+        if self.last < range.start {
+            self.annot
+                .add_text(self.text[self.last..range.start].to_string());
+        }
+        self.annot.add_markup(
+            self.text[range.clone()].to_string(),
+            interpret_as.to_string(),
+        );
+        self.last = range.end;
+        range.end
+    }
+    fn finish(mut self) -> AnnotatedText {
+        if self.last < self.text.len() {
+            self.annot.add_text(self.text[self.last..].to_string());
+        }
+        self.annot
+    }
+}
+
+/// Returns the range of the argument starting at `start` (after whitespace)
+/// including the braces
+fn argument(text: &str, start: usize, open: u8) -> Option<Range<usize>> {
+    let close = if open == b'{' { b'}' } else { b']' };
+    let bytes = text.as_bytes();
+    let start = start + text[start..].len() - text[start..].trim_start().len();
+    if bytes.get(start) != Some(&open) {
+        return None;
+    }
+    let mut depth = 0;
+    let mut i = start;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 1,
+            c if c == open => depth += 1,
+            c if c == close => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(start..i + 1);
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    None
+}
+
+/// Finds the delimiter, skipping escaped characters
+fn find_unescaped(text: &str, start: usize, delim: &str) -> Option<usize> {
+    let bytes = text.as_bytes();
+    let mut i = start;
+    while i < bytes.len() {
+        if bytes[i] == b'\\' {
+            i += 2;
+        } else if bytes[i..].starts_with(delim.as_bytes()) {
+            return Some(i);
+        } else {
+            i += 1;
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn latex() {
+        let latex = "\\documentclass[a4paper]{article}\n\
+            % A comment\n\
+            \\section*{Teh Introduction}\n\
+            As shown in \\cite[p.~5]{knuth}, $a + b$ costs 5\\% more.\\todo{fix this}\n\
+            \\begin{lstlisting}[language=Rust]\n\
+            \n\
+            fn main() {}\n\
+            \\end{lstlisting}\n\
+            \\textbf{Bold} text~here.\n";
+        let mut settings = LatexSettings::default();
+        settings
+            .commands
+            .insert("\\todo{}".into(), LatexAction::Ignore);
+        let annot = parse(latex, &settings);
+        assert_eq!(annot.len(), latex.len());
+        let text = annot.text_parts().map(|(_, t)| t).collect::<String>();
+        assert_eq!(
+            text,
+            "\n\nTeh Introduction\nAs shown in ,  costs 5 more.\n\nBold texthere.\n"
+        );
+        let json = serde_json::to_string(&annot).unwrap();
+        assert!(json.contains(r#"{"markup":"\\cite[p.~5]{knuth}","interpretAs":"Dummy"}"#));
+        assert!(json.contains(r#"{"markup":"\\%","interpretAs":"%"}"#));
+    }
+
+    #[test]
+    fn latex_unicode() {
+        let settings = LatexSettings::default();
+        for latex in [
+            "Größe $α$ und \\ä.",
+            "Price: $ and ä",
+            "$\\ä$ ü",
+            "$$ö$$ \\",
+        ] {
+            let annot = parse(latex, &settings);
+            assert_eq!(annot.len(), latex.len(), "{latex}");
+        }
+        let annot = parse("Größe $α$ ist ä.", &settings);
+        let text = annot.text_parts().map(|(_, t)| t).collect::<String>();
+        assert_eq!(text, "Größe  ist ä.");
+    }
+}
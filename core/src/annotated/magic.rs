@@ -0,0 +1,166 @@
+//! Magic comments that override the settings for a single document, e.g.
+//! `<!-- lt-language: de-DE -->` or `% lt-disable-rule: MORFOLOGIK_RULE_EN_US`,
+//! or suppress the matches of a line like `// lt-disable-next-line RULE_ID`.
+
+use std::ops::Range;
+
+use regex::Regex;
+
+use crate::api::Match;
+use crate::settings::Settings;
+use crate::source::SourceFile;
+
+const SUPPRESS: &str = r"\blt-disable-(next-)?line\b(?:[ \t]*:?[ \t]*([A-Z][A-Z0-9_]*(?:[ \t]*,[ \t]*[A-Z][A-Z0-9_]*)*))?";
+const MAGIC: &str = r"(?m)\blt-([a-z-]+)(?:[ \t]*:[ \t]*([\w.-]+(?:[ \t]*,[ \t]*[\w.-]+)*))?";
+
+/// Returns the settings with the overrides of the magic comments in the text.
+pub fn overrides(text: &str, settings: &Settings) -> Settings {
+    let mut settings = settings.clone();
+    for c in Regex::new(MAGIC).unwrap().captures_iter(text) {
+        let values = c.get(2).map_or(Vec::new(), |v| {
+            v.as_str()
+                .split(',')
+                .map(|v| v.trim().to_string())
+                .collect()
+        });
+        match &c[1] {
+            "language" => settings.static_language = values.last().cloned(),
+            "disable-rule" | "disable-rules" => settings.disabled_rules.extend(values),
+            "enable-rule" | "enable-rules" => settings.enabled_rules.extend(values),
+            "disable-category" | "disable-categories" => {
+                append(&mut settings.disabled_categories, &values)
+            }
+            "enable-category" | "enable-categories" => {
+                append(&mut settings.enabled_categories, &values)
+            }
+            "enable-picky" => settings.picky = true,
+            "disable-picky" => settings.picky = false,
+            _ => {}
+        }
+    }
+    settings
+}
+
+/// Matches on a line that are suppressed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Suppression {
+    /// Byte range of the line
+    pub range: Range<usize>,
+    /// Suppressed rules, all if empty
+    pub rules: Vec<String>,
+}
+
+impl Suppression {
+    pub fn suppresses(&self, m: &Match) -> bool {
+        m.range.start < self.range.end
+            && self.range.start < m.range.end
+            && (self.rules.is_empty() || self.rules.contains(&m.rule))
+    }
+}
+
+/// Returns the lines suppressed by `lt-disable-line` and `lt-disable-next-line`.
+pub fn suppressions(source: &SourceFile) -> Vec<Suppression> {
+    let lines = source.lines();
+    Regex::new(SUPPRESS)
+        .unwrap()
+        .captures_iter(source.text())
+        .filter_map(|c| {
+            let start = c.get(0).unwrap().start();
+            let line = lines.partition_point(|(_, end)| end.byte <= start);
+            let line = if c.get(1).is_some() { line + 1 } else { line };
+            let (start, end) = lines.get(line)?;
+            Some(Suppression {
+                range: start.byte..end.byte,
+                rules: c.get(2).map_or(Vec::new(), |v| {
+                    v.as_str()
+                        .split(',')
+                        .map(|v| v.trim().to_string())
+                        .collect()
+                }),
+            })
+        })
+        .collect()
+}
+
+/// Returns the start and end of a line comment for the language id
+pub fn comment(language_id: &str) -> Option<(&'static str, &'static str)> {
+    Some(match language_id {
+        "markdown" | "html" => ("<!--", " -->"),
+        "mdx" => ("{/*", " */}"),
+        "latex" | "tex" => ("%", ""),
+        "python" | "org" | "po" | "pot" | "gettext" | "git-commit" | "gitcommit" => ("#", ""),
+        "rust" | "c" | "cpp" | "javascript" | "javascriptreact" | "typescript"
+        | "typescriptreact" => ("//", ""),
+        _ => return None,
+    })
+}
+
+/// Appends the values to a comma separated list
+fn append(list: &mut String, values: &[String]) {
+    for value in values {
+        if !list.is_empty() {
+            list.push(',');
+        }
+        list.push_str(value);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn magic() {
+        let text = "<!-- lt-language: de-DE -->\n\
+            % lt-disable-rule: MORFOLOGIK_RULE_EN_US, WHITESPACE_RULE\n\
+            # lt-disable-category: TYPOS\n\
+            lt-enable-picky\n\
+            The lt-unknown here.\n";
+        let settings = overrides(text, &Settings::default());
+        assert_eq!(settings.static_language.as_deref(), Some("de-DE"));
+        assert_eq!(
+            settings.disabled_rules,
+            vec!["MORFOLOGIK_RULE_EN_US", "WHITESPACE_RULE"]
+        );
+        assert_eq!(settings.disabled_categories, "TYPOS");
+        assert!(settings.picky);
+    }
+
+    #[test]
+    fn suppress() {
+        let source = SourceFile::new(
+            "// lt-disable-next-line RULE_A, RULE_B\n\
+            Teh line.\n\
+            Teh other line. <!-- lt-disable-line -->\n"
+                .into(),
+        );
+        let suppressions = suppressions(&source);
+        assert_eq!(
+            suppressions,
+            vec![
+                Suppression {
+                    range: 39..49,
+                    rules: vec!["RULE_A".into(), "RULE_B".into()]
+                },
+                Suppression {
+                    range: 49..90,
+                    rules: Vec::new()
+                },
+            ]
+        );
+        let m = |range: Range<usize>, rule: &str| Match {
+            range,
+            title: String::new(),
+            message: String::new(),
+            replacements: Vec::new(),
+            category: String::new(),
+            rule: rule.into(),
+            severity: None,
+            details: Default::default(),
+        };
+        assert!(suppressions[0].suppresses(&m(39..42, "RULE_B")));
+        assert!(!suppressions[0].suppresses(&m(39..42, "RULE_C")));
+        assert!(!suppressions[0].suppresses(&m(49..52, "RULE_A")));
+        assert!(suppressions[1].suppresses(&m(49..52, "RULE_C")));
+    }
+}
@@ -0,0 +1,159 @@
+//! Annotations for Markdown documents.
+//!
+//! Front matter, code blocks, HTML, link destinations and the syntax of
+//! headings, lists and emphasis are markup. Links are only checked with their
+//! description.
+
+use std::ops::Range;
+
+use regex::Regex;
+
+use crate::settings::Settings;
+use crate::source::SourceFile;
+
+use super::{AnnotatedText, paragraph};
+
+pub fn annotate(
+    source: &SourceFile,
+    lines: Range<usize>,
+    _settings: &Settings,
+) -> anyhow::Result<(Range<usize>, AnnotatedText)> {
+    // Code blocks may span multiple paragraphs, so parse the whole document
+    let (range, _) = paragraph(source, lines)?;
+    Ok((range.clone(), parse(source.text()).slice(range)))
+}
+
+pub fn parse(md: &str) -> AnnotatedText {
+    let mut annot = blocks(md);
+    inline(&mut annot);
+    annot
+}
+
+/// Converts the block structure into markup, consecutive text lines are
+/// kept together in a single text part.
+pub fn blocks(md: &str) -> AnnotatedText {
+    let fence = Regex::new(r"^[ \t]*(`{3,}|~{3,})").unwrap();
+    let heading = Regex::new(r"^([ \t]*#{1,6}[ \t]+)(.*?)([ \t]+#+)?[ \t]*$").unwrap();
+    let underline = Regex::new(r"^[ \t]*(?:=+|-+)[ \t]*$").unwrap();
+    let thematic_break = Regex::new(r"^[ \t]*(?:(?:\*[ \t]*){3,}|(?:_[ \t]*){3,})$").unwrap();
+    let reference = Regex::new(r"^[ \t]*\[[^\]\n]+\]:[ \t]*\S").unwrap();
+    let comment_end = Regex::new("-->").unwrap();
+
+    let mut annot = AnnotatedText::new();
+    let mut text = String::new();
+    // End marker of the current code block, comment or front matter
+    let mut skip_until: Option<Regex> = None;
+    for (i, line) in md.split_inclusive('\n').enumerate() {
+        let content = line.trim_end_matches(['\r', '\n']);
+
+        if let Some(end) = &skip_until {
+            if end.is_match(content) {
+                skip_until = None;
+            }
+            annot.add_markup(line.to_string(), "\n".to_string());
+            continue;
+        }
+
+        if i == 0 && (content == "---" || content == "+++") {
+            skip_until = Some(Regex::new(&format!(r"^{}[ \t]*$", regex::escape(content))).unwrap());
+        } else if let Some(c) = fence.captures(content) {
+            let (marker, len) = (&c[1][..1], c[1].len());
+            skip_until = Some(
+                Regex::new(&format!(
+                    r"^[ \t]*{}{{{len},}}[ \t]*$",
+                    regex::escape(marker)
+                ))
+                .unwrap(),
+            );
+        } else if content.trim_start().starts_with("<!--") && !content.contains("-->") {
+            skip_until = Some(comment_end.clone());
+        } else if let Some(c) = heading.captures(content) {
+            flush(&mut annot, &mut text);
+            annot.add_markup(c[1].to_string(), String::new());
+            annot.add_text(c[2].to_string());
+            let rest = &line[c.get(2).unwrap().end()..];
+            annot.add_markup(rest.to_string(), "\n\n".to_string());
+            continue;
+        } else if !(underline.is_match(content)
+            || thematic_break.is_match(content)
+            || reference.is_match(content))
+        {
+            text.push_str(line);
+            continue;
+        }
+
+        flush(&mut annot, &mut text);
+        annot.add_markup(line.to_string(), "\n\n".to_string());
+    }
+    flush(&mut annot, &mut text);
+    annot
+}
+
+/// Converts the inline syntax of the text parts into markup.
+pub fn inline(annot: &mut AnnotatedText) {
+    // List markers and checkboxes (before the lines are split up)
+    annot.mark(
+        &Regex::new(r"(?m)^[ \t]*(?:[-+*]|\d+[.)])[ \t]+(?:\[[ xX]\][ \t]+)?").unwrap(),
+        "",
+    );
+    // Comments and code
+    annot.mark(&Regex::new(r"<!--[\s\S]*?-->").unwrap(), "");
+    annot.mark(&Regex::new(r"``[^`]+``|`[^`\n]+`").unwrap(), "");
+    // Images are skipped and links only checked with their description
+    annot.mark(&Regex::new(r"!\[[^\]\n]*\]\([^)\n]*\)").unwrap(), "");
+    let link = Regex::new(r"(\[)[^\]\n]*(\]\([^)\n]*\)|\]\[[^\]\n]*\])").unwrap();
+    annot.mark_with(
+        |text| {
+            link.captures_iter(text)
+                .flat_map(|c| [c.get(1).unwrap().range(), c.get(2).unwrap().range()])
+                .collect()
+        },
+        "",
+    );
+    // Autolinks and HTML tags
+    annot.mark(
+        &Regex::new(r"<[a-zA-Z][\w+.-]*:[^>\s]*>|</?[a-zA-Z][^>\n]*>").unwrap(),
+        "",
+    );
+    // Emphasis
+    annot.mark(
+        &Regex::new(r"\*{1,3}\b|\b\*{1,3}|\b_{1,3}|_{1,3}\b").unwrap(),
+        "",
+    );
+}
+
+fn flush(annot: &mut AnnotatedText, text: &mut String) {
+    if !text.is_empty() {
+        annot.add_text(std::mem::take(text));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn markdown() {
+        let md = "---\n\
+            title: Teh\n\
+            ---\n\
+            # Teh Title #\n\
+            \n\
+            Some *emphasis* and `code` with a [link](https://example.org) ![img](a.png).\n\
+            - [x] Done <b>item</b>\n\
+            \n\
+            ```rust\n\
+            \n\
+            fn main() {}\n\
+            ```\n\
+            [ref]: https://example.org\n\
+            Keep snake_case\n";
+        let annot = parse(md);
+        assert_eq!(annot.len(), md.len());
+        let text = annot.text_parts().map(|(_, t)| t).collect::<String>();
+        assert_eq!(
+            text,
+            "Teh Title\nSome emphasis and  with a link .\nDone item\n\nKeep snake_case\n"
+        );
+    }
+}
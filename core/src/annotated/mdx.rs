@@ -0,0 +1,115 @@
+//! Annotations for MDX documents.
+//!
+//! Extends the Markdown annotations with JSX elements, `import`/`export`
+//! statements and `{...}` expressions, which are all markup.
+
+use std::ops::Range;
+
+use regex::Regex;
+
+use crate::settings::Settings;
+use crate::source::SourceFile;
+
+use super::{AnnotatedText, markdown, paragraph};
+
+pub fn annotate(
+    source: &SourceFile,
+    lines: Range<usize>,
+    _settings: &Settings,
+) -> anyhow::Result<(Range<usize>, AnnotatedText)> {
+    // Code blocks and JSX may span multiple paragraphs, so parse the whole document
+    let (range, _) = paragraph(source, lines)?;
+    Ok((range.clone(), parse(source.text()).slice(range)))
+}
+
+pub fn parse(mdx: &str) -> AnnotatedText {
+    let mut annot = markdown::blocks(mdx);
+    // Statements continue until the next empty line
+    annot.mark(
+        &Regex::new(r"(?m)^(?:import|export)\b[^\n]*(?:\n[^\n]*\S[^\n]*)*").unwrap(),
+        "",
+    );
+    annot.mark_with(|text| find(text, b'<', b'>'), "");
+    annot.mark_with(|text| find(text, b'{', b'}'), "");
+    markdown::inline(&mut annot);
+    annot
+}
+
+/// Finds the JSX elements (`open` = `<`) or expressions (`open` = `{`)
+fn find(text: &str, open: u8, close: u8) -> Vec<Range<usize>> {
+    let bytes = text.as_bytes();
+    let mut ranges = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        let start = i;
+        i += 1;
+        if bytes[start] != open {
+            continue;
+        }
+        // Only tags, closing tags and fragments, not comparisons
+        if open == b'<'
+            && !bytes
+                .get(i)
+                .is_some_and(|c| c.is_ascii_alphabetic() || matches!(c, b'/' | b'>'))
+        {
+            continue;
+        }
+        if let Some(end) = closing(bytes, i, close) {
+            ranges.push(start..end + 1);
+            i = end + 1;
+        }
+    }
+    ranges
+}
+
+/// Returns the position of the closing character,
+/// skipping over strings and nested expressions
+fn closing(bytes: &[u8], start: usize, close: u8) -> Option<usize> {
+    let mut depth = 0;
+    let mut quote = None;
+    for (i, &c) in bytes.iter().enumerate().skip(start) {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None => match c {
+                b'"' | b'\'' | b'`' => quote = Some(c),
+                b'{' => depth += 1,
+                b'}' if depth > 0 => depth -= 1,
+                c if c == close && depth == 0 => return Some(i),
+                _ => {}
+            },
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn mdx() {
+        let mdx = "import { Callout } from './callout'\n\
+            export const meta = {\n  \
+              title: 'Teh',\n\
+            }\n\
+            \n\
+            # Hello {props.name}\n\
+            \n\
+            <Callout type=\"warning\" onClick={() => a > b}>\n\
+            Teh **bold** text for {1 < 2 ? 'a' : 'b'} people.\n\
+            </Callout>\n\
+            \n\
+            ```js\n\
+            const x = <Foo />;\n\
+            ```\n\
+            Is 1 < 2?\n";
+        let annot = parse(mdx);
+        assert_eq!(annot.len(), mdx.len());
+        let text = annot.text_parts().map(|(_, t)| t).collect::<String>();
+        assert_eq!(
+            text,
+            "\n\nHello \n\nTeh bold text for  people.\n\n\nIs 1 < 2?\n"
+        );
+    }
+}
@@ -0,0 +1,429 @@
+use std::borrow::Cow;
+use std::ops::Range;
+
+use anyhow::anyhow;
+use regex::Regex;
+use serde::Serialize;
+
+use crate::settings::Settings;
+use crate::source::SourceFile;
+
+pub mod code;
+pub mod git_commit;
+pub mod html;
+pub mod ipynb;
+pub mod latex;
+pub mod magic;
+pub mod markdown;
+pub mod mdx;
+pub mod org;
+pub mod patterns;
+pub mod plaintext;
+pub mod po;
+pub mod quotes;
+pub mod templates;
+pub mod tokens;
+
+/// Converts the paragraphs around the given lines into an annotated text,
+/// returns the byte range of the annotation in the source.
+pub type Annotator =
+    fn(&SourceFile, Range<usize>, &Settings) -> anyhow::Result<(Range<usize>, AnnotatedText)>;
+
+/// Annotators by the language ids of the client
+const ANNOTATORS: &[(&[&str], Annotator)] = &[
+    (&["plaintext", "text"], plaintext::annotate),
+    (&["html"], html::annotate),
+    (&["org"], org::annotate),
+    (&["ipynb", "jupyter"], ipynb::annotate),
+    (&["markdown"], markdown::annotate),
+    (&["latex", "tex"], latex::annotate),
+    (&["mdx"], mdx::annotate),
+    (&["po", "gettext"], po::annotate),
+    (&["pot"], po::annotate_template),
+    (&["git-commit", "gitcommit"], git_commit::annotate),
+];
+
+/// Language ids by file extension, for files that are not opened by the client
+const EXTENSIONS: &[(&[&str], &str)] = &[
+    (&["txt"], "plaintext"),
+    (&["md", "markdown"], "markdown"),
+    (&["mdx"], "mdx"),
+    (&["html", "htm"], "html"),
+    (&["org"], "org"),
+    (&["ipynb"], "ipynb"),
+    (&["tex"], "latex"),
+    (&["po"], "po"),
+    (&["pot"], "pot"),
+    (&["rs"], "rust"),
+    (&["py"], "python"),
+    (&["c", "h"], "c"),
+    (&["cc", "cpp", "cxx", "hpp"], "cpp"),
+    (&["js", "mjs"], "javascript"),
+    (&["jsx"], "javascriptreact"),
+    (&["ts"], "typescript"),
+    (&["tsx"], "typescriptreact"),
+];
+
+/// Returns the language id of a file extension
+pub fn language_id(extension: &str) -> Option<&'static str> {
+    EXTENSIONS
+        .iter()
+        .find(|(extensions, _)| extensions.contains(&extension))
+        .map(|(_, id)| *id)
+}
+
+/// Returns the annotator for the language id, defaults to plaintext
+pub fn annotator(language_id: &str) -> Annotator {
+    ANNOTATORS
+        .iter()
+        .find(|(ids, _)| ids.contains(&language_id))
+        .map_or(plaintext::annotate, |(_, annotator)| *annotator)
+}
+
+/// Annotates the changed lines of a document with the language id,
+/// which can be mapped to another annotator in the settings
+pub fn annotate(
+    language_id: &str,
+    source: &SourceFile,
+    lines: Range<usize>,
+    settings: &Settings,
+) -> anyhow::Result<(Range<usize>, AnnotatedText)> {
+    let language_id = settings
+        .annotators
+        .get(language_id)
+        .map_or(language_id, String::as_str);
+    #[cfg(feature = "code")]
+    if code::grammar(language_id).is_some() {
+        return code::annotate(source, lines, settings, language_id);
+    }
+    annotator(language_id)(source, lines, settings)
+}
+
+/// Extends the lines to the surrounding paragraph (separated by empty lines),
+/// returns its byte range and text.
+///
+/// Lines that only contain whitespace are not extended.
+pub fn paragraph(
+    source: &SourceFile,
+    mut lines: Range<usize>,
+) -> anyhow::Result<(Range<usize>, Cow<'_, str>)> {
+    let line = |i: usize| {
+        source
+            .line_range(i..i + 1)
+            .map(|(_, text)| text)
+            .ok_or_else(|| anyhow!("Invalid Line"))
+    };
+
+    // Skip whitespace
+    if let Some((range, text)) = source.line_range(lines.clone())
+        && text.trim().is_empty()
+    {
+        return Ok((range.0.byte..range.1.byte, text));
+    }
+
+    // Find start of paragraph
+    for i in (0..lines.start).rev() {
+        if line(i)?.trim().is_empty() {
+            break;
+        }
+        lines.start = i;
+    }
+    // Find end of paragraph
+    for i in lines.end..source.lines().len() {
+        if line(i)?.trim().is_empty() {
+            break;
+        }
+        lines.end = i + 1;
+    }
+
+    let (range, text) = source
+        .line_range(lines)
+        .ok_or_else(|| anyhow!("Invalid Line"))?;
+    Ok((range.0.byte..range.1.byte, text))
+}
+
+/// Represents a text with annotations for LanguageTool.
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct AnnotatedText {
+    annotation: Vec<Annotation>,
+}
+
+impl AnnotatedText {
+    pub fn new() -> Self {
+        Self {
+            annotation: Vec::new(),
+        }
+    }
+    pub fn add_text(&mut self, text: String) {
+        self.annotation.push(Annotation::Text { text });
+    }
+    pub fn add_markup(&mut self, markup: String, interpret_as: String) {
+        self.annotation.push(Annotation::Markup {
+            markup,
+            interpret_as,
+        });
+    }
+    pub fn parts(&self) -> impl Iterator<Item = &str> {
+        self.annotation.iter().map(|a| match a {
+            Annotation::Text { text } => text.as_str(),
+            Annotation::Markup { markup, .. } => markup.as_str(),
+        })
+    }
+    /// Returns the text parts and their offsets
+    pub fn text_parts(&self) -> impl Iterator<Item = (usize, &str)> {
+        self.annotation
+            .iter()
+            .scan(0, |offset, a| {
+                let start = *offset;
+                *offset += match a {
+                    Annotation::Text { text } => text.len(),
+                    Annotation::Markup { markup, .. } => markup.len(),
+                };
+                Some((start, a))
+            })
+            .filter_map(|(start, a)| match a {
+                Annotation::Text { text } => Some((start, text.as_str())),
+                Annotation::Markup { .. } => None,
+            })
+    }
+    /// Returns the part of the annotation in the byte range
+    pub fn slice(&self, range: Range<usize>) -> Self {
+        let mut slice = Self::new();
+        let mut offset = 0;
+        for annot in &self.annotation {
+            let start = offset;
+            offset += match annot {
+                Annotation::Text { text } => text.len(),
+                Annotation::Markup { markup, .. } => markup.len(),
+            };
+            if offset <= range.start || start >= range.end {
+                continue;
+            }
+            let part = range.start.saturating_sub(start)..range.end.min(offset) - start;
+            match annot {
+                Annotation::Text { text } => slice.add_text(text[part].to_string()),
+                // Only the start of the markup is interpreted
+                Annotation::Markup {
+                    markup,
+                    interpret_as,
+                } => slice.add_markup(
+                    markup[part.clone()].to_string(),
+                    if part.start == 0 {
+                        interpret_as.clone()
+                    } else {
+                        String::new()
+                    },
+                ),
+            }
+        }
+        slice
+    }
+    /// Turn the text matched by the pattern into markup
+    pub fn mark(&mut self, pattern: &Regex, interpret_as: &str) {
+        self.mark_with(
+            |text| pattern.find_iter(text).map(|m| m.range()).collect(),
+            interpret_as,
+        );
+    }
+    /// Turn the ranges returned by `find` for each text part into markup,
+    /// the ranges have to be sorted and must not overlap
+    pub fn mark_with(&mut self, find: impl Fn(&str) -> Vec<Range<usize>>, interpret_as: &str) {
+        for old in std::mem::take(&mut self.annotation) {
+            let Annotation::Text { text } = old else {
+                self.annotation.push(old);
+                continue;
+            };
+            let mut last = 0;
+            for m in find(&text).into_iter().filter(|m| !m.is_empty()) {
+                if m.start > last {
+                    self.add_text(text[last..m.start].to_string());
+                }
+                self.add_markup(text[m.clone()].to_string(), interpret_as.to_string());
+                last = m.end;
+            }
+            if last < text.len() {
+                self.add_text(text[last..].to_string());
+            }
+        }
+    }
+    pub fn optimize(&mut self) -> usize {
+        let mut offset = 0;
+        for old in std::mem::take(&mut self.annotation) {
+            match (old, self.annotation.last_mut()) {
+                // Remove whitespace from start
+                (Annotation::Text { text }, None) if text.trim().is_empty() => offset += text.len(),
+                (
+                    Annotation::Markup {
+                        markup,
+                        interpret_as,
+                    },
+                    None,
+                ) if interpret_as.trim().is_empty() => offset += markup.len(),
+                // Concatenate text
+                (Annotation::Text { text }, Some(Annotation::Text { text: last_text })) => {
+                    last_text.push_str(&text)
+                }
+                // Concatenate markup
+                (
+                    Annotation::Markup {
+                        markup,
+                        interpret_as,
+                    },
+                    Some(Annotation::Markup {
+                        markup: last_markup,
+                        interpret_as: last_interpret_as,
+                    }),
+                ) if interpret_as.is_empty() && last_interpret_as.is_empty() => {
+                    last_markup.push_str(&markup)
+                }
+                (old, _) => self.annotation.push(old),
+            }
+        }
+        // Remove whitespace from end
+        while let Some(annot) = self.annotation.last_mut() {
+            match annot {
+                Annotation::Text { text }
+                | Annotation::Markup {
+                    interpret_as: text, ..
+                } => {
+                    let trimmed = text.trim_end();
+                    if trimmed.is_empty() {
+                        self.annotation.pop();
+                    } else {
+                        text.truncate(trimmed.len());
+                        break;
+                    }
+                }
+            }
+        }
+        offset
+    }
+    /// Splits the annotation into parts of at most `max_size` bytes, returns
+    /// them with their offsets.
+    ///
+    /// The parts end at paragraphs, sentences or words if possible.
+    pub fn split(&self, max_size: usize) -> Vec<(usize, Self)> {
+        let text = self.parts().collect::<String>();
+        let sentence = Regex::new(r"[.!?]\s").unwrap();
+        let mut chunks = Vec::new();
+        let mut start = 0;
+        while text.len() - start > max_size {
+            let mut end = start + max_size.max(1);
+            while !text.is_char_boundary(end) {
+                end -= 1;
+            }
+            let window = &text[start..end];
+            let cut = window
+                .rfind("\n\n")
+                .map(|i| i + 2)
+                .or_else(|| sentence.find_iter(window).last().map(|m| m.end()))
+                .or_else(|| window.rfind(char::is_whitespace).map(|i| i + 1))
+                .filter(|cut| *cut > 0)
+                .unwrap_or(window.len());
+            chunks.push((start, self.slice(start..start + cut)));
+            start += cut;
+        }
+        chunks.push((start, self.slice(start..text.len())));
+        chunks
+    }
+    /// Returns the text with the markup replaced by spaces, keeping the
+    /// line breaks and byte offsets
+    pub fn plain(&self) -> String {
+        let mut plain = String::with_capacity(self.len());
+        for annot in &self.annotation {
+            match annot {
+                Annotation::Text { text } => plain.push_str(text),
+                Annotation::Markup { markup, .. } => {
+                    for c in markup.chars() {
+                        match c {
+                            '\n' => plain.push('\n'),
+                            c => plain.extend(std::iter::repeat_n(' ', c.len_utf8())),
+                        }
+                    }
+                }
+            }
+        }
+        plain
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    pub fn len(&self) -> usize {
+        self.annotation
+            .iter()
+            .map(|a| match a {
+                Annotation::Text { text } => text.len(),
+                Annotation::Markup { markup, .. } => markup.len(),
+            })
+            .sum()
+    }
+}
+
+/// Represents a range of text in the source document.
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all_fields = "camelCase", untagged)]
+enum Annotation {
+    Text {
+        text: String,
+    },
+    Markup {
+        markup: String,
+        #[serde(skip_serializing_if = "String::is_empty")]
+        interpret_as: String,
+    },
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn override_annotator() {
+        let source = SourceFile::new("# Teh `code`\n".to_string());
+        let mut settings = Settings::default();
+        let (_, annot) = annotate("quarto", &source, 0..1, &settings).unwrap();
+        assert_eq!(annot.text_parts().count(), 1);
+
+        settings
+            .annotators
+            .insert("quarto".to_string(), "markdown".to_string());
+        let (_, annot) = annotate("quarto", &source, 0..1, &settings).unwrap();
+        let text = annot.text_parts().map(|(_, t)| t).collect::<String>();
+        assert_eq!(text, "Teh ");
+    }
+
+    #[test]
+    fn split() {
+        let mut annot = AnnotatedText::new();
+        annot.add_text("First paragraph.\n\nA sentence. ".into());
+        annot.add_markup("**".into(), String::new());
+        annot.add_text("Another one without end".into());
+        let chunks = annot.split(24);
+        let parts = chunks
+            .iter()
+            .map(|(offset, chunk)| (*offset, chunk.parts().collect::<String>()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            parts,
+            [
+                (0, "First paragraph.\n\n".to_string()),
+                (18, "A sentence. ".to_string()),
+                (30, "**Another one without ".to_string()),
+                (52, "end".to_string()),
+            ]
+        );
+        assert_eq!(annot.split(100).len(), 1);
+    }
+
+    #[test]
+    fn plain() {
+        let source = SourceFile::new("Some *fancy*\n`cöde` text.\n".to_string());
+        let (range, annot) = annotate("markdown", &source, 0..1, &Settings::default()).unwrap();
+        let plain = annot.plain();
+        assert_eq!(plain.len(), range.len());
+        assert_eq!(
+            plain.split_whitespace().collect::<Vec<_>>(),
+            ["Some", "fancy", "text."]
+        );
+    }
+}
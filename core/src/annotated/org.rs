@@ -0,0 +1,132 @@
+//! Annotations for Org-mode documents.
+//!
+//! Source and example blocks, property drawers, comments and keywords are
+//! markup. Headlines are checked without their TODO keywords, priorities and
+//! tags, links only with their description.
+
+use std::ops::Range;
+
+use regex::Regex;
+
+use crate::settings::Settings;
+use crate::source::SourceFile;
+
+use super::{AnnotatedText, paragraph};
+
+/// Blocks whose content is not text
+const RAW_BLOCKS: &[&str] = &["src", "example", "export", "comment"];
+/// Keywords whose value is text
+const TEXT_KEYWORDS: &[&str] = &["title", "subtitle", "caption"];
+
+const TODO_KEYWORDS: &str = "TODO|DONE|NEXT|WAITING|HOLD|CANCELLED|CANCELED";
+
+pub fn annotate(
+    source: &SourceFile,
+    lines: Range<usize>,
+    _settings: &Settings,
+) -> anyhow::Result<(Range<usize>, AnnotatedText)> {
+    // Blocks and drawers may span multiple paragraphs, so parse the whole document
+    let (range, _) = paragraph(source, lines)?;
+    Ok((range.clone(), parse(source.text()).slice(range)))
+}
+
+pub fn parse(org: &str) -> AnnotatedText {
+    let block = Regex::new(r"(?i)^[ \t]*#\+begin_(\w+)").unwrap();
+    let keyword = Regex::new(r"(?i)^([ \t]*#\+(\w+):[ \t]*)(.*)$").unwrap();
+    let drawer = Regex::new(r"^[ \t]*:[\w-]+:[ \t]*$").unwrap();
+    let drawer_end = Regex::new(r"(?i)^[ \t]*:end:[ \t]*$").unwrap();
+    let planning = Regex::new(r"^[ \t]*(?:SCHEDULED|DEADLINE|CLOSED):").unwrap();
+    let headline = Regex::new(&format!(
+        r"^(\*+[ \t]+(?:(?:{TODO_KEYWORDS})[ \t]+)?(?:\[#\w\][ \t]+)?)(.*?)([ \t]+:[\w@#%:]+:)?[ \t]*$"
+    ))
+    .unwrap();
+
+    let mut annot = AnnotatedText::new();
+    // End marker of the current raw block or drawer
+    let mut skip_until: Option<Regex> = None;
+    for line in org.split_inclusive('\n') {
+        let content = line.trim_end_matches(['\r', '\n']);
+        let newline = &line[content.len()..];
+
+        if let Some(end) = &skip_until {
+            if end.is_match(content) {
+                skip_until = None;
+            }
+            annot.add_markup(line.to_string(), "\n".to_string());
+            continue;
+        }
+
+        if let Some(c) = block.captures(content) {
+            let name = c[1].to_lowercase();
+            if RAW_BLOCKS.contains(&name.as_str()) {
+                skip_until = Some(Regex::new(&format!(r"(?i)^[ \t]*#\+end_{name}\b")).unwrap());
+            }
+            annot.add_markup(line.to_string(), "\n\n".to_string());
+        } else if drawer.is_match(content) && !drawer_end.is_match(content) {
+            skip_until = Some(drawer_end.clone());
+            annot.add_markup(line.to_string(), "\n".to_string());
+        } else if let Some(c) = keyword.captures(content)
+            && TEXT_KEYWORDS.contains(&c[2].to_lowercase().as_str())
+        {
+            annot.add_markup(c[1].to_string(), String::new());
+            annot.add_text(format!("{}{newline}", &c[3]));
+        } else if content.trim_start().starts_with('#') || planning.is_match(content) {
+            // Comments, other keywords and planning information
+            annot.add_markup(line.to_string(), "\n".to_string());
+        } else if let Some(c) = headline.captures(content) {
+            annot.add_markup(c[1].to_string(), String::new());
+            annot.add_text(c[2].to_string());
+            let rest = &line[c.get(2).unwrap().end()..];
+            annot.add_markup(rest.to_string(), "\n\n".to_string());
+        } else {
+            annot.add_text(line.to_string());
+        }
+    }
+
+    // Links are only checked with their description
+    annot.mark(&Regex::new(r"\[\[[^\]\n]*\]\[").unwrap(), "");
+    annot.mark(&Regex::new(r"\[\[[^\]\n]*\]\]|\]\]").unwrap(), "");
+    // Verbatim and code
+    annot.mark(
+        &Regex::new(r"\B[=~][^\s=~](?:[^=~\n]*[^\s=~])?[=~]\B").unwrap(),
+        "",
+    );
+    // Timestamps and checkboxes
+    annot.mark(
+        &Regex::new(r"[<\[]\d{4}-\d{2}-\d{2}[^>\]\n]*[>\]]").unwrap(),
+        "",
+    );
+    annot.mark(
+        &Regex::new(r"(?m)^[ \t]*(?:[-+]|\d+[.)])[ \t]+\[[ xX-]\]").unwrap(),
+        "",
+    );
+    annot
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn org() {
+        let org = "#+TITLE: Teh Notes\n\
+            * TODO [#A] Write teh report :work:\n\
+            :PROPERTIES:\n\
+            :ID: abc-123\n\
+            :END:\n\
+            SCHEDULED: <2024-01-02 Tue>\n\
+            See [[https://example.org][the website]] and [[file:x.org]].\n\
+            #+BEGIN_SRC rust\n\
+            \n\
+            fn main() {}\n\
+            #+END_SRC\n\
+            - [X] Run =cargo test= now\n";
+        let annot = parse(org);
+        assert_eq!(annot.len(), org.len());
+        let text = annot.text_parts().map(|(_, t)| t).collect::<String>();
+        assert_eq!(
+            text,
+            "Teh Notes\nWrite teh reportSee the website and .\n Run  now\n"
+        );
+    }
+}
@@ -0,0 +1,41 @@
+//! Excludes text matched by user defined patterns, like ticket ids or
+//! inline math of formats without an annotator.
+
+use regex::Regex;
+use tracing::warn;
+
+use super::AnnotatedText;
+
+/// Turn the text matched by the regex patterns into markup.
+pub fn skip(annot: &mut AnnotatedText, patterns: &[String]) {
+    for pattern in patterns {
+        match Regex::new(pattern) {
+            Ok(regex) => annot.mark(&regex, ""),
+            Err(err) => warn!("Invalid ignore pattern {pattern:?}: {err}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn patterns() {
+        let mut annot = AnnotatedText::new();
+        annot.add_text("See JIRA-1234 for $a + b$ teh details.".into());
+        let len = annot.len();
+
+        skip(
+            &mut annot,
+            &[
+                r"\b[A-Z]+-\d+\b".to_string(),
+                r"\$[^$]+\$".to_string(),
+                "(invalid".to_string(),
+            ],
+        );
+        assert_eq!(annot.len(), len);
+        let text = annot.text_parts().map(|(_, t)| t).collect::<String>();
+        assert_eq!(text, "See  for  teh details.");
+    }
+}
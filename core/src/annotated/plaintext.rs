@@ -0,0 +1,20 @@
+use std::ops::Range;
+
+use crate::settings::Settings;
+use crate::source::SourceFile;
+
+use super::{AnnotatedText, paragraph};
+
+pub fn annotate(
+    source: &SourceFile,
+    lines: Range<usize>,
+    _settings: &Settings,
+) -> anyhow::Result<(Range<usize>, AnnotatedText)> {
+    let (range, text) = paragraph(source, lines)?;
+
+    let mut annot = AnnotatedText::new();
+    if !text.trim().is_empty() {
+        annot.add_text(text.into_owned());
+    }
+    Ok((range, annot))
+}
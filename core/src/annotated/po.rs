@@ -0,0 +1,147 @@
+//! Annotations for gettext catalogs (`.po` and `.pot`).
+//!
+//! Only the contents of the translated strings (`msgstr`) are checked, the
+//! source strings (`msgid`) optionally. Keywords, comments, quotes and the
+//! header entry are markup and escape sequences are interpreted as the
+//! characters they represent.
+
+use std::ops::Range;
+
+use regex::Regex;
+
+use crate::settings::Settings;
+use crate::source::SourceFile;
+
+use super::{AnnotatedText, paragraph};
+
+pub fn annotate(
+    source: &SourceFile,
+    lines: Range<usize>,
+    settings: &Settings,
+) -> anyhow::Result<(Range<usize>, AnnotatedText)> {
+    // The header entry is identified by its msgid, so parse the whole document
+    let (range, _) = paragraph(source, lines)?;
+    let annot = parse(source.text(), settings.gettext_msgid);
+    Ok((range.clone(), annot.slice(range)))
+}
+
+/// Templates have no translations, so the source strings are checked
+pub fn annotate_template(
+    source: &SourceFile,
+    lines: Range<usize>,
+    _settings: &Settings,
+) -> anyhow::Result<(Range<usize>, AnnotatedText)> {
+    let (range, _) = paragraph(source, lines)?;
+    Ok((range.clone(), parse(source.text(), true).slice(range)))
+}
+
+pub fn parse(po: &str, msgid: bool) -> AnnotatedText {
+    let keyword = Regex::new(
+        r#"^([ \t]*(msgctxt|msgid|msgid_plural|msgstr(?:\[\d+\])?)[ \t]+")(.*)("[ \t]*)$"#,
+    )
+    .unwrap();
+    let continuation = Regex::new(r#"^([ \t]*")(.*)("[ \t]*)$"#).unwrap();
+
+    let mut annot = AnnotatedText::new();
+    let mut field = String::new();
+    // The header entry has an empty msgid
+    let mut header = false;
+    for line in po.split_inclusive('\n') {
+        let content = line.trim_end_matches(['\r', '\n']);
+        let newline = &line[content.len()..];
+
+        let (prefix, string, suffix, interpret_as) = if let Some(c) = keyword.captures(content) {
+            field = c[2].to_string();
+            if field == "msgid" {
+                header = c[3].is_empty();
+            }
+            (c.get(1), c.get(3), c.get(4), "\n\n")
+        } else if let Some(c) = continuation.captures(content) {
+            if field == "msgid" && !c[2].is_empty() {
+                header = false;
+            }
+            (c.get(1), c.get(2), c.get(3), "")
+        } else {
+            // Comments, obsolete entries and empty lines
+            annot.add_markup(line.to_string(), String::new());
+            continue;
+        };
+        let (prefix, string, suffix) = (prefix.unwrap(), string.unwrap(), suffix.unwrap());
+
+        let checked = match field.as_str() {
+            "msgid" | "msgid_plural" => msgid,
+            "msgctxt" => false,
+            _ => !header,
+        };
+        if checked {
+            annot.add_markup(prefix.as_str().to_string(), interpret_as.to_string());
+            unescape(string.as_str(), &mut annot);
+            annot.add_markup(format!("{}{newline}", suffix.as_str()), String::new());
+        } else {
+            annot.add_markup(line.to_string(), String::new());
+        }
+    }
+    annot
+}
+
+/// Converts C-style escape sequences into markup
+fn unescape(string: &str, annot: &mut AnnotatedText) {
+    let mut last = 0;
+    let mut chars = string.char_indices();
+    while let Some((i, c)) = chars.next() {
+        if c != '\\' {
+            continue;
+        }
+        let Some((j, escaped)) = chars.next() else {
+            break;
+        };
+        if last < i {
+            annot.add_text(string[last..i].to_string());
+        }
+        let interpret_as = match escaped {
+            'n' => '\n',
+            't' => '\t',
+            'r' => '\r',
+            c => c,
+        };
+        let end = j + escaped.len_utf8();
+        annot.add_markup(string[i..end].to_string(), interpret_as.to_string());
+        last = end;
+    }
+    if last < string.len() {
+        annot.add_text(string[last..].to_string());
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn po() {
+        let po = "msgid \"\"\n\
+            msgstr \"\"\n\
+            \"Project-Id-Version: Teh Project\\n\"\n\
+            \n\
+            #: src/main.rs:10\n\
+            #, fuzzy\n\
+            msgctxt \"menu\"\n\
+            msgid \"Open teh file\"\n\
+            msgstr \"Öffne die \\\"Datei\\\"\"\n\
+            \n\
+            msgid \"\"\n\
+            \"Line one\\n\"\n\
+            msgstr \"\"\n\
+            \"Zeile \"\n\
+            \"eins\\n\"\n\
+            #~ msgid \"Obsolete\"\n";
+        let annot = parse(po, false);
+        assert_eq!(annot.len(), po.len());
+        let text = annot.text_parts().map(|(_, t)| t).collect::<String>();
+        assert_eq!(text, "Öffne die DateiZeile eins");
+
+        let annot = parse(po, true);
+        let text = annot.text_parts().map(|(_, t)| t).collect::<String>();
+        assert_eq!(text, "Open teh fileÖffne die DateiLine oneZeile eins");
+    }
+}
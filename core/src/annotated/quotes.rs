@@ -0,0 +1,73 @@
+//! Excludes quoted material, which is usually someone else's words.
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use super::AnnotatedText;
+
+/// Quoted text that is not checked
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct QuoteSettings {
+    /// Markdown blockquotes (`> ...`) and LaTeX quote environments
+    pub blockquotes: bool,
+    /// Quoted lines of email replies, including the "On ... wrote:" line
+    pub email_quotes: bool,
+    /// Text inside quotation marks
+    pub quotation_marks: bool,
+}
+
+const BLOCKQUOTES: &str = r"(?m)^[ \t]*>.*\n?|(?s)\\begin\{quote\}.*?\\end\{quote\}|\\begin\{quotation\}.*?\\end\{quotation\}";
+const EMAIL_QUOTES: &str =
+    r"(?m)^(?:On .+ wrote:|Am .+ schrieb .+:)[ \t]*\n(?:[ \t]*>.*\n?)+|^[ \t]*>.*\n?";
+const QUOTATION_MARKS: &str = r#""[^"]*"|“[^“”]*”|„[^„“”]*[“”]|«[^«»]*»|»[^«»]*«|‘[^‘’]*’"#;
+
+/// Turn the quoted parts of the text into markup.
+pub fn skip(annot: &mut AnnotatedText, settings: &QuoteSettings) {
+    if settings.blockquotes {
+        annot.mark(&Regex::new(BLOCKQUOTES).unwrap(), "\n");
+    }
+    if settings.email_quotes {
+        annot.mark(&Regex::new(EMAIL_QUOTES).unwrap(), "\n");
+    }
+    if settings.quotation_marks {
+        annot.mark(&Regex::new(QUOTATION_MARKS).unwrap(), "\"…\"");
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn text(annot: &AnnotatedText) -> String {
+        annot.text_parts().map(|(_, t)| t).collect()
+    }
+
+    #[test]
+    fn quotes() {
+        let mut annot = AnnotatedText::new();
+        annot.add_text(
+            "Thanks, see below.\n\
+            On Monday, Alice wrote:\n\
+            > Teh quote\n\
+            >> older\n\
+            He said „Das ist gut“ and left.\n"
+                .into(),
+        );
+        let len = annot.len();
+
+        skip(
+            &mut annot,
+            &QuoteSettings {
+                email_quotes: true,
+                quotation_marks: true,
+                ..Default::default()
+            },
+        );
+        assert_eq!(annot.len(), len);
+        assert_eq!(text(&annot), "Thanks, see below.\nHe said  and left.\n");
+        let (offset, part) = annot.text_parts().nth(2).unwrap();
+        assert_eq!(part, " and left.\n");
+        assert_eq!(offset, len - part.len());
+    }
+}
@@ -0,0 +1,78 @@
+//! Excludes the tags of template languages like Jinja, Liquid, Handlebars
+//! or ERB, which are not prose.
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use super::AnnotatedText;
+
+/// Template tags that are not checked
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct TemplateSettings {
+    /// Jinja, Liquid, Handlebars and Mustache tags (`{{ }}`, `{% %}`, `{# #}`)
+    pub jinja: bool,
+    /// ERB and EJS tags (`<% %>`)
+    pub erb: bool,
+    /// Dummy word the expressions are interpreted as (e.g. "Bob"),
+    /// otherwise they are skipped
+    pub interpret_as: String,
+}
+
+const JINJA_STATEMENTS: &str = r"\{%[\s\S]*?%\}|\{#[\s\S]*?#\}|\{\{[#/!>^][\s\S]*?\}\}\}?";
+const JINJA_EXPRESSIONS: &str = r"\{\{\{[\s\S]*?\}\}\}|\{\{[\s\S]*?\}\}";
+const ERB_EXPRESSIONS: &str = r"<%=[\s\S]*?%>";
+const ERB_STATEMENTS: &str = r"<%[\s\S]*?%>";
+
+/// Turn the template tags of the text into markup.
+///
+/// Statements and comments are skipped, expressions are interpreted as
+/// the configured dummy word.
+pub fn skip(annot: &mut AnnotatedText, settings: &TemplateSettings) {
+    if settings.jinja {
+        annot.mark(&Regex::new(JINJA_STATEMENTS).unwrap(), "");
+        annot.mark(
+            &Regex::new(JINJA_EXPRESSIONS).unwrap(),
+            &settings.interpret_as,
+        );
+    }
+    if settings.erb {
+        annot.mark(
+            &Regex::new(ERB_EXPRESSIONS).unwrap(),
+            &settings.interpret_as,
+        );
+        annot.mark(&Regex::new(ERB_STATEMENTS).unwrap(), "");
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn templates() {
+        let mut annot = AnnotatedText::new();
+        annot.add_text(
+            "{% if user %}Hello {{ user.name }}, you have {{{count}}} messages.{% endif %}\n\
+            {{#each items}}{{! comment }}<%= item %> was sold <% end %>\n"
+                .into(),
+        );
+        let len = annot.len();
+
+        skip(
+            &mut annot,
+            &TemplateSettings {
+                jinja: true,
+                erb: true,
+                interpret_as: "Bob".into(),
+            },
+        );
+        assert_eq!(annot.len(), len);
+        let text = annot.text_parts().map(|(_, t)| t).collect::<String>();
+        assert_eq!(text, "Hello , you have  messages.\n was sold \n");
+        let json = serde_json::to_string(&annot).unwrap();
+        assert!(json.contains(r#"{"markup":"{{ user.name }}","interpretAs":"Bob"}"#));
+        assert!(json.contains(r#"{"markup":"<%= item %>","interpretAs":"Bob"}"#));
+        assert!(json.contains(r#"{"markup":"{% endif %}"}"#));
+    }
+}
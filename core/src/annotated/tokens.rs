@@ -0,0 +1,139 @@
+//! Excludes technical tokens like URLs, email addresses, file paths,
+//! back-ticked code and identifiers, which are the most common false
+//! positives in plain text and code comments.
+
+use std::ops::Range;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use super::AnnotatedText;
+
+/// Technical tokens that are not checked
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(default)]
+pub struct TokenSettings {
+    pub urls: bool,
+    pub emails: bool,
+    /// Absolute, home and relative paths, and paths with a file extension
+    pub paths: bool,
+    /// Text in backticks
+    pub code_spans: bool,
+    /// CamelCase and snake_case identifiers, e.g. `getUserName` or
+    /// `max_file_size`
+    pub identifiers: bool,
+}
+
+impl Default for TokenSettings {
+    fn default() -> Self {
+        Self {
+            urls: true,
+            emails: true,
+            paths: true,
+            code_spans: true,
+            identifiers: true,
+        }
+    }
+}
+
+const URLS: &str = r#"\b(?:[a-zA-Z][a-zA-Z0-9+.-]*://|www\.)[^\s<>"'`]*[^\s<>"'`.,;:!?)\]]"#;
+const EMAILS: &str = r"\b[\w.+-]+@[\w-]+(?:\.[\w-]+)+\b";
+const PATHS: &str = r#"(?:^|[\s(\["'])((?:~|\.{1,2})?/[\w.-]+(?:/[\w.-]+)*|[\w.-]+(?:/[\w.-]+)+\.[a-zA-Z0-9]+|[a-zA-Z]:\\[\w.\\-]+)"#;
+const CODE_SPANS: &str = r"`[^`\n]+`";
+const IDENTIFIERS: &str = r"\b(?:[a-z][a-z0-9]*[A-Z]|[A-Z][a-z0-9]+[A-Z]|\w+_)\w*\b";
+
+/// Turn the technical tokens of the text into markup, interpreted as
+/// neutral placeholders so that the surrounding sentence stays intact.
+pub fn skip(annot: &mut AnnotatedText, settings: &TokenSettings) {
+    // URLs first, as they might contain emails and paths
+    if settings.urls {
+        annot.mark(&Regex::new(URLS).unwrap(), "https://example.com");
+    }
+    if settings.emails {
+        annot.mark(&Regex::new(EMAILS).unwrap(), "mail@example.com");
+    }
+    if settings.code_spans {
+        annot.mark(&Regex::new(CODE_SPANS).unwrap(), "code");
+    }
+    if settings.paths {
+        let regex = Regex::new(PATHS).unwrap();
+        annot.mark_with(|text| paths(&regex, text), "file");
+    }
+    // Last, as URLs and paths often contain identifiers
+    if settings.identifiers {
+        annot.mark(&Regex::new(IDENTIFIERS).unwrap(), "code");
+    }
+}
+
+/// Ranges of the paths without trailing punctuation
+fn paths(regex: &Regex, text: &str) -> Vec<Range<usize>> {
+    regex
+        .captures_iter(text)
+        .filter_map(|c| c.get(1))
+        .map(|m| {
+            let path = m.as_str().trim_end_matches(['.', '-']);
+            m.start()..m.start() + path.len()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn tokens() {
+        let mut annot = AnnotatedText::new();
+        annot.add_text(
+            "Visit https://exmaple.org/a_b?x=1. Mail to jdoe@exmaple.org, \
+            edit ~/.confg/app.toml or src/mian.rs, run `cargo tset` and/or stop.\n\
+            /usr/lib is fine."
+                .into(),
+        );
+        let len = annot.len();
+        skip(&mut annot, &TokenSettings::default());
+        assert_eq!(annot.len(), len);
+        let text = annot.text_parts().map(|(_, t)| t).collect::<String>();
+        assert_eq!(
+            text,
+            "Visit . Mail to , edit  or , run  and/or stop.\n is fine."
+        );
+
+        let mut annot = AnnotatedText::new();
+        annot.add_text("See https://exmaple.org".into());
+        skip(
+            &mut annot,
+            &TokenSettings {
+                urls: false,
+                ..Default::default()
+            },
+        );
+        let text = annot.text_parts().map(|(_, t)| t).collect::<String>();
+        assert_eq!(text, "See https://exmaple.org");
+    }
+
+    #[test]
+    fn identifiers() {
+        let mut annot = AnnotatedText::new();
+        annot.add_text(
+            "Call getUserName or HashMap with max_file_size, MAX_SIZE and __init__. \
+            The iOS app, Teh end."
+                .into(),
+        );
+        let len = annot.len();
+        skip(&mut annot, &TokenSettings::default());
+        assert_eq!(annot.len(), len);
+        let text = annot.text_parts().map(|(_, t)| t).collect::<String>();
+        assert_eq!(text, "Call  or  with ,  and . The  app, Teh end.");
+
+        let mut annot = AnnotatedText::new();
+        annot.add_text("Call getUserName".into());
+        let settings = TokenSettings {
+            identifiers: false,
+            ..Default::default()
+        };
+        skip(&mut annot, &settings);
+        let text = annot.text_parts().map(|(_, t)| t).collect::<String>();
+        assert_eq!(text, "Call getUserName");
+    }
+}
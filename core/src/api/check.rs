@@ -0,0 +1,460 @@
+use serde::{Deserialize, Serialize};
+use tracing::{debug, info};
+
+use crate::annotated::AnnotatedText;
+#[cfg(feature = "http")]
+use crate::api::{client, send_retry};
+use crate::settings::Settings;
+#[cfg(feature = "http")]
+use crate::util::language_matches;
+use crate::util::utf16_to_byte;
+
+use super::fetch::{Fetch, form_urlencode};
+#[cfg(feature = "http")]
+use super::stub::Stub;
+use super::{CheckResult, Match, Mode, RuleDetails};
+
+#[cfg(feature = "http")]
+pub async fn check(
+    text: AnnotatedText,
+    offset: usize,
+    settings: &Settings,
+    language: Option<String>,
+) -> anyhow::Result<CheckResult> {
+    if settings.offline_stub {
+        return check_with(&Stub, text, offset, settings, language).await;
+    }
+    chunked(text, offset, settings, |text, offset| {
+        check_request(text, offset, settings, language.as_deref())
+    })
+    .await
+}
+
+#[cfg(feature = "http")]
+async fn check_request(
+    text: AnnotatedText,
+    offset: usize,
+    settings: &Settings,
+    language: Option<&str>,
+) -> anyhow::Result<CheckResult> {
+    let data = serde_json::to_string(&text)?;
+    let params = CheckParams::new(&data, settings, language);
+
+    let url = settings.server.join("v2/check")?;
+    info!("url: {url}");
+    debug!("params: {params:?}");
+    let request = client(settings)?.post(url).form(&params);
+    let response = send_retry(request, settings.max_retries).await?;
+
+    let response: CheckResponse = response.json().await?;
+    Ok(response.into_result(&text, offset, settings.max_replacements))
+}
+
+/// Sends a minimal check request and returns the software of the server
+#[cfg(feature = "http")]
+pub async fn software(settings: &Settings) -> anyhow::Result<Software> {
+    if settings.offline_stub {
+        return Ok(Software {
+            name: super::stub::SOFTWARE.into(),
+            version: env!("CARGO_PKG_VERSION").into(),
+            ..Default::default()
+        });
+    }
+    let mut text = AnnotatedText::new();
+    text.add_text("Test.".into());
+    let data = serde_json::to_string(&text)?;
+    let params = CheckParams::new(&data, settings, Some("en-US"));
+
+    let url = settings.server.join("v2/check")?;
+    let request = client(settings)?.post(url).form(&params);
+    let response = send_retry(request, settings.max_retries).await?;
+    let response: CheckResponse = response.json().await?;
+    Ok(response.software)
+}
+
+/// Checks the text with the HTTP client of the host.
+pub async fn check_with(
+    fetch: &impl Fetch,
+    text: AnnotatedText,
+    offset: usize,
+    settings: &Settings,
+    language: Option<String>,
+) -> anyhow::Result<CheckResult> {
+    let language = language.as_deref();
+    chunked(text, offset, settings, |text, offset| async move {
+        let data = serde_json::to_string(&text)?;
+        let params = CheckParams::new(&data, settings, language);
+
+        let url = settings.server.join("v2/check")?;
+        info!("url: {url}");
+        debug!("params: {params:?}");
+        let body = fetch.post_form(url, form_urlencode(&params)?).await?;
+
+        let response: CheckResponse = serde_json::from_str(&body)?;
+        Ok(response.into_result(&text, offset, settings.max_replacements))
+    })
+    .await
+}
+
+/// Checks texts that exceed the size limit of the server in multiple
+/// requests and combines their matches.
+async fn chunked<F: Future<Output = anyhow::Result<CheckResult>>>(
+    text: AnnotatedText,
+    offset: usize,
+    settings: &Settings,
+    check: impl Fn(AnnotatedText, usize) -> F,
+) -> anyhow::Result<CheckResult> {
+    let max_size = settings.endpoint().max_size();
+    if text.len() <= max_size {
+        return check(text, offset).await;
+    }
+    let mut result: Option<CheckResult> = None;
+    for (start, chunk) in text.split(max_size) {
+        info!("Check chunk {} ({})", offset + start, chunk.len());
+        let chunk = check(chunk, offset + start).await?;
+        match &mut result {
+            Some(result) => {
+                result.matches.extend(chunk.matches);
+                result.confidence = result.confidence.min(chunk.confidence);
+                result.premium &= chunk.premium;
+            }
+            None => result = Some(chunk),
+        }
+    }
+    Ok(result.expect("at least one chunk"))
+}
+
+/// Checks the text with automatic language detection.
+///
+/// If the detection is not confident or detects a language that is not one
+/// of the configured candidates, the text is checked with each candidate and
+/// the most plausible result (with the fewest matches) is used.
+#[cfg(feature = "http")]
+pub async fn check_auto(
+    text: AnnotatedText,
+    offset: usize,
+    settings: &Settings,
+) -> anyhow::Result<CheckResult> {
+    let result = check(text.clone(), offset, settings, None).await?;
+    if settings.static_language.is_some()
+        || settings.languages.is_empty()
+        || (result.confidence >= settings.language_confidence
+            && settings
+                .languages
+                .iter()
+                .any(|l| language_matches(l, &result.language)))
+    {
+        return Ok(result);
+    }
+
+    info!(
+        "Uncertain language {} ({}), try {:?}",
+        result.language, result.confidence, settings.languages
+    );
+    let mut best: Option<CheckResult> = None;
+    for language in &settings.languages {
+        let candidate = check(text.clone(), offset, settings, Some(language.clone())).await?;
+        if best
+            .as_ref()
+            .is_none_or(|best| candidate.matches.len() < best.matches.len())
+        {
+            best = Some(candidate);
+        }
+    }
+    Ok(best.unwrap_or(result))
+}
+
+/// The response structure returned by the LanguageTool check API.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CheckResponse {
+    matches: Vec<CheckMatch>,
+    software: Software,
+    language: Language,
+}
+
+/// Information about the server software.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Software {
+    pub name: String,
+    pub version: String,
+    pub build_date: String,
+    /// The request was authenticated with a premium account
+    pub premium: bool,
+}
+
+impl CheckResponse {
+    fn into_result(
+        self,
+        text: &AnnotatedText,
+        offset: usize,
+        max_replacements: usize,
+    ) -> CheckResult {
+        let max_replacements = match max_replacements {
+            0 => usize::MAX,
+            max => max,
+        };
+        info!("Software {:?}", self.software);
+        let language = &self.language.code;
+        let matches = self
+            .matches
+            .into_iter()
+            .map(|m| Match {
+                // Java and JavaScript Strings are UTF-16, so we need to convert them to UTF-8.
+                range: offset + utf16_to_byte(text.parts().flat_map(|p| p.chars()), m.offset)
+                    ..offset
+                        + utf16_to_byte(text.parts().flat_map(|p| p.chars()), m.offset + m.length),
+                title: m.short_message,
+                message: m.message,
+                replacements: m
+                    .replacements
+                    .into_iter()
+                    .take(max_replacements)
+                    .map(|r| r.value)
+                    .collect(),
+                details: RuleDetails {
+                    description: m.rule.description,
+                    category_name: m.rule.category.name,
+                    issue_type: m.rule.issue_type,
+                    urls: m.rule.urls.into_iter().map(|u| u.value).collect(),
+                    page: Some(format!(
+                        "https://community.languagetool.org/rule/show/{}?lang={language}",
+                        m.rule.id
+                    )),
+                },
+                category: m.rule.category.id,
+                rule: m.rule.id,
+                severity: None,
+            })
+            .collect();
+        CheckResult {
+            matches,
+            language: self.language.code.clone(),
+            confidence: self.language.detected_language.confidence,
+            premium: self.software.premium,
+        }
+    }
+}
+
+/// The language used for checking.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Language {
+    /// Language code, e.g. "en-US"
+    code: String,
+    #[serde(default)]
+    detected_language: DetectedLanguage,
+}
+
+/// The result of the automatic language detection.
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct DetectedLanguage {
+    /// Between 0 and 1
+    confidence: f64,
+}
+
+/// Represents a single match (potential issue) found by LanguageTool.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CheckMatch {
+    /// The full message describing the issue.
+    message: String,
+    /// A short version of the message, if available.
+    short_message: String,
+    /// The rule that triggered this match.
+    rule: Rule,
+    /// Suggested replacements for the issue.
+    replacements: Vec<Replacement>,
+    /// The offset (in bytes) where the issue starts in the text.
+    offset: usize,
+    /// The length (in bytes) of the problematic text.
+    length: usize,
+}
+
+/// Information about the rule that triggered a match.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Rule {
+    id: String,
+    #[serde(default)]
+    description: String,
+    #[serde(default)]
+    issue_type: String,
+    category: Category,
+    /// Links to explanations of the rule
+    #[serde(default)]
+    urls: Vec<RuleUrl>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RuleUrl {
+    value: String,
+}
+
+/// Represents a category of rules in LanguageTool.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Category {
+    id: String,
+    #[serde(default)]
+    name: String,
+}
+
+/// Represents a suggested replacement for a detected issue.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Replacement {
+    value: String,
+}
+
+/// Parameters for the LanguageTool check API call.
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct CheckParams<'a> {
+    /// The annotated text to check.
+    data: &'a str,
+    /// The language code to use for checking (e.g., "en", "de", or "auto").
+    language: &'a str,
+    /// The username for authentication, if required.
+    #[serde(skip_serializing_if = "str::is_empty")]
+    username: &'a str,
+    /// The API key for authentication, if required.
+    #[serde(skip_serializing_if = "str::is_empty")]
+    api_key: &'a str,
+    /// The checking level ("picky" or "default").
+    level: &'a str,
+    /// The user's mother tongue, if specified.
+    #[serde(skip_serializing_if = "str::is_empty")]
+    mother_tongue: &'a str,
+    /// Comma-separated list of enabled categories.
+    #[serde(skip_serializing_if = "str::is_empty")]
+    enabled_categories: &'a str,
+    /// Comma-separated list of disabled categories.
+    #[serde(skip_serializing_if = "str::is_empty")]
+    disabled_categories: &'a str,
+    /// Comma-separated list of enabled rules.
+    #[serde(skip_serializing_if = "String::is_empty")]
+    enabled_rules: String,
+    /// Comma-separated list of disabled rules.
+    #[serde(skip_serializing_if = "String::is_empty")]
+    disabled_rules: String,
+    /// Only use the enabled rules and categories.
+    #[serde(skip_serializing_if = "is_false")]
+    enabled_only: bool,
+    /// Comma-separated list of preferred language variants.
+    #[serde(skip_serializing_if = "String::is_empty")]
+    preferred_variants: String,
+    /// Comma-separated list of named dictionaries of the user.
+    #[serde(skip_serializing_if = "String::is_empty")]
+    dicts: String,
+    /// The rules to use ("all", "textLevelOnly" or "allButTextLevelOnly").
+    #[serde(skip_serializing_if = "is_all")]
+    mode: Mode,
+}
+
+fn is_all(mode: &Mode) -> bool {
+    *mode == Mode::All
+}
+
+fn is_false(value: &bool) -> bool {
+    !value
+}
+
+impl<'a> CheckParams<'a> {
+    fn new(data: &'a str, settings: &'a Settings, language: Option<&'a str>) -> Self {
+        Self {
+            data,
+            language: language
+                .or(settings.static_language.as_deref())
+                .unwrap_or("auto"),
+            username: &settings.username,
+            api_key: &settings.api_key,
+            level: if settings.picky { "picky" } else { "default" },
+            mother_tongue: &settings.mother_tongue,
+            enabled_categories: &settings.enabled_categories,
+            disabled_categories: &settings.disabled_categories,
+            enabled_rules: settings.enabled_rules.join(","),
+            disabled_rules: settings.disabled_rules.join(","),
+            enabled_only: settings.enabled_only,
+            preferred_variants: settings
+                .language_variety
+                .values()
+                .map(String::as_str)
+                .collect::<Vec<_>>()
+                .join(","),
+            dicts: settings.dicts.join(","),
+            mode: settings.mode,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    struct MockFetch;
+
+    impl Fetch for MockFetch {
+        async fn post_form(&self, url: url::Url, body: String) -> anyhow::Result<String> {
+            assert_eq!(url.path(), "/v2/check");
+            assert!(body.contains("language=de-DE"));
+            Ok(r#"{
+                "software": {},
+                "language": { "code": "de-DE" },
+                "matches": [{
+                    "message": "Möglicher Tippfehler gefunden.",
+                    "shortMessage": "Tippfehler",
+                    "rule": {
+                        "id": "GERMAN_SPELLER_RULE",
+                        "description": "Möglicher Tippfehler",
+                        "issueType": "misspelling",
+                        "category": { "id": "TYPOS", "name": "Tippfehler" },
+                        "urls": [{ "value": "https://example.org/typos" }]
+                    },
+                    "replacements": [{ "value": "Haus" }],
+                    "offset": 4,
+                    "length": 4
+                }]
+            }"#
+            .into())
+        }
+    }
+
+    #[tokio::test]
+    async fn fetch() {
+        let mut text = AnnotatedText::new();
+        text.add_text("Ein Hsus".into());
+        let result = check_with(
+            &MockFetch,
+            text,
+            100,
+            &Settings::default(),
+            Some("de-DE".into()),
+        )
+        .await
+        .unwrap();
+        assert_eq!(result.language, "de-DE");
+        assert_eq!(result.matches[0].range, 104..108);
+        assert_eq!(result.matches[0].replacements, ["Haus"]);
+        let details = &result.matches[0].details;
+        assert_eq!(details.category_name, "Tippfehler");
+        assert_eq!(details.urls, ["https://example.org/typos"]);
+        assert_eq!(
+            details.page.as_deref(),
+            Some("https://community.languagetool.org/rule/show/GERMAN_SPELLER_RULE?lang=de-DE")
+        );
+    }
+
+    #[test]
+    fn mode() {
+        let mut settings = Settings::default();
+        let params = form_urlencode(&CheckParams::new("", &settings, None)).unwrap();
+        assert!(!params.contains("mode="));
+        settings.mode = Mode::TextLevelOnly;
+        let params = form_urlencode(&CheckParams::new("", &settings, None)).unwrap();
+        assert!(params.contains("mode=textLevelOnly"));
+    }
+}
@@ -0,0 +1,29 @@
+//! HTTP abstraction for targets without the native client (e.g. WASM).
+
+use anyhow::bail;
+use serde::Serialize;
+use url::Url;
+use url::form_urlencoded::Serializer;
+
+/// HTTP client of the host, e.g. `fetch` in the browser
+#[allow(async_fn_in_trait)]
+pub trait Fetch {
+    /// Send a form-encoded POST request and return the body of the response.
+    /// Fails if the response has an unsuccessful status.
+    async fn post_form(&self, url: Url, body: String) -> anyhow::Result<String>;
+}
+
+/// Encodes the fields of the struct as `application/x-www-form-urlencoded`
+pub fn form_urlencode(params: &impl Serialize) -> anyhow::Result<String> {
+    let serde_json::Value::Object(fields) = serde_json::to_value(params)? else {
+        bail!("Form parameters must be a struct");
+    };
+    let mut form = Serializer::new(String::new());
+    for (key, value) in fields {
+        match value {
+            serde_json::Value::String(value) => form.append_pair(&key, &value),
+            value => form.append_pair(&key, &value.to_string()),
+        };
+    }
+    Ok(form.finish())
+}
@@ -0,0 +1,78 @@
+use serde::{Deserialize, Serialize};
+
+use crate::settings::Settings;
+
+#[cfg(feature = "http")]
+use super::{client, send_retry};
+
+/// A language supported by the server.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Language {
+    pub name: String,
+    /// Short code, e.g. "en"
+    pub code: String,
+    /// Code with variant, e.g. "en-US"
+    pub long_code: String,
+}
+
+/// Fetch the languages supported by the server.
+#[cfg(feature = "http")]
+pub async fn list(settings: &Settings) -> anyhow::Result<Vec<Language>> {
+    if settings.offline_stub {
+        return Ok(super::stub::languages());
+    }
+    let url = settings.server.join("v2/languages")?;
+    let request = client(settings)?.get(url);
+    let response = send_retry(request, settings.max_retries).await?;
+    Ok(response.json().await?)
+}
+
+/// Returns the configured language codes that are not supported.
+pub fn invalid<'a>(settings: &'a Settings, languages: &[Language]) -> Vec<&'a str> {
+    let supported = |code: &str| {
+        code == "auto"
+            || languages.iter().any(|l| {
+                l.long_code.eq_ignore_ascii_case(code) || l.code.eq_ignore_ascii_case(code)
+            })
+    };
+    settings
+        .static_language
+        .iter()
+        .chain(Some(&settings.mother_tongue).filter(|m| !m.is_empty()))
+        .chain(settings.language_variety.values())
+        .chain(&settings.languages)
+        .map(String::as_str)
+        .filter(|code| !supported(code))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn validate() {
+        let language = |code: &str, long_code: &str| Language {
+            name: String::new(),
+            code: code.into(),
+            long_code: long_code.into(),
+        };
+        let languages = [
+            language("en", "en-US"),
+            language("en", "en-GB"),
+            language("de", "de-DE"),
+            language("pt", "pt-PT"),
+            language("ca", "ca-ES"),
+        ];
+        let mut settings = Settings {
+            static_language: Some("en-gb".into()),
+            mother_tongue: "de".into(),
+            languages: vec!["en-US".into(), "xx-YY".into()],
+            ..Default::default()
+        };
+        assert_eq!(invalid(&settings, &languages), ["xx-YY"]);
+        settings.static_language = Some("en-XX".into());
+        assert_eq!(invalid(&settings, &languages), ["en-XX", "xx-YY"]);
+    }
+}
@@ -0,0 +1,335 @@
+//! Bindings of the LanguageTool HTTP API: checking texts, listing the
+//! languages and rules, syncing the words of premium accounts, and querying
+//! synonyms and rephrasings.
+
+#[cfg(feature = "http")]
+use anyhow::anyhow;
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+
+use crate::source::SourceFile;
+
+#[cfg(feature = "http")]
+use crate::settings::Settings;
+#[cfg(feature = "http")]
+use std::sync::Mutex;
+#[cfg(feature = "http")]
+use std::time::Duration;
+#[cfg(feature = "http")]
+use tracing::{error, warn};
+
+mod check;
+pub use check::{Software, check_with};
+#[cfg(feature = "http")]
+pub use check::{check, check_auto, software};
+pub mod fetch;
+pub mod languages;
+#[cfg(feature = "http")]
+pub mod rephrase;
+pub mod rules;
+pub mod stub;
+pub mod synonyms;
+pub use synonyms::{SynonymApi, SynonymProvider};
+#[cfg(feature = "http")]
+pub mod words;
+
+/// The result of a check request.
+#[derive(Debug, Clone)]
+pub struct CheckResult {
+    pub matches: Vec<Match>,
+    /// Language used for checking (detected if not specified)
+    pub language: String,
+    /// Confidence of the language detection (between 0 and 1)
+    pub confidence: f64,
+    /// The server used the premium rules of the account
+    pub premium: bool,
+}
+
+/// Represents a match (potential issue) found by LanguageTool.
+#[derive(Debug, Clone)]
+pub struct Match {
+    pub range: Range<usize>,
+    pub title: String,
+    pub message: String,
+    pub replacements: Vec<String>,
+    pub category: String,
+    pub rule: String,
+    /// Overrides the severity derived from the category
+    pub severity: Option<Severity>,
+    pub details: RuleDetails,
+}
+
+/// Additional information about the rule of a match.
+#[derive(Debug, Clone, Default)]
+pub struct RuleDetails {
+    pub description: String,
+    /// Human readable name of the category
+    pub category_name: String,
+    /// Type of the issue, e.g. "misspelling" or "duplication"
+    pub issue_type: String,
+    /// Links to explanations of the rule
+    pub urls: Vec<String>,
+    /// Page of the rule on the LanguageTool community website
+    pub page: Option<String>,
+}
+
+impl Match {
+    /// Checks if the match refers to other occurrences in the text, like
+    /// repeated words or inconsistent spellings
+    pub fn has_occurrences(&self) -> bool {
+        matches!(
+            self.details.issue_type.as_str(),
+            "duplication" | "inconsistency"
+        ) || ["REPEAT", "COHERENCY", "CONSISTENCY"]
+            .iter()
+            .any(|k| self.rule.contains(k))
+    }
+
+    /// Returns the other occurrences of the same term, e.g. the same
+    /// repeated word or another spelling of the inconsistent one
+    pub fn occurrences<'a>(
+        &'a self,
+        matches: &'a [Match],
+        source: &'a SourceFile,
+    ) -> impl Iterator<Item = &'a Match> {
+        let term = |m: &Match| {
+            source
+                .get(m.range.clone())
+                .unwrap_or_default()
+                .to_lowercase()
+        };
+        let suggests =
+            |m: &Match, term: &str| m.replacements.iter().any(|r| r.to_lowercase() == term);
+        let own = term(self);
+        let candidates = if self.has_occurrences() { matches } else { &[] };
+        candidates.iter().filter(move |m| {
+            if m.rule != self.rule || m.range == self.range {
+                return false;
+            }
+            let other = term(m);
+            other == own || suggests(self, &other) || suggests(m, &own)
+        })
+    }
+
+    /// The severity of the match, derived from the category if not overridden
+    pub fn level(&self) -> Severity {
+        match (self.severity, self.category.as_str()) {
+            (Some(severity), _) => severity,
+            (None, "COLLOQUIALISMS" | "REDUNDANCY" | "STYLE" | "SYNONYMS") => Severity::Hint,
+            (None, "TYPOS") => Severity::Warning,
+            (None, _) => Severity::Information,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+    Information,
+    Hint,
+}
+
+/// Rules used by a check request
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum Mode {
+    #[default]
+    All,
+    /// Only rules that consider the whole text, e.g. repetitions
+    TextLevelOnly,
+    /// Only rules that are limited to single paragraphs
+    AllButTextLevelOnly,
+}
+
+/// Tags that change how the client renders a match
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Tag {
+    /// Faded out, e.g. redundant words
+    Unnecessary,
+    /// Struck through, e.g. outdated phrasing
+    Deprecated,
+}
+
+/// Default number of retries of rate limited or unavailable requests
+pub const RETRIES: u32 = 3;
+/// Delay of the first retry, doubled for each further attempt
+#[cfg(feature = "http")]
+const RETRY_DELAY: Duration = Duration::from_secs(1);
+/// Upper bound for the delay requested by the server
+#[cfg(feature = "http")]
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(60);
+
+/// Shared HTTP client, so that connections are reused between requests
+#[cfg(feature = "http")]
+static CLIENT: Mutex<Option<((f64, f64), reqwest::Client)>> = Mutex::new(None);
+
+/// Returns the shared HTTP client, it is rebuilt if the timeouts have changed.
+#[cfg(feature = "http")]
+pub fn client(settings: &Settings) -> anyhow::Result<reqwest::Client> {
+    let timeouts = (settings.connect_timeout, settings.request_timeout);
+    let mut shared = CLIENT.lock().unwrap();
+    if let Some((t, client)) = shared.as_ref()
+        && *t == timeouts
+    {
+        return Ok(client.clone());
+    }
+    let client = reqwest::Client::builder()
+        .connect_timeout(Duration::from_secs_f64(settings.connect_timeout.max(0.0)))
+        .timeout(Duration::from_secs_f64(settings.request_timeout.max(0.0)))
+        .pool_idle_timeout(Duration::from_secs(90))
+        .tcp_keepalive(Duration::from_secs(60))
+        .build()?;
+    *shared = Some((timeouts, client.clone()));
+    Ok(client)
+}
+
+/// Returns the shared HTTP client with the last used timeouts
+#[cfg(feature = "http")]
+fn shared_client() -> anyhow::Result<reqwest::Client> {
+    if let Some((_, client)) = CLIENT.lock().unwrap().as_ref() {
+        return Ok(client.clone());
+    }
+    client(&Settings::default())
+}
+
+/// Checks if the request failed because the server is unreachable.
+#[cfg(feature = "http")]
+pub fn is_offline(err: &anyhow::Error) -> bool {
+    err.downcast_ref::<reqwest::Error>()
+        .is_some_and(|e| e.is_connect() || e.is_timeout())
+}
+
+/// Checks if the server is unreachable or failed with a server error.
+#[cfg(feature = "http")]
+pub fn is_unavailable(err: &anyhow::Error) -> bool {
+    is_offline(err)
+        || err
+            .downcast_ref::<StatusError>()
+            .is_some_and(|e| e.status.is_server_error())
+}
+
+/// Unsuccessful response of the server
+#[cfg(feature = "http")]
+#[derive(Debug)]
+pub struct StatusError {
+    pub status: reqwest::StatusCode,
+    message: String,
+}
+
+#[cfg(feature = "http")]
+impl std::fmt::Display for StatusError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+#[cfg(feature = "http")]
+impl std::error::Error for StatusError {}
+
+/// Sends an idempotent request and retries it with exponential backoff if the
+/// server is rate limited or temporarily unavailable.
+#[cfg(feature = "http")]
+async fn send_retry(
+    request: reqwest::RequestBuilder,
+    retries: u32,
+) -> anyhow::Result<reqwest::Response> {
+    use reqwest::StatusCode;
+
+    let mut delay = RETRY_DELAY;
+    for attempt in 0.. {
+        let response = request
+            .try_clone()
+            .ok_or_else(|| anyhow!("Request can not be retried"))?
+            .send()
+            .await?;
+        let status = response.status();
+        if attempt >= retries
+            || !matches!(
+                status,
+                StatusCode::TOO_MANY_REQUESTS
+                    | StatusCode::BAD_GATEWAY
+                    | StatusCode::SERVICE_UNAVAILABLE
+            )
+        {
+            return handle_response_errors(response).await;
+        }
+        let wait = retry_after(&response).unwrap_or(delay);
+        warn!("Status {status}, retry in {wait:?}");
+        tokio::time::sleep(wait).await;
+        delay *= 2;
+    }
+    unreachable!()
+}
+
+/// Delay of the `Retry-After` header in seconds
+#[cfg(feature = "http")]
+fn retry_after(response: &reqwest::Response) -> Option<Duration> {
+    let value = response.headers().get(reqwest::header::RETRY_AFTER)?;
+    let seconds = value.to_str().ok()?.trim().parse().ok()?;
+    Some(Duration::from_secs(seconds).min(MAX_RETRY_DELAY))
+}
+
+#[cfg(feature = "http")]
+async fn handle_response_errors(response: reqwest::Response) -> anyhow::Result<reqwest::Response> {
+    if !response.status().is_success() {
+        error!("Response: {response:?}");
+        let status = response.status();
+        if status == reqwest::StatusCode::GATEWAY_TIMEOUT
+            || status == reqwest::StatusCode::SERVICE_UNAVAILABLE
+        {
+            let message = "Request to LanguageTool timed out. Please try again later.".into();
+            return Err(StatusError { status, message }.into());
+        }
+        let mut message = response
+            .text()
+            .await
+            .unwrap_or_else(|_| "Unknown Error.".to_string());
+        message.truncate(300);
+        let message = format!("Status: {status}\n{message}");
+        return Err(StatusError { status, message }.into());
+    }
+    Ok(response)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn occurrences() {
+        let source = SourceFile::new("Color is colour. It is is the the end, it is is.".into());
+        let m = |range: Range<usize>, rule: &str, replacement: &str| Match {
+            range,
+            title: String::new(),
+            message: String::new(),
+            replacements: vec![replacement.into()],
+            category: String::new(),
+            rule: rule.into(),
+            severity: None,
+            details: Default::default(),
+        };
+        let matches = [
+            m(0..5, "EN_WORD_COHERENCY", "Colour"),
+            m(9..15, "EN_WORD_COHERENCY", "color"),
+            m(20..25, "ENGLISH_WORD_REPEAT_RULE", "is"),
+            m(26..33, "ENGLISH_WORD_REPEAT_RULE", "the"),
+            m(42..47, "ENGLISH_WORD_REPEAT_RULE", "is"),
+            m(0..5, "MORFOLOGIK_RULE_EN_US", "Colon"),
+            m(9..15, "MORFOLOGIK_RULE_EN_US", "color"),
+        ];
+        let ranges = |m: &Match| {
+            m.occurrences(&matches, &source)
+                .map(|o| o.range.start)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(ranges(&matches[0]), [9]);
+        assert_eq!(ranges(&matches[1]), [0]);
+        assert_eq!(ranges(&matches[2]), [42]);
+        // Different repeated words are not linked
+        assert!(ranges(&matches[3]).is_empty());
+        assert!(ranges(&matches[5]).is_empty());
+    }
+}
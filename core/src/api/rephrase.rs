@@ -0,0 +1,52 @@
+//! Rephrasing of sentences with the paraphrase service of a premium account.
+
+use crate::settings::Settings;
+
+use anyhow::anyhow;
+use url::Url;
+
+use super::{client, send_retry};
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RephraseRequest<'a> {
+    text: &'a str,
+    language: &'a str,
+    username: &'a str,
+    api_key: &'a str,
+}
+
+#[derive(serde::Deserialize)]
+struct RephraseResponse {
+    rephrasings: Vec<String>,
+}
+
+/// Returns alternative formulations of the sentence
+pub async fn rephrase(
+    settings: &Settings,
+    sentence: &str,
+    language: &str,
+) -> anyhow::Result<Vec<String>> {
+    let Some(url) = &settings.rephrase_url else {
+        return Err(anyhow!("No rephrase service configured"));
+    };
+    if settings.username.is_empty() || settings.api_key.is_empty() {
+        return Err(anyhow!("Rephrasing is only supported for premium users"));
+    }
+
+    let request = client(settings)?
+        .post(Url::parse(url)?)
+        .form(&RephraseRequest {
+            text: sentence,
+            language,
+            username: &settings.username,
+            api_key: &settings.api_key,
+        });
+    let response = send_retry(request, settings.max_retries).await?;
+    let data: RephraseResponse = response.json().await?;
+    Ok(data
+        .rephrasings
+        .into_iter()
+        .filter(|r| r.trim() != sentence.trim())
+        .collect())
+}
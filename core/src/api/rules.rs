@@ -0,0 +1,34 @@
+use serde::Deserialize;
+
+#[cfg(feature = "http")]
+use crate::settings::Settings;
+
+#[cfg(feature = "http")]
+use super::{client, send_retry};
+
+/// A rule as listed by the LanguageTool configinfo API.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RuleInfo {
+    pub rule_id: String,
+    pub description: String,
+    #[serde(default)]
+    pub is_default_off: bool,
+    pub category_id: String,
+    pub category_name: String,
+}
+
+/// Fetch all rules of the given language.
+#[cfg(feature = "http")]
+pub async fn list(settings: &Settings, language: &str) -> anyhow::Result<Vec<RuleInfo>> {
+    let url = settings.server.join("v2/configinfo")?;
+    let request = client(settings)?.get(url).query(&[("language", language)]);
+    let response = send_retry(request, settings.max_retries).await?;
+
+    #[derive(Deserialize)]
+    struct ConfigInfoResponse {
+        rules: Vec<RuleInfo>,
+    }
+    let data: ConfigInfoResponse = response.json().await?;
+    Ok(data.rules)
+}
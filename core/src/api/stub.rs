@@ -0,0 +1,196 @@
+//! Deterministic stand-in for the LanguageTool server, e.g. for tests of the
+//! whole pipeline without network access and for offline demos.
+//!
+//! It answers check requests like the server with two fixed rules: doubled
+//! words and a short list of common misspellings.
+
+use anyhow::{anyhow, bail};
+use serde_json::{Value, json};
+use url::Url;
+use url::form_urlencoded;
+
+use super::fetch::Fetch;
+use super::languages::Language;
+
+/// Common misspellings and their corrections
+const MISSPELLINGS: &[(&str, &str)] = &[
+    ("teh", "the"),
+    ("tset", "test"),
+    ("wich", "which"),
+    ("recieve", "receive"),
+    ("seperate", "separate"),
+    ("langauge", "language"),
+];
+
+pub const SOFTWARE: &str = "LanguageTool Stub";
+
+/// Server stub that implements the check endpoint
+pub struct Stub;
+
+impl Fetch for Stub {
+    async fn post_form(&self, url: Url, body: String) -> anyhow::Result<String> {
+        if !url.path().ends_with("v2/check") {
+            bail!("Unsupported endpoint: {url}");
+        }
+        let param = |name: &str| {
+            form_urlencoded::parse(body.as_bytes())
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.into_owned())
+                .unwrap_or_default()
+        };
+        let data: Value = serde_json::from_str(&param("data"))?;
+        let annotation = data["annotation"]
+            .as_array()
+            .ok_or_else(|| anyhow!("Invalid data"))?;
+
+        let disabled_rules = param("disabledRules");
+        let disabled_categories = param("disabledCategories");
+        let enabled = |rule: &str, category: &str| {
+            !disabled_rules.split(',').any(|r| r == rule)
+                && !disabled_categories.split(',').any(|c| c == category)
+        };
+
+        let mut matches = Vec::new();
+        // The stub has no text level rules
+        if param("mode") != "textLevelOnly" {
+            let mut offset = 0;
+            for part in annotation {
+                if let Some(text) = part["text"].as_str() {
+                    matches.extend(check(text, offset).filter(|m| {
+                        enabled(
+                            m["rule"]["id"].as_str().unwrap_or_default(),
+                            m["rule"]["category"]["id"].as_str().unwrap_or_default(),
+                        )
+                    }));
+                }
+                let part = part["text"].as_str().or(part["markup"].as_str());
+                offset += part.map_or(0, |p| p.encode_utf16().count());
+            }
+        }
+
+        let language = match param("language").as_str() {
+            "auto" | "" => "en-US".to_string(),
+            language => language.to_string(),
+        };
+        let response = json!({
+            "software": { "name": SOFTWARE, "version": env!("CARGO_PKG_VERSION") },
+            "language": {
+                "code": language,
+                "detectedLanguage": { "confidence": 1.0 },
+            },
+            "matches": matches,
+        });
+        Ok(response.to_string())
+    }
+}
+
+/// Languages of the stub, including the default language varieties
+pub fn languages() -> Vec<Language> {
+    [
+        ("English (US)", "en", "en-US"),
+        ("English (GB)", "en", "en-GB"),
+        ("German (Germany)", "de", "de-DE"),
+        ("Portuguese (Portugal)", "pt", "pt-PT"),
+        ("Catalan", "ca", "ca-ES"),
+    ]
+    .into_iter()
+    .map(|(name, code, long_code)| Language {
+        name: name.into(),
+        code: code.into(),
+        long_code: long_code.into(),
+    })
+    .collect()
+}
+
+/// Matches of the text in the format of the server, with UTF-16 offsets
+fn check(text: &str, offset: usize) -> impl Iterator<Item = Value> {
+    let utf16 = |byte: usize| offset + text[..byte].encode_utf16().count();
+    let words = text
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(|w| (w.as_ptr() as usize - text.as_ptr() as usize, w))
+        .collect::<Vec<_>>();
+
+    let mut matches = Vec::new();
+    for (i, &(start, word)) in words.iter().enumerate() {
+        if let Some((_, correction)) = MISSPELLINGS
+            .iter()
+            .find(|(m, _)| m.eq_ignore_ascii_case(word))
+        {
+            matches.push(json!({
+                "message": "Possible spelling mistake found.",
+                "shortMessage": "Spelling mistake",
+                "offset": utf16(start),
+                "length": word.encode_utf16().count(),
+                "replacements": [{ "value": correction }],
+                "rule": {
+                    "id": "STUB_SPELLING",
+                    "description": "Possible spelling mistake",
+                    "issueType": "misspelling",
+                    "category": { "id": "TYPOS", "name": "Possible Typo" },
+                },
+            }));
+        }
+        // Only words separated by whitespace are repetitions
+        if let Some(&(prev_start, prev)) = i.checked_sub(1).map(|i| &words[i])
+            && prev.eq_ignore_ascii_case(word)
+            && text[prev_start + prev.len()..start].trim().is_empty()
+        {
+            let end = start + word.len();
+            matches.push(json!({
+                "message": "Possible typo: you repeated a word.",
+                "shortMessage": "Word repetition",
+                "offset": utf16(prev_start),
+                "length": text[prev_start..end].encode_utf16().count(),
+                "replacements": [{ "value": prev }],
+                "rule": {
+                    "id": "STUB_WORD_REPEAT",
+                    "description": "Word repetition",
+                    "issueType": "duplication",
+                    "category": { "id": "MISC", "name": "Miscellaneous" },
+                },
+            }));
+        }
+    }
+    matches.into_iter()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::annotated::AnnotatedText;
+    use crate::api::check_with;
+    use crate::settings::Settings;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn stub() {
+        let mut annot = AnnotatedText::new();
+        annot.add_markup("**".into(), String::new());
+        annot.add_text("Thé tset is is done.".into());
+        let settings = Settings::default();
+        let result = check_with(&Stub, annot.clone(), 10, &settings, None)
+            .await
+            .unwrap();
+        assert_eq!(result.language, "en-US");
+        let matches = result
+            .matches
+            .iter()
+            .map(|m| (m.rule.as_str(), m.range.clone(), m.replacements[0].as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            matches,
+            [
+                ("STUB_SPELLING", 17..21, "test"),
+                ("STUB_WORD_REPEAT", 22..27, "is")
+            ]
+        );
+
+        let settings = Settings {
+            disabled_rules: vec!["STUB_SPELLING".into()],
+            ..Default::default()
+        };
+        let result = check_with(&Stub, annot, 0, &settings, None).await.unwrap();
+        assert_eq!(result.matches.len(), 1);
+    }
+}
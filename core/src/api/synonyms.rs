@@ -0,0 +1,273 @@
+use std::ops::Range;
+
+#[cfg(feature = "http")]
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "http")]
+use url::Url;
+
+#[cfg(feature = "http")]
+use super::{RETRIES, send_retry, shared_client};
+use crate::util::{language_matches, sentence_span};
+
+/// Protocol of a synonym service
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SynonymApi {
+    /// Phrasal paraphraser, which receives the sentence and the index of the
+    /// selected phrase
+    Paraphraser,
+    /// Synonym service, which is queried with `<url>/<word>?before=..&after=..`
+    /// and returns synsets
+    Synsets,
+}
+
+/// Synonym service for the documents of a language
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct SynonymProvider {
+    /// Language tag, e.g. "de" for all German variants or "de-CH"
+    pub language: String,
+    pub api: SynonymApi,
+    pub url: String,
+}
+
+impl SynonymProvider {
+    pub fn new(language: &str, api: SynonymApi, url: &str) -> Self {
+        Self {
+            language: language.into(),
+            api,
+            url: url.into(),
+        }
+    }
+
+    /// Built-in providers of LanguageTool
+    pub fn defaults() -> Vec<Self> {
+        vec![
+            Self::new(
+                "en",
+                SynonymApi::Paraphraser,
+                "https://qb-grammar-en.languagetool.org/phrasal-paraphraser/subscribe/",
+            ),
+            Self::new(
+                "de",
+                SynonymApi::Synsets,
+                "https://synonyms.languagetool.org/synonyms/de/",
+            ),
+        ]
+    }
+
+    /// The first of the providers for the language
+    pub fn select<'a>(providers: &'a [Self], language: &str) -> Option<&'a Self> {
+        providers
+            .iter()
+            .find(|p| language_matches(&p.language, language))
+    }
+
+    /// Queries the synonyms of the selection in the sentence, see [`context`]
+    #[cfg(feature = "http")]
+    pub async fn query(&self, sentence: &str, selection: Range<usize>) -> Result<Vec<String>> {
+        let url = Url::parse(&self.url)?;
+        match self.api {
+            SynonymApi::Paraphraser => paraphrases(url, sentence, selection).await,
+            SynonymApi::Synsets => synsets(url, sentence, selection).await,
+        }
+    }
+}
+
+/// Returns the sentence around the byte range of the text, e.g. of a
+/// paragraph that is wrapped over multiple lines, with single spaces between
+/// the words and the range of the selection in it
+pub fn context(text: &str, selection: Range<usize>) -> (String, Range<usize>) {
+    let span = sentence_span(text, std::slice::from_ref(&selection));
+    let start = span.start.min(selection.start);
+    let end = span.end.max(selection.end);
+    let words = |range: Range<usize>| text[range].split_whitespace().collect::<Vec<_>>();
+
+    let before = words(start..selection.start).join(" ");
+    let word = text[selection.clone()].trim();
+    let after = words(selection.end..end).join(" ");
+
+    let mut sentence = before;
+    if !sentence.is_empty() {
+        sentence.push(' ');
+    }
+    let selection = sentence.len()..sentence.len() + word.len();
+    sentence.push_str(word);
+    if !after.is_empty() {
+        sentence.push(' ');
+        sentence.push_str(&after);
+    }
+    (sentence, selection)
+}
+
+#[cfg(feature = "http")]
+async fn paraphrases(url: Url, sentence: &str, selection: Range<usize>) -> Result<Vec<String>> {
+    let index = sentence[0..selection.start].split_whitespace().count();
+    let word = sentence[selection.clone()].trim();
+
+    let body = serde_json::json!({
+        "message": {
+            "indices": [index],
+            "mode": 0,
+            "phrases": [word],
+            "text": sentence,
+        },
+        "meta": {
+            "clientStatus": "string",
+            "product": "string",
+            "traceID": "string",
+            "userID": "string",
+        },
+        "response_queue": "string",
+    });
+
+    let request = shared_client()?
+        .post(url)
+        .header("Accept", "application/json")
+        .json(&body);
+    let response = send_retry(request, RETRIES).await?;
+
+    let data = response.json::<serde_json::Value>().await?;
+    let synonyms = || -> Option<Vec<String>> {
+        Some(
+            data.get("data")?
+                .get("suggestions")?
+                .as_object()?
+                .values()
+                .filter_map(|v| v.as_array())
+                .flat_map(|v| v.iter().filter_map(|v| v.as_str()))
+                .map(|v| v.to_string())
+                .collect::<Vec<String>>(),
+        )
+    }()
+    .ok_or_else(|| anyhow!("Invalid response"))?;
+
+    Ok(synonyms)
+}
+
+/// Url of the synsets of the word with the surrounding words as context
+#[cfg(feature = "http")]
+fn synsets_url(mut url: Url, word: &str, before: &str, after: &str) -> Result<Url> {
+    url.path_segments_mut()
+        .map_err(|_| anyhow!("Invalid url"))?
+        .pop_if_empty()
+        .push(word);
+    url.query_pairs_mut()
+        .append_pair("before", before)
+        .append_pair("after", after);
+    Ok(url)
+}
+
+#[cfg(feature = "http")]
+async fn synsets(url: Url, sentence: &str, selection: Range<usize>) -> Result<Vec<String>> {
+    let word = sentence[selection.clone()].trim();
+    if word.contains(char::is_whitespace) {
+        return Err(anyhow!("Word contains whitespace"));
+    }
+    let before = sentence[..selection.start]
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+    let after = sentence[selection.end..]
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    let url = synsets_url(url, word, &before, &after)?;
+    let request = shared_client()?
+        .get(url)
+        .header("Accept", "application/json");
+    let data: serde_json::Value = send_retry(request, RETRIES).await?.json().await?;
+
+    let synonyms = || -> Option<Vec<String>> {
+        Some(
+            data.get("synsets")?
+                .as_array()?
+                .iter()
+                .filter_map(|s| {
+                    Some(
+                        s.get("terms")?
+                            .as_array()?
+                            .iter()
+                            .filter_map(|t| t.get("term")?.as_str()),
+                    )
+                })
+                .flatten()
+                .map(|t| t.to_string())
+                .collect::<Vec<String>>(),
+        )
+    }()
+    .ok_or_else(|| anyhow!("Invalid response"))?;
+
+    Ok(synonyms)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn select() {
+        let mut providers = vec![SynonymProvider::new(
+            "de-CH",
+            SynonymApi::Synsets,
+            "http://localhost:8000/synonyms/",
+        )];
+        providers.extend(SynonymProvider::defaults());
+        let url = |language| SynonymProvider::select(&providers, language).map(|p| &p.url[..]);
+        assert_eq!(url("de-CH"), Some("http://localhost:8000/synonyms/"));
+        assert_eq!(url("de-DE"), Some(&providers[2].url[..]));
+        assert_eq!(url("en-GB"), Some(&providers[1].url[..]));
+        assert_eq!(url("fr"), None);
+    }
+
+    #[test]
+    fn context() {
+        let text = "First one. A wrapped\n  long sentence, or\nnot? Last.";
+        let (sentence, selection) = super::context(text, 23..27);
+        assert_eq!(sentence, "A wrapped long sentence, or not?");
+        assert_eq!(&sentence[selection], "long");
+
+        let (sentence, selection) = super::context(text, 0..6);
+        assert_eq!(sentence, "First one.");
+        assert_eq!(selection, 0..5);
+    }
+
+    #[cfg(feature = "http")]
+    #[test]
+    fn url() {
+        for base in ["http://localhost/de/", "http://localhost/de"] {
+            let url = synsets_url(base.parse().unwrap(), "Haus/Hof", "foo", "bar").unwrap();
+            assert_eq!(
+                url.as_str(),
+                "http://localhost/de/Haus%2FHof?before=foo&after=bar"
+            );
+        }
+    }
+
+    #[cfg(feature = "http")]
+    #[ignore]
+    #[tokio::test]
+    async fn en() {
+        let sentence = "This is a test sentence.";
+        let selection = 10..14;
+        let synonyms = SynonymProvider::defaults()[0]
+            .query(sentence, selection)
+            .await
+            .unwrap();
+        println!("{synonyms:?}");
+    }
+
+    #[cfg(feature = "http")]
+    #[ignore]
+    #[tokio::test]
+    async fn de() {
+        let sentence = "Dies ist ein Test Satz.";
+        let selection = 13..17;
+        let synonyms = SynonymProvider::defaults()[1]
+            .query(sentence, selection)
+            .await
+            .unwrap();
+        println!("{synonyms:?}");
+    }
+}
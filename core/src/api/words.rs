@@ -1,33 +1,49 @@
 use crate::settings::Settings;
 
 use anyhow::anyhow;
+use tracing::info;
 
-use super::handle_response_errors;
+use super::{client, handle_response_errors, send_retry};
 
-pub async fn get(settings: &Settings) -> anyhow::Result<Vec<String>> {
+/// Maximum number of words per request
+const PAGE_SIZE: usize = 1000;
+
+/// Fetches all words of the remote dictionary, page by page
+///
+/// The `dict` names a dictionary of the account, otherwise the words of the
+/// default dictionary are returned.
+pub async fn get(settings: &Settings, dict: Option<&str>) -> anyhow::Result<Vec<String>> {
     if settings.username.is_empty() || settings.api_key.is_empty() {
         return Err(anyhow!("Syncing words is only supported for premium users"));
     }
 
-    let url = settings.server.join("v2/words")?;
-    let client = reqwest::Client::new();
-    let response = client
-        .get(url)
-        .query(&[
-            ("username", settings.username.as_str()),
-            ("apiKey", settings.api_key.as_str()),
-            ("limit", "1000"),
-        ])
-        .send()
-        .await?;
-    let response = handle_response_errors(response).await?;
-
     #[derive(serde::Deserialize)]
     struct WordsResponse {
         words: Vec<String>,
     }
-    let data: WordsResponse = response.json().await?;
-    Ok(data.words)
+
+    let url = settings.server.join("v2/words")?;
+    let mut words = Vec::new();
+    loop {
+        let mut query = vec![
+            ("username", settings.username.clone()),
+            ("apiKey", settings.api_key.clone()),
+            ("offset", words.len().to_string()),
+            ("limit", PAGE_SIZE.to_string()),
+        ];
+        if let Some(dict) = dict {
+            query.push(("dicts", dict.to_string()));
+        }
+        let request = client(settings)?.get(url.clone()).query(&query);
+        let response = send_retry(request, settings.max_retries).await?;
+        let data: WordsResponse = response.json().await?;
+        let count = data.words.len();
+        words.extend(data.words);
+        info!("Fetched {} remote words", words.len());
+        if count < PAGE_SIZE {
+            return Ok(words);
+        }
+    }
 }
 
 #[derive(serde::Serialize)]
@@ -36,21 +52,25 @@ struct WordRequest<'a> {
     word: &'a str,
     username: &'a str,
     api_key: &'a str,
+    /// Named dictionary, the default dictionary if not specified
+    #[serde(skip_serializing_if = "Option::is_none")]
+    dict: Option<&'a str>,
 }
 
-pub async fn add(settings: &Settings, word: &str) -> anyhow::Result<bool> {
+/// Adds the word to the named or the default dictionary
+pub async fn add(settings: &Settings, dict: Option<&str>, word: &str) -> anyhow::Result<bool> {
     if settings.username.is_empty() || settings.api_key.is_empty() {
         return Err(anyhow!("Syncing words is only supported for premium users"));
     }
 
     let url = settings.server.join("v2/words/add")?;
-    let client = reqwest::Client::new();
-    let response = client
+    let response = client(settings)?
         .post(url)
         .form(&WordRequest {
             word: &word,
             username: &settings.username,
             api_key: &settings.api_key,
+            dict,
         })
         .send()
         .await?;
@@ -61,19 +81,20 @@ pub async fn add(settings: &Settings, word: &str) -> anyhow::Result<bool> {
     Ok(success)
 }
 
-pub async fn delete(settings: &Settings, word: &str) -> anyhow::Result<bool> {
+/// Deletes the word from the named or the default dictionary
+pub async fn delete(settings: &Settings, dict: Option<&str>, word: &str) -> anyhow::Result<bool> {
     if settings.username.is_empty() || settings.api_key.is_empty() {
         return Err(anyhow!("Syncing words is only supported for premium users"));
     }
 
     let url = settings.server.join("v2/words/delete")?;
-    let client = reqwest::Client::new();
-    let response = client
+    let response = client(settings)?
         .post(url)
         .form(&WordRequest {
             word: &word,
             username: &settings.username,
             api_key: &settings.api_key,
+            dict,
         })
         .send()
         .await?;
@@ -0,0 +1,400 @@
+//! Dictionaries of words that are not reported as typos.
+//!
+//! The dictionary file of a workspace contains one word per line, optionally
+//! followed by a language tag and a comment. Comments start with a `#` after
+//! whitespace, so words like `C#` are kept:
+//!
+//! ```text
+//! # Header comments are kept at the top
+//! LanguageTool
+//! Rechtschreibprüfung [de] # only for German texts
+//! C# # programming language
+//! languagetool/i # case-insensitive
+//! /Kubernetes(es)?/ # regex matching the whole word
+//! ```
+//!
+//! Normalizing the file sorts and deduplicates the entries, which keeps
+//! the diffs small and avoids merge conflicts.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::io::ErrorKind;
+use std::path::Path;
+
+use anyhow::Result;
+use regex::Regex;
+use tracing::warn;
+
+use crate::util::language_matches;
+
+/// Dictionary file in the project directory
+pub const FILE: &str = "dictionary.txt";
+
+/// Languages an entry is restricted to (`None` for all)
+type Languages = Option<HashSet<String>>;
+
+/// Words with optional language restrictions
+#[derive(Debug, Default)]
+pub struct Dictionary {
+    words: HashMap<String, Languages>,
+    /// Case-insensitive words (`word/i`) in lowercase
+    folded: HashMap<String, Languages>,
+    /// Patterns (`/regex/`) that match whole words
+    patterns: Vec<(Regex, Languages)>,
+}
+
+impl Dictionary {
+    /// Number of words and patterns
+    pub fn len(&self) -> usize {
+        self.words.len() + self.folded.len() + self.patterns.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn insert(&mut self, word: &str, language: Option<&str>) {
+        let languages = if is_pattern(word) {
+            let pattern = &word[1..word.len() - 1];
+            match Regex::new(&format!("^(?:{pattern})$")) {
+                Ok(regex) => {
+                    let i = match self
+                        .patterns
+                        .iter()
+                        .position(|(r, _)| r.as_str() == regex.as_str())
+                    {
+                        Some(i) => i,
+                        None => {
+                            self.patterns.push((regex, Some(HashSet::new())));
+                            self.patterns.len() - 1
+                        }
+                    };
+                    &mut self.patterns[i].1
+                }
+                Err(err) => {
+                    warn!("Invalid dictionary pattern {word:?}: {err}");
+                    return;
+                }
+            }
+        } else if is_folded(word) {
+            let word = &word[..word.len() - 2];
+            self.folded
+                .entry(word.to_lowercase())
+                .or_insert_with(|| Some(HashSet::new()))
+        } else {
+            self.words
+                .entry(word.to_string())
+                .or_insert_with(|| Some(HashSet::new()))
+        };
+        if let (Some(languages), Some(language)) = (languages.as_mut(), language) {
+            languages.insert(language.to_string());
+        } else {
+            // Unrestricted entries apply to all languages
+            *languages = None;
+        }
+    }
+
+    pub fn extend(&mut self, file: &DictionaryFile) {
+        for entry in &file.entries {
+            self.insert(&entry.word, entry.language.as_deref());
+        }
+    }
+
+    /// Checks if the word is part of the dictionary for the given language
+    /// (e.g. "de-DE" matches the tags "de" and "de-DE").
+    pub fn contains(&self, word: &str, language: &str) -> bool {
+        let applies = |languages: &Languages| match languages {
+            Some(languages) => languages.iter().any(|tag| language_matches(tag, language)),
+            None => true,
+        };
+        self.words.get(word).is_some_and(applies)
+            || (!self.folded.is_empty()
+                && self.folded.get(&word.to_lowercase()).is_some_and(applies))
+            || self
+                .patterns
+                .iter()
+                .any(|(regex, languages)| applies(languages) && regex.is_match(word))
+    }
+}
+
+/// Pattern entry (`/regex/`)
+fn is_pattern(word: &str) -> bool {
+    word.len() > 2 && word.starts_with('/') && word.ends_with('/')
+}
+
+/// Case-insensitive entry (`word/i`)
+fn is_folded(word: &str) -> bool {
+    word.len() > 2 && word.ends_with("/i")
+}
+
+/// Changes that synchronize a local with a remote dictionary, based on the
+/// snapshot of the last synchronization
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct SyncPlan {
+    /// Added locally, to be added remotely
+    pub push: Vec<String>,
+    /// Removed locally, to be deleted remotely
+    pub delete: Vec<String>,
+    /// Added remotely, to be added locally
+    pub pull: Vec<String>,
+    /// Deleted remotely, to be removed locally
+    pub remove: Vec<String>,
+    /// Words of both dictionaries after the synchronization
+    pub words: Vec<String>,
+}
+
+impl SyncPlan {
+    pub fn new(
+        local: &HashSet<String>,
+        snapshot: &HashSet<String>,
+        remote: &HashSet<String>,
+    ) -> Self {
+        fn sorted<'a>(words: impl Iterator<Item = &'a String>) -> Vec<String> {
+            let mut words = words.cloned().collect::<Vec<_>>();
+            words.sort();
+            words.dedup();
+            words
+        }
+        let push = sorted(local.difference(snapshot).filter(|w| !remote.contains(*w)));
+        let delete = sorted(snapshot.difference(local).filter(|w| remote.contains(*w)));
+        let pull = sorted(remote.difference(snapshot).filter(|w| !local.contains(*w)));
+        let remove = sorted(snapshot.difference(remote).filter(|w| local.contains(*w)));
+        let words = sorted(remote.iter().chain(&push).filter(|w| !delete.contains(w)));
+        Self {
+            push,
+            delete,
+            pull,
+            remove,
+            words,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    pub word: String,
+    pub language: Option<String>,
+    pub comment: Option<String>,
+}
+
+impl Entry {
+    /// The entry is a word and no pattern or case-insensitive word
+    pub fn is_plain(&self) -> bool {
+        !is_pattern(&self.word) && !is_folded(&self.word)
+    }
+
+    fn parse(line: &str) -> Option<Self> {
+        let comment_start = line
+            .match_indices('#')
+            .find(|(i, _)| line[..*i].ends_with(char::is_whitespace));
+        let (line, comment) = match comment_start {
+            Some((i, _)) => (&line[..i], Some(line[i + 1..].trim().to_string())),
+            None => (line, None),
+        };
+        let line = line.trim();
+        let (word, language) = match line.strip_suffix(']').and_then(|l| l.rsplit_once('[')) {
+            Some((word, language)) => (word.trim(), Some(language.trim().to_string())),
+            None => (line, None),
+        };
+        (!word.is_empty()).then(|| Self {
+            word: word.to_string(),
+            language: language.filter(|l| !l.is_empty()),
+            comment: comment.filter(|c| !c.is_empty()),
+        })
+    }
+}
+
+impl fmt::Display for Entry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.word)?;
+        if let Some(language) = &self.language {
+            write!(f, " [{language}]")?;
+        }
+        if let Some(comment) = &self.comment {
+            write!(f, " # {comment}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Git-friendly dictionary file
+#[derive(Debug, Default)]
+pub struct DictionaryFile {
+    /// Comment lines
+    pub header: Vec<String>,
+    pub entries: Vec<Entry>,
+}
+
+impl DictionaryFile {
+    pub fn parse(text: &str) -> Self {
+        let mut file = Self::default();
+        for line in text.lines().map(str::trim) {
+            if line.starts_with('#') {
+                file.header.push(line.to_string());
+            } else if let Some(entry) = Entry::parse(line) {
+                file.entries.push(entry);
+            }
+        }
+        file
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        match std::fs::read_to_string(path) {
+            Ok(text) => Ok(Self::parse(&text)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, self.to_string())?;
+        Ok(())
+    }
+
+    /// Adds a word for all languages, returns false if it already exists
+    pub fn insert(&mut self, word: &str) -> bool {
+        if self
+            .entries
+            .iter()
+            .any(|e| e.word == word && e.language.is_none())
+        {
+            return false;
+        }
+        self.entries.push(Entry {
+            word: word.to_string(),
+            language: None,
+            comment: None,
+        });
+        true
+    }
+
+    /// Removes the word for all languages, returns false if it is missing
+    pub fn remove(&mut self, word: &str) -> bool {
+        let len = self.entries.len();
+        self.entries.retain(|e| e.word != word);
+        self.entries.len() != len
+    }
+
+    /// Sort the entries and remove duplicates
+    pub fn normalize(&mut self) {
+        self.entries.sort_by(|a, b| {
+            (a.word.to_lowercase(), &a.word, &a.language).cmp(&(
+                b.word.to_lowercase(),
+                &b.word,
+                &b.language,
+            ))
+        });
+        self.entries.dedup_by(|next, prev| {
+            if next.word == prev.word && next.language == prev.language {
+                if prev.comment.is_none() {
+                    prev.comment = next.comment.take();
+                }
+                true
+            } else {
+                false
+            }
+        });
+    }
+}
+
+impl fmt::Display for DictionaryFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for line in &self.header {
+            writeln!(f, "{line}")?;
+        }
+        for entry in &self.entries {
+            writeln!(f, "{entry}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn sync() {
+        let set = |words: &[&str]| words.iter().map(|w| w.to_string()).collect::<HashSet<_>>();
+        let plan = SyncPlan::new(
+            &set(&["kept", "local-new", "remote-gone"]),
+            &set(&["kept", "local-gone", "remote-gone"]),
+            &set(&["kept", "local-gone", "remote-new"]),
+        );
+        assert_eq!(plan.push, ["local-new"]);
+        assert_eq!(plan.delete, ["local-gone"]);
+        assert_eq!(plan.pull, ["remote-new"]);
+        assert_eq!(plan.remove, ["remote-gone"]);
+        assert_eq!(plan.words, ["kept", "local-new", "remote-new"]);
+
+        // Only plain words are synchronized
+        let file = DictionaryFile::parse("word\nWord/i\n/Pat+ern/\n/i\n");
+        let plain = file.entries.iter().map(Entry::is_plain);
+        assert_eq!(plain.collect::<Vec<_>>(), [true, false, false, true]);
+    }
+
+    #[test]
+    fn normalize() {
+        let mut file = DictionaryFile::parse(
+            "# Team dictionary\n\
+            zeta\n\
+            Alpha [de] # German\n\
+            \n\
+            alpha\n\
+            # Products\n\
+            zeta # duplicate\n\
+            Alpha [de]\n",
+        );
+        file.normalize();
+        assert_eq!(
+            file.to_string(),
+            "# Team dictionary\n\
+            # Products\n\
+            Alpha [de] # German\n\
+            alpha\n\
+            zeta # duplicate\n"
+        );
+
+        let mut dict = Dictionary::default();
+        dict.extend(&file);
+        assert!(dict.contains("alpha", "en-US"));
+        assert!(dict.contains("Alpha", "de-DE"));
+        assert!(!dict.contains("Alpha", "en-US"));
+        assert!(!dict.contains("Alpha", "dex"));
+        dict.insert("Alpha", None);
+        assert!(dict.contains("Alpha", "en-US"));
+
+        dict.insert("LanguageTool/i", None);
+        dict.insert("/Kubernetes(es)?/", Some("en"));
+        dict.insert("/(/", None);
+        assert!(dict.contains("languagetool", "de-DE"));
+        assert!(dict.contains("LANGUAGETOOL", "en-US"));
+        assert!(dict.contains("Kuberneteses", "en-US"));
+        assert!(!dict.contains("Kubernetesx", "en-US"));
+        assert!(!dict.contains("Kubernetes", "de-DE"));
+
+        assert!(file.insert("Alpha"));
+        assert!(!file.insert("alpha"));
+        assert!(file.to_string().ends_with("zeta # duplicate\nAlpha\n"));
+        assert!(file.remove("Alpha"));
+        assert!(!file.remove("Alpha"));
+        assert!(file.to_string().ends_with("alpha\nzeta # duplicate\n"));
+    }
+
+    #[test]
+    fn hash_in_words() {
+        let text = "# Header\nC#\nF# [en] # language\n";
+        let file = DictionaryFile::parse(text);
+        let words = file.entries.iter().map(|e| e.word.as_str());
+        assert_eq!(words.collect::<Vec<_>>(), ["C#", "F#"]);
+        assert_eq!(file.entries[1].comment.as_deref(), Some("language"));
+        assert_eq!(file.to_string(), text);
+
+        let mut file = DictionaryFile::default();
+        assert!(file.insert("C#"));
+        let file = DictionaryFile::parse(&file.to_string());
+        assert_eq!(file.entries[0].word, "C#");
+    }
+}
@@ -0,0 +1,155 @@
+//! False positives that are ignored permanently.
+//!
+//! The matches are identified by their rule, the matched text and a hash of
+//! the sentence they occur in, so they stay ignored after unrelated edits or rewrapping, but
+//! are reported again if the sentence itself changes.
+
+use std::io::ErrorKind;
+use std::ops::Range;
+use std::path::Path;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::api::Match;
+
+/// False positives file in the project directory
+pub const FILE: &str = "false-positives.json";
+
+#[derive(Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FalsePositives {
+    pub entries: Vec<FalsePositive>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FalsePositive {
+    pub rule: String,
+    /// Hash of the sentence containing the match
+    pub context: String,
+    /// Matched text, other matches of the rule in the sentence are still
+    /// reported
+    #[serde(default)]
+    pub text: String,
+}
+
+impl FalsePositive {
+    /// Creates the entry of a match in the given text
+    pub fn new(m: &Match, text: &str) -> Self {
+        Self {
+            rule: m.rule.clone(),
+            context: hash(sentence(text, &m.range)),
+            text: text.get(m.range.clone()).unwrap_or_default().to_string(),
+        }
+    }
+}
+
+impl FalsePositives {
+    pub fn load(path: &Path) -> Result<Self> {
+        match std::fs::read_to_string(path) {
+            Ok(data) => Ok(serde_json::from_str(&data)?),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut data = serde_json::to_string_pretty(self)?;
+        data.push('\n');
+        std::fs::write(path, data)?;
+        Ok(())
+    }
+
+    /// Adds the match, returns false if it is already ignored
+    pub fn insert(&mut self, m: &Match, text: &str) -> bool {
+        let entry = FalsePositive::new(m, text);
+        if self
+            .entries
+            .iter()
+            .any(|e| e.rule == entry.rule && e.context == entry.context && e.text == entry.text)
+        {
+            return false;
+        }
+        self.entries.push(entry);
+        true
+    }
+
+    /// Checks if the match in the given text has been ignored
+    pub fn contains(&self, m: &Match, text: &str) -> bool {
+        if !self.entries.iter().any(|e| e.rule == m.rule) {
+            return false;
+        }
+        let context = hash(sentence(text, &m.range));
+        let matched = text.get(m.range.clone()).unwrap_or_default();
+        self.entries
+            .iter()
+            .any(|e| e.rule == m.rule && e.context == context && e.text == matched)
+    }
+}
+
+/// Returns the sentence that contains the range
+fn sentence<'a>(text: &'a str, range: &Range<usize>) -> &'a str {
+    let (Some(before), Some(after)) = (text.get(..range.start), text.get(range.end..)) else {
+        return "";
+    };
+    let start = before
+        .rfind(['.', '!', '?'])
+        .map_or(0, |i| i + 1)
+        .max(before.rfind("\n\n").map_or(0, |i| i + 2));
+    let end = after
+        .find(['.', '!', '?'])
+        .map_or(after.len(), |i| i + 1)
+        .min(after.find("\n\n").unwrap_or(after.len()));
+    &text[start..range.end + end]
+}
+
+/// Stable FNV-1a hash of the text, ignoring differences in whitespace
+fn hash(text: &str) -> String {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for (i, word) in text.split_whitespace().enumerate() {
+        let separator = if i > 0 { " " } else { "" };
+        for b in separator.bytes().chain(word.bytes()) {
+            hash ^= b as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+    }
+    format!("{hash:016x}")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn ignore() {
+        let m = |range: Range<usize>| Match {
+            range,
+            title: String::new(),
+            message: String::new(),
+            replacements: Vec::new(),
+            category: "TYPOS".into(),
+            rule: "SPELLER".into(),
+            severity: None,
+            details: Default::default(),
+        };
+        let text = "First sentence. Teh second\nsentence! Third.";
+        let mut ignored = FalsePositives::default();
+        assert!(ignored.insert(&m(16..19), text));
+        assert!(!ignored.insert(&m(16..19), text));
+        assert_eq!(ignored.entries[0].text, "Teh");
+
+        // Unrelated edits and rewrapping
+        let text = "The first sentence. Teh second sentence! Third.";
+        assert!(ignored.contains(&m(20..23), text));
+        // Other match of the rule in the same sentence
+        assert!(!ignored.contains(&m(24..30), text));
+        assert!(ignored.insert(&m(24..30), text));
+        assert!(ignored.contains(&m(24..30), text));
+        // Changed sentence
+        let text = "First sentence. Teh third sentence! Third.";
+        assert!(!ignored.contains(&m(16..19), text));
+    }
+}
@@ -0,0 +1,54 @@
+//! Checking pipeline of the LanguageTool language server.
+//!
+//! Documents are stored as [`source::SourceFile`]s, split into paragraphs and
+//! converted into [`annotated::AnnotatedText`] by the annotator of their
+//! language id. The annotations are checked by the LanguageTool [`api`] and
+//! the [`local`] rules, configured by [`settings::Settings`].
+//!
+//! The default `http` feature provides the native HTTP client. Without it,
+//! the crate compiles to WASM and the host sends the requests by implementing
+//! [`api::fetch::Fetch`]. The default `code` feature extracts the comments of
+//! source code with the native tree-sitter grammars.
+//!
+//! Besides the language server, other tools (e.g. linters or documentation
+//! generators) can use the crate to check texts. The requests are sent by a
+//! [`api::fetch::Fetch`] implementation, here the offline stub, with the
+//! `http` feature [`api::check_auto`] uses the native client instead:
+//!
+//! ```
+//! # #[tokio::main(flavor = "current_thread")]
+//! # async fn main() -> anyhow::Result<()> {
+//! use languagetool_lsp_core::annotated::{self, AnnotatedText};
+//! use languagetool_lsp_core::api::{check_with, stub::Stub};
+//! use languagetool_lsp_core::settings::Settings;
+//! use languagetool_lsp_core::source::SourceFile;
+//!
+//! let settings = Settings::default();
+//!
+//! // Plain text
+//! let mut text = AnnotatedText::new();
+//! text.add_text("This is a tset.".into());
+//! let result = check_with(&Stub, text, 0, &settings, None).await?;
+//! assert_eq!(result.matches[0].replacements, ["test"]);
+//!
+//! // Markdown, with the byte offsets of the matches in the source
+//! let source = SourceFile::new("# Title\n\nSome *markdown* teh text.\n".into());
+//! let lines = 0..source.lines().len();
+//! let (range, text) = annotated::annotate("markdown", &source, lines, &settings)?;
+//! let result = check_with(&Stub, text, range.start, &settings, None).await?;
+//! for m in result.matches {
+//!     println!("{:?}: {} {:?}", m.range, m.message, m.replacements);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+pub mod annotated;
+pub mod api;
+pub mod dictionary;
+pub mod false_positives;
+pub mod local;
+mod rope;
+pub mod settings;
+pub mod source;
+pub mod util;
@@ -0,0 +1,142 @@
+//! Compact set of correctly spelled words built from word lists.
+//!
+//! Hunspell dictionaries (`.dic`) and frequency lists (one word per line,
+//! optionally followed by a count) are stored in a Bloom filter. It is used
+//! to drop spelling matches of known words and to highlight unknown words
+//! locally while the full check is still pending.
+
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::path::Path;
+
+use anyhow::Result;
+use regex::Regex;
+
+use crate::api::{Match, Severity};
+
+pub const RULE: &str = "UNKNOWN_WORD";
+
+/// Probability that an unknown word is considered to be known
+const FALSE_POSITIVE_RATE: f64 = 0.001;
+
+#[derive(Debug)]
+pub struct KnownWords {
+    bits: Vec<u64>,
+    hashes: u32,
+    len: usize,
+}
+
+impl KnownWords {
+    pub fn new(capacity: usize) -> Self {
+        let capacity = capacity.max(1) as f64;
+        let ln2 = std::f64::consts::LN_2;
+        let bits = (-capacity * FALSE_POSITIVE_RATE.ln() / (ln2 * ln2)).ceil() as usize;
+        Self {
+            bits: vec![0; bits.div_ceil(64)],
+            hashes: ((bits as f64 / capacity) * ln2).round().max(1.0) as u32,
+            len: 0,
+        }
+    }
+
+    /// Load the words of the given Hunspell or frequency lists
+    pub fn load(paths: &[impl AsRef<Path>]) -> Result<Self> {
+        let mut texts = Vec::new();
+        for path in paths {
+            texts.push(std::fs::read_to_string(path)?);
+        }
+        let words = || texts.iter().flat_map(|t| t.lines().filter_map(parse_line));
+        let mut known = Self::new(words().count());
+        for word in words() {
+            known.insert(word);
+        }
+        Ok(known)
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn insert(&mut self, word: &str) {
+        for bit in self.bit_indices(word).collect::<Vec<_>>() {
+            self.bits[bit / 64] |= 1 << (bit % 64);
+        }
+        self.len += 1;
+    }
+
+    /// Checks if the word is (probably) known, allowing capitalized words
+    /// at the beginning of sentences.
+    pub fn contains(&self, word: &str) -> bool {
+        let contains = |word: &str| {
+            self.bit_indices(word)
+                .all(|bit| self.bits[bit / 64] & (1 << (bit % 64)) != 0)
+        };
+        contains(word) || (word.starts_with(char::is_uppercase) && contains(&word.to_lowercase()))
+    }
+
+    /// Double hashing to derive the bit positions
+    fn bit_indices(&self, word: &str) -> impl Iterator<Item = usize> {
+        let mut hasher = DefaultHasher::new();
+        word.hash(&mut hasher);
+        let hash = hasher.finish();
+        let (h1, h2) = (hash & 0xffff_ffff, (hash >> 32) | 1);
+        let bits = self.bits.len() as u64 * 64;
+        (0..self.hashes as u64).map(move |i| (h1.wrapping_add(i.wrapping_mul(h2)) % bits) as usize)
+    }
+
+    /// Report words that are not part of the set
+    pub fn check(&self, text: &str, offset: usize) -> Vec<Match> {
+        let words = Regex::new(r"\p{L}+(?:['’]\p{L}+)*").unwrap();
+        words
+            .find_iter(text)
+            .filter(|w| w.as_str().chars().count() > 1)
+            // Skip acronyms
+            .filter(|w| !w.as_str().chars().all(char::is_uppercase))
+            .filter(|w| !self.contains(w.as_str()))
+            .map(|w| Match {
+                range: offset + w.start()..offset + w.end(),
+                title: "Unknown word".to_string(),
+                message: format!("{:?} is not part of the word lists.", w.as_str()),
+                replacements: Vec::new(),
+                category: "LOCAL_SPELLING".to_string(),
+                rule: RULE.to_string(),
+                severity: Some(Severity::Hint),
+                details: Default::default(),
+            })
+            .collect()
+    }
+}
+
+/// Parses "word/FLAGS" (Hunspell) or "word count" (frequency lists)
+fn parse_line(line: &str) -> Option<&str> {
+    let word = line.split_whitespace().next()?;
+    let word = word.split('/').next()?;
+    // Hunspell dictionaries start with the number of words
+    (!word.is_empty() && !word.starts_with('#') && !word.chars().all(|c| c.is_ascii_digit()))
+        .then_some(word)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn known_words() {
+        let dic = "3\nhello/S\nworld\nthe 1000\n";
+        let mut known = KnownWords::new(3);
+        for word in dic.lines().filter_map(parse_line) {
+            known.insert(word);
+        }
+        assert_eq!(known.len(), 3);
+        assert!(known.contains("hello"));
+        assert!(known.contains("The"));
+        assert!(!known.contains("wrold"));
+        assert!(!known.contains("3"));
+
+        let matches = known.check("Hello wrold, the NASA world.", 10);
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].range, 16..21);
+    }
+}
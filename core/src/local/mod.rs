@@ -0,0 +1,34 @@
+//! Checks that run locally without LanguageTool.
+
+use crate::annotated::AnnotatedText;
+use crate::api::Match;
+use crate::settings::Settings;
+
+pub mod known_words;
+pub mod rules;
+pub mod style;
+pub mod terminology;
+
+/// Run the local checks on the text parts of the annotation.
+pub fn check(
+    annot: &AnnotatedText,
+    offset: usize,
+    settings: &Settings,
+    language_id: &str,
+) -> Vec<Match> {
+    let terminology = terminology::Checker::new(&settings.terminology);
+    let rules = rules::Checker::new(&settings.custom_rules, language_id);
+    // The server detects the language later, so rely on the configured one
+    let german = settings
+        .static_language
+        .as_deref()
+        .is_some_and(|l| l.starts_with("de"));
+
+    let mut matches = Vec::new();
+    for (start, text) in annot.text_parts() {
+        matches.extend(terminology.check(text, offset + start));
+        matches.extend(rules.check(text, offset + start));
+        matches.extend(style::check(&settings.style, german, text, offset + start));
+    }
+    matches
+}
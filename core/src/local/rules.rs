@@ -0,0 +1,120 @@
+//! User-defined regex rules for style conventions LanguageTool does not cover.
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::api::{Match, Severity};
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct CustomRule {
+    pub id: String,
+    /// Regular expression that triggers the rule
+    pub pattern: String,
+    pub message: String,
+    pub severity: Severity,
+    /// Replacement template, capture groups are referenced with `$1` or `$name`
+    pub replacement: Option<String>,
+    /// Language identifiers the rule applies to (all if empty)
+    pub languages: Vec<String>,
+}
+
+impl Default for CustomRule {
+    fn default() -> Self {
+        Self {
+            id: "CUSTOM".to_string(),
+            pattern: String::new(),
+            message: String::new(),
+            severity: Severity::Information,
+            replacement: None,
+            languages: Vec::new(),
+        }
+    }
+}
+
+pub struct Checker<'a> {
+    rules: Vec<(Regex, &'a CustomRule)>,
+}
+
+impl<'a> Checker<'a> {
+    /// Compile the rules that apply to the given language identifier.
+    pub fn new(rules: &'a [CustomRule], language_id: &str) -> Self {
+        let rules = rules
+            .iter()
+            .filter(|r| !r.pattern.is_empty())
+            .filter(|r| r.languages.is_empty() || r.languages.iter().any(|l| l == language_id))
+            .filter_map(|r| match Regex::new(&r.pattern) {
+                Ok(regex) => Some((regex, r)),
+                Err(err) => {
+                    warn!("Invalid pattern for {}: {err}", r.id);
+                    None
+                }
+            })
+            .collect();
+        Self { rules }
+    }
+
+    pub fn check(&self, text: &str, offset: usize) -> Vec<Match> {
+        let mut matches = Vec::new();
+        for (regex, rule) in &self.rules {
+            for captures in regex.captures_iter(text) {
+                let found = captures.get(0).unwrap();
+                if found.is_empty() {
+                    continue;
+                }
+                let replacements = rule
+                    .replacement
+                    .iter()
+                    .map(|template| {
+                        let mut replacement = String::new();
+                        captures.expand(template, &mut replacement);
+                        replacement
+                    })
+                    .collect();
+                matches.push(Match {
+                    range: offset + found.start()..offset + found.end(),
+                    title: rule.id.clone(),
+                    message: rule.message.clone(),
+                    replacements,
+                    category: "CUSTOM".to_string(),
+                    rule: rule.id.clone(),
+                    severity: Some(rule.severity),
+                    details: Default::default(),
+                });
+            }
+        }
+        matches
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn custom_rules() {
+        let rules = [
+            CustomRule {
+                id: "E_G".into(),
+                pattern: r"\b[Ee]\.g\.(\s)".into(),
+                message: "Use a comma after e.g.".into(),
+                replacement: Some("e.g.,$1".into()),
+                ..Default::default()
+            },
+            CustomRule {
+                id: "TEX_ONLY".into(),
+                pattern: "foo".into(),
+                languages: vec!["latex".into()],
+                ..Default::default()
+            },
+        ];
+        let text = "Fruits, e.g. apples and foo.";
+        let matches = Checker::new(&rules, "markdown").check(text, 0);
+        assert_eq!(matches.len(), 1);
+        assert_eq!(&text[matches[0].range.clone()], "e.g. ");
+        assert_eq!(matches[0].replacements, ["e.g., "]);
+
+        assert_eq!(Checker::new(&rules, "latex").check(text, 0).len(), 2);
+    }
+}
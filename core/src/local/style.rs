@@ -0,0 +1,258 @@
+//! Simple style checks for long sentences, adverbs and passive voice.
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::api::{Match, Severity};
+use crate::util::sentences;
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct StyleSettings {
+    /// Report sentences with more words (0 to disable)
+    pub max_sentence_words: usize,
+    /// Report the adverbs of sentences with more adverbs (0 to disable, English only)
+    pub max_adverbs: usize,
+    /// Report simple passive voice constructions
+    pub passive_voice: bool,
+}
+
+/// Words ending in "-ly" that are usually not adverbs
+const NOT_ADVERBS: &[&str] = &[
+    "apply",
+    "belly",
+    "bully",
+    "butterfly",
+    "daily",
+    "early",
+    "family",
+    "fly",
+    "holy",
+    "italy",
+    "jelly",
+    "july",
+    "lily",
+    "monthly",
+    "only",
+    "rely",
+    "reply",
+    "supply",
+    "ugly",
+    "weekly",
+    "yearly",
+];
+
+const IRREGULAR_PARTICIPLES: &[&str] = &[
+    "been",
+    "begun",
+    "bought",
+    "brought",
+    "built",
+    "chosen",
+    "done",
+    "drawn",
+    "driven",
+    "eaten",
+    "fallen",
+    "found",
+    "forgotten",
+    "given",
+    "gone",
+    "held",
+    "hidden",
+    "kept",
+    "known",
+    "laid",
+    "left",
+    "lost",
+    "made",
+    "meant",
+    "paid",
+    "put",
+    "read",
+    "run",
+    "said",
+    "seen",
+    "sent",
+    "set",
+    "shown",
+    "sold",
+    "spoken",
+    "stolen",
+    "taken",
+    "taught",
+    "thought",
+    "thrown",
+    "told",
+    "understood",
+    "won",
+    "worn",
+    "written",
+];
+
+pub fn check(settings: &StyleSettings, german: bool, text: &str, offset: usize) -> Vec<Match> {
+    let words = Regex::new(r"\w+").unwrap();
+
+    let mut matches = Vec::new();
+    let mut push = |range: std::ops::Range<usize>, rule: &str, title: &str, message: String| {
+        matches.push(Match {
+            range: offset + range.start..offset + range.end,
+            title: title.to_string(),
+            message,
+            replacements: Vec::new(),
+            category: "LOCAL_STYLE".to_string(),
+            rule: rule.to_string(),
+            severity: Some(Severity::Hint),
+            details: Default::default(),
+        })
+    };
+
+    for sentence in sentences(text) {
+        let found = words
+            .find_iter(&text[sentence.clone()])
+            .map(|w| {
+                (
+                    sentence.start + w.start()..sentence.start + w.end(),
+                    w.as_str(),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        if settings.max_sentence_words > 0 && found.len() > settings.max_sentence_words {
+            push(
+                sentence.clone(),
+                "SENTENCE_LENGTH",
+                "Long sentence",
+                format!(
+                    "This sentence has {} words, consider splitting it (max {}).",
+                    found.len(),
+                    settings.max_sentence_words
+                ),
+            );
+        }
+
+        if settings.max_adverbs > 0 && !german {
+            let adverbs = found
+                .iter()
+                .filter(|(_, w)| is_adverb(w))
+                .collect::<Vec<_>>();
+            if adverbs.len() > settings.max_adverbs {
+                for (range, word) in adverbs {
+                    push(
+                        range.clone(),
+                        "ADVERBS",
+                        "Adverb",
+                        format!("Consider removing {word:?}, this sentence has many adverbs."),
+                    );
+                }
+            }
+        }
+
+        if settings.passive_voice
+            && let Some(range) = passive_voice(&found, german)
+        {
+            push(
+                range,
+                "PASSIVE_VOICE",
+                "Passive voice",
+                "Consider using the active voice.".to_string(),
+            );
+        }
+    }
+    matches
+}
+
+fn is_adverb(word: &str) -> bool {
+    let word = word.to_lowercase();
+    word.len() > 4 && word.ends_with("ly") && !NOT_ADVERBS.contains(&word.as_str())
+}
+
+/// Finds an auxiliary verb followed by a participle
+fn passive_voice(
+    words: &[(std::ops::Range<usize>, &str)],
+    german: bool,
+) -> Option<std::ops::Range<usize>> {
+    if german {
+        const WERDEN: &[&str] = &[
+            "werde", "wirst", "wird", "werden", "werdet", "wurde", "wurdest", "wurden", "wurdet",
+            "worden",
+        ];
+        let (i, (aux, _)) = words
+            .iter()
+            .enumerate()
+            .find(|(_, (_, w))| WERDEN.contains(&w.to_lowercase().as_str()))?;
+        // German participles are usually at the end of the clause
+        let (participle, _) = words[i + 1..].iter().find(|(_, w)| {
+            w.len() > 4 && w.starts_with("ge") && (w.ends_with('t') || w.ends_with("en"))
+        })?;
+        Some(aux.start..participle.end)
+    } else {
+        const BE: &[&str] = &["am", "is", "are", "was", "were", "be", "been", "being"];
+        words.iter().enumerate().find_map(|(i, (aux, word))| {
+            if !BE.contains(&word.to_lowercase().as_str()) {
+                return None;
+            }
+            let mut next = words[i + 1..].iter();
+            let mut participle = next.next()?;
+            // Allow a single adverb in between ("is quickly done")
+            if is_adverb(participle.1) {
+                participle = next.next()?;
+            }
+            let word = participle.1.to_lowercase();
+            (word.len() > 3 && word.ends_with("ed")
+                || IRREGULAR_PARTICIPLES.contains(&word.as_str()))
+            .then_some(aux.start..participle.0.end)
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn found(settings: &StyleSettings, german: bool, text: &str) -> Vec<(String, String)> {
+        check(settings, german, text, 0)
+            .into_iter()
+            .map(|m| (m.rule, text[m.range].to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn style() {
+        let settings = StyleSettings {
+            max_sentence_words: 6,
+            max_adverbs: 1,
+            passive_voice: true,
+        };
+        assert_eq!(
+            found(
+                &settings,
+                false,
+                "The ball was thrown. It really quickly rolled away."
+            ),
+            [
+                ("PASSIVE_VOICE".into(), "was thrown".into()),
+                ("ADVERBS".into(), "really".into()),
+                ("ADVERBS".into(), "quickly".into()),
+            ]
+        );
+        assert_eq!(
+            found(
+                &settings,
+                false,
+                "The report was carefully reviewed by one of the team."
+            ),
+            [
+                (
+                    "SENTENCE_LENGTH".into(),
+                    "The report was carefully reviewed by one of the team.".into()
+                ),
+                ("PASSIVE_VOICE".into(), "was carefully reviewed".into()),
+            ]
+        );
+        assert_eq!(
+            found(&settings, true, "Das Haus wird morgen gebaut."),
+            [("PASSIVE_VOICE".into(), "wird morgen gebaut".into())]
+        );
+    }
+}
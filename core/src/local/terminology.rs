@@ -0,0 +1,146 @@
+//! User-defined terminology, e.g. forbidden terms or required casing.
+//!
+//! Terms are configured in the settings or in the `terminology.toml` file
+//! of the project directory:
+//!
+//! ```toml
+//! [[term]]
+//! preferred = "GitHub"
+//!
+//! [[term]]
+//! preferred = "use"
+//! forbidden = ["utilize", "leverage"]
+//! message = "Prefer simple words."
+//! ```
+
+use std::io::ErrorKind;
+use std::path::Path;
+
+use anyhow::Result;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::api::Match;
+
+/// Terminology file in the project directory
+pub const FILE: &str = "terminology.toml";
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct Term {
+    /// The preferred term, other casings of it are reported
+    pub preferred: Option<String>,
+    /// Terms that should be replaced by the preferred term (case-insensitive)
+    pub forbidden: Vec<String>,
+    /// Custom diagnostic message
+    pub message: Option<String>,
+}
+
+/// Load the terms of a terminology file
+pub fn load(path: &Path) -> Result<Vec<Term>> {
+    #[derive(Deserialize)]
+    struct TerminologyFile {
+        #[serde(default)]
+        term: Vec<Term>,
+    }
+    match std::fs::read_to_string(path) {
+        Ok(data) => Ok(toml::from_str::<TerminologyFile>(&data)?.term),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+pub struct Checker<'a> {
+    terms: Vec<(Regex, &'a Term)>,
+}
+
+impl<'a> Checker<'a> {
+    pub fn new(terms: &'a [Term]) -> Self {
+        let terms = terms
+            .iter()
+            .filter_map(|term| {
+                let pattern = term
+                    .preferred
+                    .iter()
+                    .chain(&term.forbidden)
+                    .map(|t| regex::escape(t))
+                    .collect::<Vec<_>>()
+                    .join("|");
+                if pattern.is_empty() {
+                    return None;
+                }
+                Some((Regex::new(&format!(r"(?i)\b(?:{pattern})\b")).ok()?, term))
+            })
+            .collect();
+        Self { terms }
+    }
+
+    pub fn check(&self, text: &str, offset: usize) -> Vec<Match> {
+        let mut matches = Vec::new();
+        for (regex, term) in &self.terms {
+            for found in regex.find_iter(text) {
+                if term.preferred.as_deref() == Some(found.as_str()) {
+                    continue;
+                }
+                let message = match (&term.message, &term.preferred) {
+                    (Some(message), _) => message.clone(),
+                    (None, Some(preferred)) => {
+                        format!("Use “{preferred}” instead of “{}”.", found.as_str())
+                    }
+                    (None, None) => format!("Avoid “{}”.", found.as_str()),
+                };
+                matches.push(Match {
+                    range: offset + found.start()..offset + found.end(),
+                    title: "Terminology".to_string(),
+                    message,
+                    replacements: term.preferred.iter().cloned().collect(),
+                    category: "TERMINOLOGY".to_string(),
+                    rule: "TERMINOLOGY".to_string(),
+                    severity: None,
+                    details: Default::default(),
+                });
+            }
+        }
+        matches
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn terms() {
+        let terms = toml::from_str::<toml::Table>(
+            r#"
+            [[term]]
+            preferred = "GitHub"
+            [[term]]
+            preferred = "use"
+            forbidden = ["utilize"]
+            [[term]]
+            forbidden = ["foobar"]
+            "#,
+        )
+        .unwrap()["term"]
+            .clone()
+            .try_into::<Vec<Term>>()
+            .unwrap();
+
+        let checker = Checker::new(&terms);
+        let text = "GitHub and Github, Utilize FOOBAR to use.";
+        let matches = checker.check(text, 10);
+        let found = matches
+            .iter()
+            .map(|m| (&text[m.range.start - 10..m.range.end - 10], &m.replacements))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            found,
+            [
+                ("Github", &vec!["GitHub".to_string()]),
+                ("Utilize", &vec!["use".to_string()]),
+                ("FOOBAR", &vec![]),
+            ]
+        );
+    }
+}
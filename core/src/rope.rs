@@ -0,0 +1,176 @@
+//! Storage of large documents.
+//!
+//! The text is split into chunks of a few kilobytes, so that an edit only
+//! moves the bytes of the affected chunks instead of the whole document.
+
+use std::borrow::Cow;
+use std::ops::Range;
+
+/// Target size of the chunks in bytes (small in tests to cover the edits
+/// across chunk boundaries)
+const CHUNK: usize = if cfg!(test) { 8 } else { 4096 };
+
+#[derive(Debug, Clone)]
+pub struct Rope {
+    chunks: Vec<String>,
+    /// Byte offset of the start of each chunk
+    starts: Vec<usize>,
+    len: usize,
+}
+
+impl Rope {
+    pub fn new(text: &str) -> Self {
+        let mut rope = Self {
+            chunks: split(text),
+            starts: Vec::new(),
+            len: text.len(),
+        };
+        rope.update_starts(0);
+        rope
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Index of the chunk containing the byte offset, the end of the text
+    /// belongs to the last chunk
+    fn chunk(&self, offset: usize) -> usize {
+        self.starts
+            .partition_point(|start| *start <= offset)
+            .saturating_sub(1)
+    }
+
+    pub fn replace(&mut self, range: Range<usize>, text: &str) {
+        assert!(range.start <= range.end && range.end <= self.len);
+        let first = self.chunk(range.start);
+        let last = self.chunk(range.end);
+        let start = range.start - self.starts[first];
+        let end = range.end - self.starts[last];
+
+        let mut chunk = std::mem::take(&mut self.chunks[first]);
+        let tail = if first == last {
+            chunk[end..].to_string()
+        } else {
+            self.chunks[last][end..].to_string()
+        };
+        chunk.truncate(start);
+        chunk.push_str(text);
+        chunk.push_str(&tail);
+
+        // Keep the chunks small and avoid empty ones
+        let mut chunks = if chunk.len() > 2 * CHUNK {
+            split(&chunk)
+        } else {
+            vec![chunk]
+        };
+        if chunks[0].is_empty() && self.chunks.len() > last - first + 1 {
+            chunks.clear();
+        }
+        self.chunks.splice(first..=last, chunks);
+        if self.chunks.is_empty() {
+            self.chunks.push(String::new());
+        }
+        self.len = self.len + text.len() - range.len();
+        self.update_starts(first);
+    }
+
+    /// Returns the text of the range, only allocated if it spans chunks
+    pub fn slice(&self, range: Range<usize>) -> Cow<'_, str> {
+        let first = self.chunk(range.start);
+        let last = self.chunk(range.end);
+        let start = range.start - self.starts[first];
+        if first == last {
+            let end = range.end - self.starts[first];
+            return Cow::Borrowed(&self.chunks[first][start..end]);
+        }
+        let mut text = String::with_capacity(range.len());
+        text.push_str(&self.chunks[first][start..]);
+        for chunk in &self.chunks[first + 1..last] {
+            text.push_str(chunk);
+        }
+        text.push_str(&self.chunks[last][..range.end - self.starts[last]]);
+        Cow::Owned(text)
+    }
+
+    /// Returns the text of the range, or None if it is out of bounds or not
+    /// at character boundaries
+    pub fn get(&self, range: Range<usize>) -> Option<Cow<'_, str>> {
+        if range.start > range.end || range.end > self.len {
+            return None;
+        }
+        let boundary = |offset: usize| {
+            let i = self.chunk(offset);
+            self.chunks[i].is_char_boundary(offset - self.starts[i])
+        };
+        (boundary(range.start) && boundary(range.end)).then(|| self.slice(range))
+    }
+
+    fn update_starts(&mut self, from: usize) {
+        self.starts.truncate(from);
+        let mut start = match from {
+            0 => 0,
+            _ => self.starts[from - 1] + self.chunks[from - 1].len(),
+        };
+        for chunk in &self.chunks[from..] {
+            self.starts.push(start);
+            start += chunk.len();
+        }
+    }
+}
+
+impl std::fmt::Display for Rope {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.chunks.iter().try_for_each(|chunk| f.write_str(chunk))
+    }
+}
+
+/// Splits the text into chunks at character boundaries
+fn split(text: &str) -> Vec<String> {
+    let mut chunks = Vec::new();
+    let mut rest = text;
+    while rest.len() > CHUNK {
+        let mut end = CHUNK;
+        while !rest.is_char_boundary(end) {
+            end += 1;
+        }
+        chunks.push(rest[..end].to_string());
+        rest = &rest[end..];
+    }
+    chunks.push(rest.to_string());
+    chunks
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn replace() {
+        let mut text = String::from("Hello Wörld, this is a longer text 😀 with chunks.");
+        let mut rope = Rope::new(&text);
+        let edits = [
+            (0..0, "A"),
+            (3..20, ""),
+            (5..5, "some inserted text that spans multiple chunks"),
+            (10..40, "x"),
+            (0..text.len() - 20, ""),
+            (0..0, "äöü"),
+        ];
+        for (range, insert) in edits {
+            text.replace_range(range.clone(), insert);
+            rope.replace(range, insert);
+            assert_eq!(rope.to_string(), text);
+            assert_eq!(rope.len(), text.len());
+            assert_eq!(rope.slice(2..text.len() - 1), &text[2..text.len() - 1]);
+            for start in 0..=text.len() {
+                for end in start..=text.len() + 1 {
+                    assert_eq!(rope.get(start..end).as_deref(), text.get(start..end));
+                }
+            }
+        }
+        rope.replace(0..rope.len(), "");
+        assert_eq!(rope.len(), 0);
+        assert_eq!(rope.slice(0..0), "");
+    }
+}
@@ -0,0 +1,749 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::annotated::code::CodeSettings;
+use crate::annotated::latex::LatexSettings;
+use crate::annotated::quotes::QuoteSettings;
+use crate::annotated::templates::TemplateSettings;
+use crate::annotated::tokens::TokenSettings;
+use crate::api::{Match, Mode, RETRIES, SynonymProvider, Tag};
+use crate::local::rules::CustomRule;
+use crate::local::style::StyleSettings;
+use crate::local::terminology::Term;
+use crate::util::glob;
+
+const ENDPOINTS: [Endpoint; 3] = [
+    Endpoint::new("https://api.languagetool.org", 20.0, 20000),
+    Endpoint::new("https://api.languagetoolplus.com", 80.0, 75000),
+    Endpoint::new("", 120.0, 1000000),
+];
+
+pub struct Endpoint {
+    url: &'static str,
+    requests_per_s: f64,
+    max_size: usize,
+}
+impl Endpoint {
+    pub const fn new(url: &'static str, requests_per_s: f64, max_size: usize) -> Self {
+        Self {
+            url,
+            requests_per_s,
+            max_size,
+        }
+    }
+    pub const fn min_delay(&self) -> f64 {
+        (60.0 / self.requests_per_s) * 1000.0
+    }
+    pub const fn requests_per_minute(&self) -> f64 {
+        self.requests_per_s
+    }
+    /// Maximum size of the text of a single request in bytes
+    pub const fn max_size(&self) -> usize {
+        self.max_size
+    }
+    /// Maximum number of concurrent requests, the public servers are
+    /// checked sequentially to stay below their request limit
+    pub const fn max_concurrency(&self) -> usize {
+        if self.url.is_empty() { usize::MAX } else { 1 }
+    }
+}
+
+/// Kind of issues that are checked
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Scope {
+    #[default]
+    All,
+    /// Only spelling mistakes
+    Spelling,
+    /// Everything except spelling mistakes
+    Grammar,
+}
+
+impl Scope {
+    /// Restricts the rules of the request to the scope
+    pub fn apply(self, settings: &mut Settings) {
+        match self {
+            Scope::All => {}
+            Scope::Spelling => {
+                settings.enabled_categories = "TYPOS".into();
+                settings.enabled_only = true;
+            }
+            Scope::Grammar => {
+                if !settings.disabled_categories.is_empty() {
+                    settings.disabled_categories.push(',');
+                }
+                settings.disabled_categories.push_str("TYPOS");
+            }
+        }
+    }
+    /// Checks if the match is part of the scope
+    pub fn includes(self, m: &Match) -> bool {
+        let spelling = m.category == "TYPOS" || m.category == "LOCAL_SPELLING";
+        match self {
+            Scope::All => true,
+            Scope::Spelling => spelling,
+            Scope::Grammar => !spelling,
+        }
+    }
+}
+
+/// Level of the log messages
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Off,
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+/// LanguageTool server in a Docker container, started by the language server
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct DockerSettings {
+    /// Run the container and use it instead of the configured server
+    pub enabled: bool,
+    pub image: String,
+    pub tag: String,
+    /// Local port of the server
+    pub port: u16,
+    /// Time for the server to become ready in seconds
+    pub startup_timeout: f64,
+}
+impl DockerSettings {
+    pub fn url(&self) -> Url {
+        format!("http://localhost:{}", self.port).parse().unwrap()
+    }
+}
+impl Default for DockerSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            image: "erikvl87/languagetool".into(),
+            tag: "latest".into(),
+            port: 8081,
+            startup_timeout: 120.0,
+        }
+    }
+}
+
+/// Settings for the LanguageTool server
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct Settings {
+    #[serde(with = "serde_url")]
+    pub server: Url,
+    pub api_key: String,
+    pub username: String,
+    /// Local server in a Docker container
+    pub docker: DockerSettings,
+    /// Use another server while the configured one is unavailable
+    pub failover: bool,
+    /// Servers used in this order by the failover, the public servers if
+    /// empty
+    pub fallback_servers: Vec<String>,
+    /// Check with the built-in stub instead of a server, e.g. for tests and
+    /// demos without network access
+    pub offline_stub: bool,
+
+    /// Diagnostic tags by rule or category id, redundancies are unnecessary
+    /// by default
+    pub tags: HashMap<String, Vec<Tag>>,
+
+    /// Glob patterns of the files checked by the workspace check
+    pub workspace_files: Vec<String>,
+    /// Glob patterns of the checked documents, all if empty
+    pub include: Vec<String>,
+    /// Glob patterns of documents that are never checked, e.g.
+    /// `package-lock.json` or `vendor/**`
+    pub exclude: Vec<String>,
+    /// Language ids of the checked documents, all if empty
+    pub language_ids: Vec<String>,
+
+    /// Retries of rate limited or temporarily unavailable requests
+    pub max_retries: u32,
+    /// Timeout for connecting to the server in seconds
+    pub connect_timeout: f64,
+    /// Timeout for a whole request in seconds
+    pub request_timeout: f64,
+
+    /// Documents larger than this (in bytes) are not checked, 0 for no limit
+    pub max_file_size: usize,
+    /// Maximum number of changed paragraphs per check, the remaining ones
+    /// are checked next time, 0 for no limit
+    pub max_paragraphs: usize,
+
+    /// Maximum number of suggested replacements per match, 0 for no limit
+    pub max_replacements: usize,
+    /// Maximum number of diagnostics per document, 0 for no limit
+    pub max_diagnostics_per_file: usize,
+
+    pub auto_check: bool,
+    pub auto_check_delay: f64,
+    /// Check the whole document when it is opened, spaced out by the request
+    /// limit of the server
+    pub check_on_open: bool,
+    /// Maximum number of paragraphs checked at the same time by
+    /// self-hosted servers
+    pub max_concurrent_checks: usize,
+    /// Check changed paragraphs without text level rules (e.g. repetitions)
+    /// and the whole document with only these rules in a second pass
+    pub text_level_check: bool,
+    /// Delay of the text level check after the last check in milliseconds
+    pub text_level_delay: f64,
+    /// Rules of the current request, set per pass
+    #[serde(skip)]
+    pub mode: Mode,
+    /// Synonym services by language, preferred over the built-in ones
+    pub synonyms: Vec<SynonymProvider>,
+    /// Paraphrase service of the premium account for rephrasing sentences
+    pub rephrase_url: Option<String>,
+
+    pub mother_tongue: String,
+    pub static_language: Option<String>,
+    pub language_variety: HashMap<String, String>,
+    /// Candidate languages if the language detection is uncertain
+    pub languages: Vec<String>,
+    /// Minimum confidence of the language detection
+    pub language_confidence: f64,
+    /// Languages of documents by language id or glob pattern of the path,
+    /// e.g. `"*.de.md": "de-DE"`, overriding the static language
+    pub document_languages: HashMap<String, String>,
+
+    pub dictionary: Vec<String>,
+    pub sync_dictionary: bool,
+    /// Snapshot of the last synchronization, used until the first
+    /// synchronization stored its own snapshot
+    pub remote_dictionary: Vec<String>,
+    /// Named dictionaries of the premium account used for checking, added
+    /// words go to the default dictionary unless the command names one
+    pub dicts: Vec<String>,
+    /// Hunspell dictionaries or frequency lists of correctly spelled words
+    /// (relative to the workspace)
+    pub word_lists: Vec<PathBuf>,
+
+    /// Overrides the log level of the command line
+    pub log_level: Option<LogLevel>,
+
+    /// Learn from accepted and ignored suggestions
+    pub learning: bool,
+    /// Automatically fix unambiguous typos on save
+    pub autocorrect: bool,
+    /// Rules that are considered safe for autocorrection
+    pub autocorrect_rules: Vec<String>,
+
+    pub picky: bool,
+    pub enabled_categories: String,
+    pub disabled_categories: String,
+    pub enabled_rules: Vec<String>,
+    pub disabled_rules: Vec<String>,
+    /// Only use the enabled rules and categories, e.g. for style guides
+    pub enabled_only: bool,
+    /// Quoted text that is not checked, by language id ("*" for all others)
+    pub skip_quotes: HashMap<String, QuoteSettings>,
+    /// Regex patterns of text that is not checked (e.g. ticket ids)
+    pub ignore_patterns: Vec<String>,
+    /// Template tags that are not checked
+    pub templates: TemplateSettings,
+    /// URLs, email addresses, paths and code spans that are not checked
+    pub tokens: TokenSettings,
+    /// Overrides the annotator of a language id, e.g. `"quarto": "markdown"`
+    pub annotators: HashMap<String, String>,
+    /// Comments of source code, by language id ("*" for all others)
+    pub code: HashMap<String, CodeSettings>,
+    /// Kind of issues that are checked, by language id ("*" for all others),
+    /// e.g. only spelling for source code
+    pub scopes: HashMap<String, Scope>,
+    /// Commands and environments of LaTeX documents
+    pub latex: LatexSettings,
+    /// Also check the source strings (`msgid`) of gettext catalogs
+    pub gettext_msgid: bool,
+
+    /// Local terminology rules
+    pub terminology: Vec<Term>,
+    /// Local regex rules
+    pub custom_rules: Vec<CustomRule>,
+    /// Local style checks
+    pub style: StyleSettings,
+}
+
+impl Settings {
+    /// Deserializes the settings field by field, so that invalid fields keep
+    /// their previous value instead of rejecting all settings.
+    ///
+    /// Returns the errors of the invalid fields.
+    pub fn parse(
+        fields: serde_json::Map<String, serde_json::Value>,
+        previous: &Settings,
+    ) -> (Self, Vec<String>) {
+        let serde_json::Value::Object(mut valid) = serde_json::to_value(Self::default()).unwrap()
+        else {
+            unreachable!("settings are a struct");
+        };
+        let previous = serde_json::to_value(previous).unwrap();
+        let mut errors = Vec::new();
+        for (key, value) in fields {
+            let mut candidate = valid.clone();
+            candidate.insert(key.clone(), value.clone());
+            match serde_json::from_value::<Self>(candidate.into()) {
+                Ok(_) => {
+                    valid.insert(key, value);
+                }
+                Err(err) => {
+                    errors.push(format!("{key}: {err}"));
+                    if let Some(value) = previous.get(&key) {
+                        valid.insert(key, value.clone());
+                    }
+                }
+            }
+        }
+        let settings = serde_json::from_value(valid.into()).unwrap_or_default();
+        (settings, errors)
+    }
+    /// Checks the values that are valid json but not usable
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        if !(0.0..=1.0).contains(&self.language_confidence) {
+            errors.push("language_confidence: must be between 0 and 1".to_string());
+        }
+        for (name, value) in [
+            ("connect_timeout", self.connect_timeout),
+            ("request_timeout", self.request_timeout),
+            ("auto_check_delay", self.auto_check_delay),
+            ("text_level_delay", self.text_level_delay),
+            ("docker.startup_timeout", self.docker.startup_timeout),
+        ] {
+            if value < 0.0 {
+                errors.push(format!("{name}: must not be negative"));
+            }
+        }
+        for server in &self.fallback_servers {
+            if let Err(err) = Url::parse(server) {
+                errors.push(format!("fallback_servers: {server:?}: {err}"));
+            }
+        }
+        if let Some(url) = &self.rephrase_url
+            && let Err(err) = Url::parse(url)
+        {
+            errors.push(format!("rephrase_url: {err}"));
+        }
+        for provider in &self.synonyms {
+            if let Err(err) = Url::parse(&provider.url) {
+                errors.push(format!("synonyms: {:?}: {err}", provider.url));
+            }
+        }
+        for pattern in &self.ignore_patterns {
+            if let Err(err) = regex::Regex::new(pattern) {
+                errors.push(format!("ignore_patterns: {err}"));
+            }
+        }
+        for pattern in self
+            .workspace_files
+            .iter()
+            .chain(&self.include)
+            .chain(&self.exclude)
+            .chain(self.document_languages.keys())
+        {
+            if let Err(err) = glob(pattern) {
+                errors.push(format!("invalid pattern {pattern:?}: {err}"));
+            }
+        }
+        errors
+    }
+    /// Servers of the failover except the given one, the premium server is
+    /// only used with credentials
+    pub fn fallbacks(&self, server: &Url) -> Vec<Url> {
+        if !self.failover {
+            return Vec::new();
+        }
+        let servers = if self.fallback_servers.is_empty() {
+            let premium = !self.username.is_empty() && !self.api_key.is_empty();
+            let public = [&ENDPOINTS[1], &ENDPOINTS[0]];
+            public
+                .into_iter()
+                .filter(|e| premium || e.url != ENDPOINTS[1].url)
+                .filter_map(|e| e.url.parse().ok())
+                .collect()
+        } else {
+            self.fallback_servers
+                .iter()
+                .filter_map(|s| s.parse().ok())
+                .collect::<Vec<Url>>()
+        };
+        servers.into_iter().filter(|s| s != server).collect()
+    }
+    /// Synonym service for the language, the configured ones first
+    pub fn synonym_provider(&self, language: &str) -> Option<SynonymProvider> {
+        SynonymProvider::select(&self.synonyms, language)
+            .cloned()
+            .or_else(|| SynonymProvider::select(&SynonymProvider::defaults(), language).cloned())
+    }
+    /// JSON schema of the settings, with the types and values of the
+    /// default settings
+    pub fn schema() -> serde_json::Value {
+        let defaults = serde_json::to_value(Self::default()).unwrap();
+        let mut schema = value_schema(&defaults);
+        schema["$schema"] = "http://json-schema.org/draft-07/schema#".into();
+        schema["title"] = "LanguageTool LSP settings".into();
+        schema
+    }
+    /// Limits of the configured server, custom servers have the last entry
+    pub fn endpoint(&self) -> &'static Endpoint {
+        ENDPOINTS
+            .iter()
+            .find(|e| self.server.as_str().starts_with(e.url))
+            .unwrap_or(&ENDPOINTS[ENDPOINTS.len() - 1])
+    }
+    /// Returns the diagnostic tags of the match
+    pub fn tags(&self, m: &Match) -> Vec<Tag> {
+        if let Some(tags) = self
+            .tags
+            .get(&m.rule)
+            .or_else(|| self.tags.get(&m.category))
+        {
+            return tags.clone();
+        }
+        if m.category == "REDUNDANCY" || m.details.issue_type == "duplication" {
+            vec![Tag::Unnecessary]
+        } else {
+            Vec::new()
+        }
+    }
+    /// Returns the language for the language id or path (relative to the
+    /// workspace) of a document, the most specific pattern wins.
+    ///
+    /// Patterns without `/` only match the file name.
+    pub fn document_language(&self, language_id: &str, path: &str) -> Option<&str> {
+        if let Some(language) = self.document_languages.get(language_id) {
+            return Some(language);
+        }
+        self.document_languages
+            .iter()
+            .filter(|(pattern, _)| matches_path(pattern, path))
+            .max_by_key(|(pattern, _)| (pattern.len(), *pattern))
+            .map(|(_, language)| language.as_str())
+    }
+    /// Checks if the document with the language id and path (relative to
+    /// the workspace) should be checked
+    pub fn is_checked(&self, language_id: &str, path: &str) -> bool {
+        self.is_language_checked(language_id)
+            && (self.include.is_empty() || self.include.iter().any(|p| matches_path(p, path)))
+            && !self.exclude.iter().any(|p| matches_path(p, path))
+    }
+    /// Checks if documents with the language id should be checked,
+    /// regardless of their path
+    pub fn is_language_checked(&self, language_id: &str) -> bool {
+        self.language_ids.is_empty() || self.language_ids.iter().any(|l| l == language_id)
+    }
+    pub fn skip_quotes(&self, language_id: &str) -> QuoteSettings {
+        self.skip_quotes
+            .get(language_id)
+            .or_else(|| self.skip_quotes.get("*"))
+            .cloned()
+            .unwrap_or_default()
+    }
+    pub fn scope(&self, language_id: &str) -> Scope {
+        self.scopes
+            .get(language_id)
+            .or_else(|| self.scopes.get("*"))
+            .copied()
+            .unwrap_or_default()
+    }
+    pub fn code(&self, language_id: &str) -> CodeSettings {
+        self.code
+            .get(language_id)
+            .or_else(|| self.code.get("*"))
+            .cloned()
+            .unwrap_or_default()
+    }
+}
+
+/// Matches the glob pattern against the path, patterns without `/` only
+/// match the file name
+fn matches_path(pattern: &str, path: &str) -> bool {
+    let target = if pattern.contains('/') {
+        path
+    } else {
+        path.rsplit('/').next().unwrap_or(path)
+    };
+    glob(pattern).is_ok_and(|g| g.is_match(target))
+}
+
+mod serde_url {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer, de::Error};
+    use url::Url;
+    pub fn deserialize<'de, D: Deserializer<'de>>(val: D) -> Result<Url, D::Error> {
+        let s = String::deserialize(val)?;
+        if s.is_empty() {
+            return Ok(super::ENDPOINTS[0].url.parse().unwrap());
+        }
+        Url::parse(&s).map_err(|e| Error::custom(format!("invalid URL: {e}")))
+    }
+    pub fn serialize<S: Serializer>(val: &Url, ser: S) -> Result<S::Ok, S::Error> {
+        String::from(val.clone()).serialize(ser)
+    }
+}
+
+/// Schema of a value, optional values without default accept any value
+fn value_schema(value: &serde_json::Value) -> serde_json::Value {
+    use serde_json::{Value, json};
+    match value {
+        Value::Null => json!({}),
+        Value::Bool(_) => json!({ "type": "boolean", "default": value }),
+        Value::Number(n) if n.is_f64() => json!({ "type": "number", "default": value }),
+        Value::Number(_) => json!({ "type": "integer", "default": value }),
+        Value::String(_) => json!({ "type": "string", "default": value }),
+        Value::Array(items) => json!({
+            "type": "array",
+            "items": items.first().map_or(json!({}), value_schema),
+            "default": value,
+        }),
+        Value::Object(fields) => {
+            let properties = fields
+                .iter()
+                .map(|(key, value)| (key.clone(), value_schema(value)))
+                .collect::<serde_json::Map<_, _>>();
+            json!({ "type": "object", "properties": properties })
+        }
+    }
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            server: ENDPOINTS[0].url.parse().unwrap(),
+            api_key: String::new(),
+            username: String::new(),
+            docker: DockerSettings::default(),
+            failover: false,
+            fallback_servers: Vec::new(),
+            offline_stub: false,
+            tags: HashMap::new(),
+            workspace_files: ["**/*.md", "**/*.txt", "**/*.tex"].map(String::from).into(),
+            include: Vec::new(),
+            exclude: Vec::new(),
+            language_ids: Vec::new(),
+            max_retries: RETRIES,
+            connect_timeout: 10.0,
+            request_timeout: 60.0,
+            max_file_size: 1_000_000,
+            max_paragraphs: 200,
+            max_replacements: 10,
+            max_diagnostics_per_file: 500,
+            auto_check: true,
+            auto_check_delay: ENDPOINTS[0].min_delay(),
+            check_on_open: true,
+            max_concurrent_checks: 4,
+            text_level_check: false,
+            text_level_delay: 10000.0,
+            mode: Mode::All,
+            synonyms: Vec::new(),
+            rephrase_url: None,
+            mother_tongue: String::new(),
+            static_language: None,
+            language_variety: [
+                ("en".to_string(), "en-US".to_string()),
+                ("de".to_string(), "de-DE".to_string()),
+                ("pt".to_string(), "pt-PT".to_string()),
+                ("ca".to_string(), "ca-ES".to_string()),
+            ]
+            .into(),
+            dictionary: Vec::new(),
+            sync_dictionary: false,
+            languages: Vec::new(),
+            language_confidence: 0.9,
+            document_languages: HashMap::new(),
+            remote_dictionary: Vec::new(),
+            dicts: Vec::new(),
+            word_lists: Vec::new(),
+            log_level: None,
+            learning: false,
+            autocorrect: false,
+            autocorrect_rules: vec![
+                "MORFOLOGIK_RULE_EN_US".to_string(),
+                "MORFOLOGIK_RULE_EN_GB".to_string(),
+                "GERMAN_SPELLER_RULE".to_string(),
+            ],
+            picky: false,
+            enabled_categories: String::new(),
+            disabled_categories: String::new(),
+            enabled_rules: Vec::new(),
+            disabled_rules: Vec::new(),
+            enabled_only: false,
+            skip_quotes: HashMap::new(),
+            ignore_patterns: Vec::new(),
+            templates: TemplateSettings::default(),
+            tokens: TokenSettings::default(),
+            annotators: HashMap::new(),
+            code: HashMap::new(),
+            scopes: HashMap::new(),
+            latex: LatexSettings::default(),
+            gettext_msgid: false,
+            terminology: Vec::new(),
+            custom_rules: Vec::new(),
+            style: StyleSettings::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn document_language() {
+        let settings = Settings {
+            document_languages: [
+                ("*.de.md", "de-DE"),
+                ("CHANGELOG.md", "en-US"),
+                ("docs/fr/**/*.md", "fr"),
+                ("latex", "en-GB"),
+            ]
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect(),
+            ..Default::default()
+        };
+        let language = |id, path| settings.document_language(id, path);
+        assert_eq!(language("markdown", "docs/intro.de.md"), Some("de-DE"));
+        assert_eq!(language("markdown", "CHANGELOG.md"), Some("en-US"));
+        assert_eq!(language("markdown", "docs/fr/guide/a.de.md"), Some("fr"));
+        assert_eq!(language("latex", "paper.tex"), Some("en-GB"));
+        assert_eq!(language("markdown", "README.md"), None);
+    }
+
+    #[test]
+    fn is_checked() {
+        let settings = Settings {
+            include: vec!["docs/**".into(), "*.md".into()],
+            exclude: vec!["docs/generated/**".into(), "CHANGELOG.md".into()],
+            ..Default::default()
+        };
+        assert!(settings.is_checked("markdown", "README.md"));
+        assert!(settings.is_checked("plaintext", "docs/guide.txt"));
+        assert!(!settings.is_checked("json", "package-lock.json"));
+        assert!(!settings.is_checked("markdown", "docs/generated/api.md"));
+        assert!(!settings.is_checked("markdown", "sub/CHANGELOG.md"));
+
+        let settings = Settings {
+            language_ids: vec!["markdown".into()],
+            ..Default::default()
+        };
+        assert!(settings.is_checked("markdown", "a.md"));
+        assert!(!settings.is_checked("rust", "main.rs"));
+        assert!(settings.is_language_checked("markdown"));
+        assert!(!settings.is_language_checked("rust"));
+    }
+
+    #[test]
+    fn parse() {
+        let previous = Settings {
+            picky: true,
+            max_retries: 7,
+            ..Default::default()
+        };
+        let fields = serde_json::json!({
+            "server": "not a url",
+            "max_retries": "many",
+            "synonyms": "fr",
+            "auto_check": false,
+            "unknown": 1,
+        });
+        let serde_json::Value::Object(fields) = fields else {
+            unreachable!()
+        };
+        let (settings, errors) = Settings::parse(fields, &previous);
+        assert_eq!(errors.len(), 3);
+        assert!(errors.iter().any(|e| e.starts_with("server:")));
+        assert_eq!(settings.server, previous.server);
+        assert_eq!(settings.max_retries, 7);
+        assert!(!settings.auto_check);
+        // Missing fields are reset to the default
+        assert!(!settings.picky);
+
+        let settings = Settings {
+            language_confidence: 2.0,
+            ignore_patterns: vec!["(".into()],
+            ..Default::default()
+        };
+        assert_eq!(settings.validate().len(), 2);
+    }
+
+    #[test]
+    fn scope() {
+        let settings = Settings {
+            scopes: [
+                ("rust".into(), Scope::Spelling),
+                ("*".into(), Scope::Grammar),
+            ]
+            .into(),
+            disabled_categories: "STYLE".into(),
+            ..Default::default()
+        };
+        let m = |category: &str| Match {
+            range: 0..1,
+            title: String::new(),
+            message: String::new(),
+            replacements: Vec::new(),
+            category: category.into(),
+            rule: String::new(),
+            severity: None,
+            details: Default::default(),
+        };
+        assert_eq!(settings.scope("rust"), Scope::Spelling);
+        assert!(settings.scope("rust").includes(&m("TYPOS")));
+        assert!(!settings.scope("rust").includes(&m("GRAMMAR")));
+        assert!(!settings.scope("markdown").includes(&m("LOCAL_SPELLING")));
+
+        let mut grammar = settings.clone();
+        Scope::Grammar.apply(&mut grammar);
+        assert_eq!(grammar.disabled_categories, "STYLE,TYPOS");
+        let mut spelling = settings.clone();
+        Scope::Spelling.apply(&mut spelling);
+        assert_eq!(spelling.enabled_categories, "TYPOS");
+        assert!(spelling.enabled_only);
+    }
+
+    #[test]
+    fn schema() {
+        let schema = Settings::schema();
+        let properties = &schema["properties"];
+        assert_eq!(properties["picky"]["type"], "boolean");
+        assert_eq!(properties["max_retries"]["type"], "integer");
+        assert_eq!(properties["connect_timeout"]["type"], "number");
+        assert_eq!(properties["docker"]["properties"]["port"]["default"], 8081);
+        assert_eq!(properties["language_variety"]["type"], "object");
+        assert!(properties.get("mode").is_none());
+    }
+
+    #[test]
+    fn fallbacks() {
+        let local: Url = "http://localhost:8081".parse().unwrap();
+        let mut settings = Settings {
+            server: local.clone(),
+            ..Default::default()
+        };
+        assert!(settings.fallbacks(&local).is_empty());
+
+        settings.failover = true;
+        let public = settings.fallbacks(&local);
+        assert_eq!(public.len(), 1);
+        assert_eq!(public[0].as_str(), "https://api.languagetool.org/");
+        assert!(settings.fallbacks(&public[0]).is_empty());
+
+        settings.username = "user".into();
+        settings.api_key = "key".into();
+        assert_eq!(settings.fallbacks(&local).len(), 2);
+
+        settings.fallback_servers = vec!["http://backup:8081".into(), "invalid".into()];
+        let custom = settings.fallbacks(&local);
+        assert_eq!(custom.len(), 1);
+        assert_eq!(custom[0].host_str(), Some("backup"));
+    }
+}
@@ -0,0 +1,426 @@
+use std::borrow::Cow;
+use std::ops::{Add, AddAssign, Range, Sub, SubAssign};
+use std::sync::OnceLock;
+
+use lsp_types::Position;
+
+use crate::rope::Rope;
+use crate::util::utf16_to_byte;
+
+/// Encoding of the characters of positions, negotiated with the client
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Encoding {
+    Utf8,
+    #[default]
+    Utf16,
+}
+impl Encoding {
+    /// Length of the text in code units
+    pub fn len(self, text: &str) -> usize {
+        match self {
+            Encoding::Utf8 => text.len(),
+            Encoding::Utf16 => text.encode_utf16().count(),
+        }
+    }
+    /// Byte offset of the code unit in the text
+    pub fn to_byte(self, text: &str, pos: usize) -> usize {
+        match self {
+            Encoding::Utf8 => {
+                let mut pos = pos.min(text.len());
+                while !text.is_char_boundary(pos) {
+                    pos -= 1;
+                }
+                pos
+            }
+            Encoding::Utf16 => utf16_to_byte(text.chars(), pos),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SourceFile {
+    rope: Rope,
+    /// Contiguous text, created on demand after the edits
+    text: OnceLock<String>,
+    /// Line ranges as (start, end)
+    lines: Vec<(Size, Size)>,
+    encoding: Encoding,
+}
+impl SourceFile {
+    pub fn new(text: String) -> Self {
+        let mut val = Self {
+            rope: Rope::new(&text),
+            text: OnceLock::from(text),
+            lines: Vec::new(),
+            encoding: Encoding::default(),
+        };
+        val.compute_lines();
+        val
+    }
+    /// Uses the encoding for the character offsets of positions
+    pub fn with_encoding(mut self, encoding: Encoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+    pub fn encoding(&self) -> Encoding {
+        self.encoding
+    }
+
+    fn compute_lines(&mut self) {
+        self.lines = scan_lines(Size::zero(), self.text(), true);
+    }
+
+    /// The whole text, which is copied from the chunks after edits, prefer
+    /// [`Self::get`] and [`Self::len`] on frequent calls
+    pub fn text(&self) -> &str {
+        self.text.get_or_init(|| self.rope.to_string())
+    }
+    /// Length of the text in bytes
+    pub fn len(&self) -> usize {
+        self.rope.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    /// Text of the byte range, or None if it is out of bounds or not at
+    /// character boundaries
+    pub fn get(&self, range: Range<usize>) -> Option<Cow<'_, str>> {
+        self.rope.get(range)
+    }
+    pub fn lines(&self) -> &[(Size, Size)] {
+        &self.lines
+    }
+    pub fn line_start(&self, i: usize) -> Option<Size> {
+        self.lines.get(i).map(|(start, _)| *start)
+    }
+    pub fn line_end(&self, i: usize) -> Option<Size> {
+        self.lines.get(i).map(|(_, end)| *end)
+    }
+    pub fn line_range(&self, range: Range<usize>) -> Option<((Size, Size), Cow<'_, str>)> {
+        let start = self.line_start(range.start)?;
+        let end = self.line_end(range.end - 1)?;
+        Some(((start, end), self.rope.slice(start.byte..end.byte)))
+    }
+
+    /// Replaces the text and updates only the lines affected by the change
+    pub fn replace(&mut self, range: Range<usize>, text: &str) {
+        let last = self.lines.len() - 1;
+        let first = self
+            .lines
+            .partition_point(|(_, end)| end.byte <= range.start)
+            .min(last);
+        let end = self
+            .lines
+            .partition_point(|(_, end)| end.byte <= range.end)
+            .min(last);
+        let start = self.lines[first].0;
+        let old_end = self.lines[end].1;
+
+        self.rope.replace(range.clone(), text);
+        self.text = OnceLock::new();
+
+        let new_end = old_end.byte + text.len() - range.len();
+        let segment = self.rope.slice(start.byte..new_end);
+        let lines = scan_lines(start, &segment, end == last);
+        let new_end = lines.last().map_or(start, |(_, end)| *end);
+        for (line_start, line_end) in &mut self.lines[end + 1..] {
+            *line_start = *line_start - old_end + new_end;
+            *line_end = *line_end - old_end + new_end;
+        }
+        self.lines.splice(first..=end, lines);
+    }
+
+    /// Limits the lines to at most `max` paragraphs (separated by empty
+    /// lines), returns the end of the limited lines and their paragraphs
+    pub fn limit_paragraphs(&self, lines: Range<usize>, max: usize) -> (usize, usize) {
+        let mut paragraphs = 0;
+        let mut in_paragraph = false;
+        for i in lines.clone() {
+            let Some((_, text)) = self.line_range(i..i + 1) else {
+                break;
+            };
+            let empty = text.trim().is_empty();
+            if !empty && !in_paragraph {
+                if paragraphs == max {
+                    return (i, paragraphs);
+                }
+                paragraphs += 1;
+            }
+            in_paragraph = !empty;
+        }
+        (lines.end, paragraphs)
+    }
+
+    /// Convert a line/column position to a utf-8 byte offset.
+    ///
+    /// Characters behind the end of the line are moved to the end of its
+    /// content (before the line break), characters inside of a multi-byte
+    /// or surrogate pair character to its start.
+    pub fn to_offset(&self, pos: Position) -> Option<usize> {
+        let (l_start, l_end) = self.lines.get(pos.line as usize)?;
+        let line = self.rope.slice(l_start.byte..l_end.byte);
+        let content = line.strip_suffix('\n').unwrap_or(&line);
+        let content = content.strip_suffix('\r').unwrap_or(content);
+        let byte_offset = self.encoding.to_byte(content, pos.character as _);
+        Some(l_start.byte + byte_offset)
+    }
+
+    /// Convert a utf-8 byte offset to a line/column position, the end of the
+    /// text is a valid position and offsets inside of a character are moved
+    /// to its start
+    pub fn to_position(&self, offset: usize) -> Option<Position> {
+        if offset > self.rope.len() {
+            return None;
+        }
+        let line = self
+            .lines
+            .partition_point(|(_, end)| end.byte <= offset)
+            .min(self.lines.len() - 1);
+        let (l_start, l_end) = self.lines[line];
+
+        let line_text = self.rope.slice(l_start.byte..l_end.byte);
+        let mut end = offset - l_start.byte;
+        while !line_text.is_char_boundary(end) {
+            end -= 1;
+        }
+        let character = self.encoding.len(&line_text[..end]) as _;
+
+        Some(Position {
+            line: line as _,
+            character,
+        })
+    }
+}
+
+/// Line ranges of the text, which starts at `start`. The lines include their
+/// line endings, the last one is empty if the text ends with a line break.
+fn scan_lines(start: Size, text: &str, is_last: bool) -> Vec<(Size, Size)> {
+    let mut lines = Vec::new();
+    let mut last = start;
+    for line in text.split_inclusive('\n') {
+        let curr = last;
+        last += Size::new(line);
+        lines.push((curr, last));
+    }
+    if is_last && (text.is_empty() || text.ends_with('\n')) {
+        lines.push((last, last));
+    }
+    lines
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Size {
+    pub byte: usize,
+    pub utf16: usize,
+}
+impl Size {
+    pub fn new(s: &str) -> Self {
+        Self {
+            byte: s.len(),
+            utf16: s.encode_utf16().count(),
+        }
+    }
+    pub fn zero() -> Self {
+        Self { byte: 0, utf16: 0 }
+    }
+}
+impl Add for Size {
+    type Output = Self;
+    fn add(self, rhs: Self) -> Self::Output {
+        Self {
+            byte: self.byte + rhs.byte,
+            utf16: self.utf16 + rhs.utf16,
+        }
+    }
+}
+impl AddAssign for Size {
+    fn add_assign(&mut self, rhs: Self) {
+        self.byte += rhs.byte;
+        self.utf16 += rhs.utf16;
+    }
+}
+impl Sub for Size {
+    type Output = Self;
+    fn sub(self, rhs: Self) -> Self::Output {
+        Self {
+            byte: self.byte - rhs.byte,
+            utf16: self.utf16 - rhs.utf16,
+        }
+    }
+}
+impl SubAssign for Size {
+    fn sub_assign(&mut self, rhs: Self) {
+        self.byte -= rhs.byte;
+        self.utf16 -= rhs.utf16;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use proptest::prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn test_lines() {
+        let file = SourceFile::new("Hello\nWorld\n".into());
+        let [a, b, c] = file.lines.try_into().unwrap();
+        assert_eq!(a.0.byte, 0);
+        assert_eq!(a.1.byte, 6);
+        assert_eq!(b.0.byte, 6);
+        assert_eq!(b.1.byte, 12);
+        assert_eq!(c.0.byte, 12);
+        assert_eq!(c.1.byte, 12);
+
+        let file = SourceFile::new("Hello\nWorld\nFoo".into());
+        let [a, b, c] = file.lines.try_into().unwrap();
+        assert_eq!(a.0.byte, 0);
+        assert_eq!(a.1.byte, 6);
+        assert_eq!(b.0.byte, 6);
+        assert_eq!(b.1.byte, 12);
+        assert_eq!(c.0.byte, 12);
+        assert_eq!(c.1.byte, 15);
+
+        let file = SourceFile::new("Hello\r\nWorld\r\nFoo".into());
+        let [a, b, c] = file.lines.try_into().unwrap();
+        assert_eq!(a.0.byte, 0);
+        assert_eq!(a.1.byte, 7);
+        assert_eq!(b.0.byte, 7);
+        assert_eq!(b.1.byte, 14);
+        assert_eq!(c.0.byte, 14);
+        assert_eq!(c.1.byte, 17);
+
+        let file = SourceFile::new("▲\nWorld\n".into());
+        let [a, b, c] = file.lines.try_into().unwrap();
+        assert_eq!(a.0.byte, 0);
+        assert_eq!(a.1.byte, 4);
+        assert_eq!(a.1.utf16, 2);
+        assert_eq!(b.0.byte, 4);
+        assert_eq!(b.1.byte, 10);
+        assert_eq!(b.1.utf16, 8);
+        assert_eq!(c.0.byte, 10);
+        assert_eq!(c.1.byte, 10);
+    }
+
+    #[test]
+    fn limit_paragraphs() {
+        let file = SourceFile::new("A\nB\n\nC\n\n\nD\nE".into());
+        assert_eq!(file.limit_paragraphs(0..8, 1), (3, 1));
+        assert_eq!(file.limit_paragraphs(0..8, 2), (6, 2));
+        assert_eq!(file.limit_paragraphs(3..8, 1), (6, 1));
+        assert_eq!(file.limit_paragraphs(0..8, 3), (8, 3));
+        assert_eq!(file.limit_paragraphs(0..8, 0), (0, 0));
+    }
+
+    #[test]
+    fn encoding() {
+        let text = "a😀b\nä😀c";
+        let utf16 = SourceFile::new(text.into());
+        let utf8 = SourceFile::new(text.into()).with_encoding(Encoding::Utf8);
+        let offset = text.find('c').unwrap();
+        assert_eq!(utf16.to_position(offset), Some(Position::new(1, 3)));
+        assert_eq!(utf8.to_position(offset), Some(Position::new(1, 6)));
+        assert_eq!(utf16.to_offset(Position::new(1, 3)), Some(offset));
+        assert_eq!(utf8.to_offset(Position::new(1, 6)), Some(offset));
+        // Positions inside of a character are moved to its start
+        assert_eq!(utf8.to_offset(Position::new(0, 3)), Some(1));
+    }
+
+    #[test]
+    fn positions() {
+        // Astral characters, combining marks and CRLF line breaks
+        let text = "a😀\u{301}b\r\ncd\r\n";
+        let file = SourceFile::new(text.into());
+        let b = text.find('b').unwrap();
+        assert_eq!(file.to_position(b), Some(Position::new(0, 4)));
+        assert_eq!(file.to_offset(Position::new(0, 4)), Some(b));
+        // Inside of the surrogate pair or the UTF-8 bytes of the emoji
+        assert_eq!(file.to_offset(Position::new(0, 2)), Some(1));
+        assert_eq!(file.to_position(2), Some(Position::new(0, 1)));
+        // Behind the end of the line
+        assert_eq!(file.to_offset(Position::new(0, 100)), Some(b + 1));
+        assert_eq!(file.to_offset(Position::new(1, 100)), Some(text.len() - 2));
+        // End of the text
+        assert_eq!(file.to_position(text.len()), Some(Position::new(2, 0)));
+        assert_eq!(file.to_position(text.len() + 1), None);
+        let file = SourceFile::new("ab\nc😀".into());
+        assert_eq!(file.to_position(8), Some(Position::new(1, 3)));
+        assert_eq!(
+            SourceFile::new(String::new()).to_position(0),
+            Some(Position::new(0, 0))
+        );
+    }
+
+    /// Texts with multi-byte, astral and combining characters and line breaks
+    fn text() -> impl Strategy<Value = String> {
+        const PARTS: &[&str] = &["a", "ä", "e\u{301}", "😀", "👩‍👩‍👧", "漢", " ", "\n", "\r\n"];
+        prop::collection::vec(prop::sample::select(PARTS), 0..40).prop_map(|p| p.concat())
+    }
+
+    proptest! {
+        #[test]
+        fn position_roundtrip(text in text(), utf8 in any::<bool>()) {
+            let encoding = if utf8 { Encoding::Utf8 } else { Encoding::Utf16 };
+            let file = SourceFile::new(text.clone()).with_encoding(encoding);
+            for offset in 0..=text.len() {
+                let pos = file.to_position(offset).unwrap();
+                let start = (0..=offset).rev().find(|i| text.is_char_boundary(*i)).unwrap();
+                let before = &text[..start];
+                prop_assert_eq!(pos.line as usize, before.matches('\n').count());
+                let line = &before[before.rfind('\n').map_or(0, |i| i + 1)..];
+                prop_assert_eq!(pos.character as usize, encoding.len(line));
+                // Between CR and LF is moved before the line break
+                let expected = if text[start..].starts_with('\n') && line.ends_with('\r') {
+                    start - 1
+                } else {
+                    start
+                };
+                prop_assert_eq!(file.to_offset(pos), Some(expected));
+            }
+        }
+
+        #[test]
+        fn utf16_offsets(text in text(), pos in 0usize..100) {
+            let byte = utf16_to_byte(text.chars(), pos);
+            prop_assert!(text.is_char_boundary(byte));
+            prop_assert!(text[..byte].encode_utf16().count() <= pos);
+            let next = text[byte..].chars().next();
+            prop_assert!(next.is_none_or(|c| text[..byte].encode_utf16().count() + c.len_utf16() > pos));
+        }
+
+        #[test]
+        fn replace_lines(text in text(), edits in prop::collection::vec((any::<prop::sample::Index>(), any::<prop::sample::Index>(), text()), 1..5)) {
+            let mut file = SourceFile::new(text);
+            for (a, b, insert) in edits {
+                let boundaries = (0..=file.text().len())
+                    .filter(|i| file.text().is_char_boundary(*i))
+                    .collect::<Vec<_>>();
+                let (a, b) = (*a.get(&boundaries), *b.get(&boundaries));
+                file.replace(a.min(b)..a.max(b), &insert);
+                let expected = SourceFile::new(file.text().to_string());
+                prop_assert_eq!(&file.lines, &expected.lines);
+            }
+        }
+    }
+
+    #[test]
+    fn replace() {
+        let mut file = SourceFile::new("Hello\nWörld\r\n\nFoo".into());
+        let edits = [
+            (0..0, "A"),
+            (3..8, ""),
+            (2..2, "x\ny\n"),
+            (0..0, "\n"),
+            (4..4, "ä😀"),
+            (1..1, "\r\n"),
+            (0..4, ""),
+            (2..23, ""),
+            (2..2, "\n"),
+        ];
+        for (range, text) in edits {
+            file.replace(range, text);
+            let expected = SourceFile::new(file.text().to_string());
+            assert_eq!(file.lines, expected.lines, "{:?}", file.text());
+        }
+    }
+}
@@ -0,0 +1,193 @@
+use std::ops::Range;
+
+use regex::Regex;
+
+/// Byte offset of the UTF-16 position, positions inside of a surrogate pair
+/// are moved to the start of the character
+pub fn utf16_to_byte(chars: impl Iterator<Item = char>, utf16_pos: usize) -> usize {
+    let mut byte_offset = 0;
+    let mut utf16_offset = 0;
+    for c in chars {
+        utf16_offset += c.len_utf16();
+        if utf16_offset > utf16_pos {
+            break;
+        }
+        byte_offset += c.len_utf8();
+    }
+    byte_offset
+}
+
+/// Split the text into sentences, ending at punctuation followed by
+/// whitespace or at empty lines.
+pub fn sentences(text: &str) -> Vec<Range<usize>> {
+    let mut sentences = Vec::new();
+    let mut start = 0;
+    let mut chars = text.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        let next = chars.peek().map(|(_, c)| *c);
+        let end = match c {
+            '.' | '!' | '?' => next.is_none_or(char::is_whitespace),
+            '\n' => next == Some('\n'),
+            _ => false,
+        };
+        if end {
+            sentences.push(start..i + c.len_utf8());
+            start = i + c.len_utf8();
+        }
+    }
+    sentences.push(start..text.len());
+
+    // Trim surrounding whitespace and drop empty sentences
+    sentences
+        .into_iter()
+        .filter_map(|range| {
+            let sentence = &text[range.clone()];
+            let trimmed = sentence.trim_start();
+            let start = range.start + sentence.len() - trimmed.len();
+            let end = start + trimmed.trim_end().len();
+            (start < end).then_some(start..end)
+        })
+        .collect()
+}
+
+/// Returns the range of the sentences that contain the changed byte ranges,
+/// the whole text if there are no changes
+pub fn sentence_span(text: &str, changes: &[Range<usize>]) -> Range<usize> {
+    let sentences = sentences(text);
+    let mut span: Option<Range<usize>> = None;
+    for change in changes {
+        let start = sentences
+            .iter()
+            .rev()
+            .find(|s| s.start <= change.start)
+            .map_or(0, |s| s.start);
+        let end = sentences
+            .iter()
+            .find(|s| s.end >= change.end)
+            .map_or(text.len(), |s| s.end);
+        span = Some(match span {
+            Some(span) => span.start.min(start)..span.end.max(end),
+            None => start..end,
+        });
+    }
+    span.unwrap_or(0..text.len())
+}
+
+/// Returns the range of the word that contains the byte offset
+pub fn word_at(text: &str, offset: usize) -> Option<Range<usize>> {
+    let start = text[..offset]
+        .char_indices()
+        .rev()
+        .find(|(_, c)| !c.is_alphanumeric())
+        .map_or(0, |(i, c)| i + c.len_utf8());
+    let end = text[offset..]
+        .find(|c: char| !c.is_alphanumeric())
+        .map_or(text.len(), |i| offset + i);
+    (start < end).then_some(start..end)
+}
+
+/// Checks if the language tag matches the language code
+/// (e.g. "de-DE" matches the tags "de" and "de-DE").
+pub fn language_matches(tag: &str, language: &str) -> bool {
+    language == tag
+        || language
+            .strip_prefix(tag)
+            .is_some_and(|rest| rest.starts_with('-'))
+}
+
+pub trait RangeExt {
+    fn touches(&self, other: &Self) -> bool;
+}
+
+impl RangeExt for Range<usize> {
+    fn touches(&self, other: &Self) -> bool {
+        self.start <= other.end && other.start <= self.end
+    }
+}
+
+/// Converts a glob pattern like `docs/**/*.{md,txt}` into a regex for paths
+/// relative to the workspace with `/` as separator.
+pub fn glob(pattern: &str) -> Result<Regex, regex::Error> {
+    let mut regex = String::from("^");
+    let mut groups = 0;
+    let mut chars = pattern.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                if chars.next_if_eq(&'/').is_some() {
+                    regex.push_str("(?:.*/)?");
+                } else {
+                    regex.push_str(".*");
+                }
+            }
+            '*' => regex.push_str("[^/]*"),
+            '?' => regex.push_str("[^/]"),
+            '{' => {
+                groups += 1;
+                regex.push_str("(?:");
+            }
+            '}' if groups > 0 => {
+                groups -= 1;
+                regex.push(')');
+            }
+            ',' if groups > 0 => regex.push('|'),
+            c => regex.push_str(&regex::escape(c.encode_utf8(&mut [0; 4]))),
+        }
+    }
+    regex.push('$');
+    Regex::new(&regex)
+}
+
+/// Orders the replacements that keep the capitalization of the original
+/// text first, otherwise keeping the order of the server
+pub fn sort_by_case(original: &str, replacements: &mut [String]) {
+    let case = |s: &str| {
+        (
+            s.chars().next().is_some_and(char::is_uppercase),
+            s.chars().any(char::is_lowercase),
+        )
+    };
+    replacements.sort_by_key(|r| case(r) != case(original));
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn sentence_span() {
+        let text = "First one. Second one! Third\none? Fourth.";
+        let span = |changes: &[(usize, usize)]| {
+            let changes = changes.iter().map(|(s, e)| *s..*e).collect::<Vec<_>>();
+            &text[super::sentence_span(text, &changes)]
+        };
+        assert_eq!(span(&[(13, 14)]), "Second one!");
+        assert_eq!(span(&[(13, 14), (25, 25)]), "Second one! Third\none?");
+        // Between sentences
+        assert_eq!(span(&[(10, 11)]), "First one. Second one!");
+        assert_eq!(span(&[]), text);
+    }
+
+    #[test]
+    fn case() {
+        let mut replacements = ["the".to_string(), "The".into(), "THE".into(), "Then".into()];
+        sort_by_case("Teh", &mut replacements);
+        assert_eq!(replacements, ["The", "Then", "the", "THE"]);
+        sort_by_case("TEH", &mut replacements);
+        assert_eq!(replacements, ["THE", "The", "Then", "the"]);
+    }
+
+    #[test]
+    fn glob_match() {
+        let md = glob("**/*.{md,txt}").unwrap();
+        assert!(md.is_match("README.md"));
+        assert!(md.is_match("docs/guide/intro.txt"));
+        assert!(!md.is_match("docs/main.rs"));
+
+        let docs = glob("docs/*.md").unwrap();
+        assert!(docs.is_match("docs/a.md"));
+        assert!(!docs.is_match("docs/sub/a.md"));
+        assert!(!docs.is_match("docs.md"));
+    }
+}
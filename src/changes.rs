@@ -1,5 +1,6 @@
 use std::ops::Range;
 
+#[derive(Default)]
 pub struct Changes {
     changes: Vec<Range<usize>>,
 }
@@ -0,0 +1,348 @@
+//! Checks files from the command line with the same pipeline as the server,
+//! e.g. for scripts and pre-commit hooks.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Result, anyhow};
+use clap::ValueEnum;
+use languagetool_lsp_core::annotated::{self, magic};
+use languagetool_lsp_core::api::{self, Match, Severity};
+use languagetool_lsp_core::dictionary::{self, Dictionary, DictionaryFile};
+use languagetool_lsp_core::false_positives::{self, FalsePositives};
+use languagetool_lsp_core::local::{self, known_words::KnownWords};
+use languagetool_lsp_core::settings::Settings;
+use languagetool_lsp_core::source::SourceFile;
+use serde_json::{Value, json};
+
+use crate::learning::Learning;
+use crate::{pipeline, project, user_dictionary};
+
+/// Output format of the results
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    /// One `file:line:column: message [rule]` line per issue
+    #[default]
+    Text,
+    /// Array of the issues with their positions and replacements
+    Json,
+    /// SARIF log, e.g. for GitHub code scanning
+    Sarif,
+}
+
+/// Match of a checked file
+struct Finding {
+    file: String,
+    /// One-based line and character column of the start and end
+    start: (usize, usize),
+    end: (usize, usize),
+    m: Match,
+}
+
+/// Check the files and print their matches in the given format, returns the
+/// number of matches
+pub async fn check(files: &[PathBuf], format: Format, offline_stub: bool) -> Result<usize> {
+    // The files may be checked from outside of their project, e.g. by editors
+    let cwd = std::env::current_dir()?;
+    let root = files
+        .first()
+        .and_then(|file| std::path::absolute(file).ok())
+        .and_then(|file| project_root(file.parent()?))
+        .or_else(|| project_root(&cwd))
+        .unwrap_or(cwd);
+    let project = project::load_settings(&root)?;
+    let (mut settings, mut errors) = Settings::parse(project, &Settings::default());
+    settings.offline_stub |= offline_stub;
+    errors.extend(settings.validate());
+    if !errors.is_empty() {
+        return Err(anyhow!("Invalid settings: {}", errors.join("; ")));
+    }
+    let terminology = project::path(&root, local::terminology::FILE);
+    settings
+        .terminology
+        .extend(local::terminology::load(&terminology)?);
+
+    let mut dict = Dictionary::default();
+    for word in &settings.dictionary {
+        dict.insert(word, None);
+    }
+    let dict_files = user_dictionary()
+        .into_iter()
+        .chain([project::path(&root, dictionary::FILE)]);
+    for path in dict_files {
+        dict.extend(&DictionaryFile::load(&path)?);
+    }
+    let known_words = if settings.word_lists.is_empty() {
+        None
+    } else {
+        let paths = settings.word_lists.iter().map(|p| root.join(p));
+        Some(KnownWords::load(&paths.collect::<Vec<_>>())?)
+    };
+    let false_positives = FalsePositives::load(&project::path(&root, false_positives::FILE))?;
+    let learning = Learning::load();
+    let filter = pipeline::Filter {
+        dictionary: &dict,
+        known_words: known_words.as_ref(),
+        false_positives: &false_positives,
+        learning: settings.learning.then_some(&learning),
+    };
+
+    let mut findings = Vec::new();
+    for path in files {
+        let Some(language_id) = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .and_then(annotated::language_id)
+        else {
+            eprintln!("{}: unknown file type", path.display());
+            continue;
+        };
+        let absolute = std::path::absolute(path)?;
+        let relative = absolute.strip_prefix(&root).unwrap_or(&absolute);
+        let relative = relative.to_string_lossy().replace('\\', "/");
+        if !settings.is_checked(language_id, &relative) {
+            continue;
+        }
+        let source = SourceFile::new(std::fs::read_to_string(path)?);
+        if settings.max_file_size > 0 && source.len() > settings.max_file_size {
+            eprintln!("{}: file too large", path.display());
+            continue;
+        }
+
+        let mut settings = settings.clone();
+        if let Some(language) = settings.document_language(language_id, &relative) {
+            settings.static_language = Some(language.to_string());
+        }
+        let mut settings = magic::overrides(source.text(), &settings);
+        settings.scope(language_id).apply(&mut settings);
+
+        let lines = 0..source.lines().len();
+        let (range, annot) = pipeline::annotate(language_id, &source, lines, &settings)?;
+        if annot.is_empty() {
+            continue;
+        }
+
+        let local_matches = local::check(&annot, range.start, &settings, language_id);
+        let result = api::check_auto(annot, range.start, &settings).await?;
+        let mut matches = result.matches;
+        matches.extend(local_matches);
+        filter.apply(
+            &mut matches,
+            &source,
+            &settings,
+            language_id,
+            &result.language,
+        );
+        matches.sort_by_key(|m| m.range.start);
+
+        let text = source.text();
+        for m in matches {
+            findings.push(Finding {
+                file: path.to_string_lossy().replace('\\', "/"),
+                start: position(text, m.range.start),
+                end: position(text, m.range.end),
+                m,
+            });
+        }
+    }
+
+    match format {
+        Format::Text => {
+            for finding in &findings {
+                println!("{}", format_text(finding));
+            }
+        }
+        Format::Json => println!("{}", serde_json::to_string_pretty(&to_json(&findings))?),
+        Format::Sarif => println!("{}", serde_json::to_string_pretty(&to_sarif(&findings))?),
+    }
+    Ok(findings.len())
+}
+
+/// The nearest ancestor with a project directory
+fn project_root(dir: &Path) -> Option<PathBuf> {
+    dir.ancestors()
+        .find(|d| d.join(project::DIR).is_dir())
+        .map(Path::to_path_buf)
+}
+
+/// One-based line and character column of the byte offset
+fn position(text: &str, offset: usize) -> (usize, usize) {
+    let before = &text[..offset];
+    let line = before.matches('\n').count() + 1;
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    (line, before[line_start..].chars().count() + 1)
+}
+
+/// Number of suggested replacements in the output
+const REPLACEMENTS: usize = 5;
+
+fn format_text(finding: &Finding) -> String {
+    let Finding { file, start, m, .. } = finding;
+    let (line, column) = start;
+    let mut out = format!("{file}:{line}:{column}: {} [{}]", m.message, m.rule);
+    if !m.replacements.is_empty() {
+        let replacements = m.replacements.iter().take(REPLACEMENTS).collect::<Vec<_>>();
+        out.push_str(&format!(" -> {replacements:?}"));
+    }
+    out
+}
+
+fn to_json(findings: &[Finding]) -> Value {
+    let findings = findings.iter().map(
+        |Finding {
+             file,
+             start,
+             end,
+             m,
+         }| {
+            json!({
+                "file": file,
+                "line": start.0,
+                "column": start.1,
+                "endLine": end.0,
+                "endColumn": end.1,
+                "rule": m.rule,
+                "category": m.category,
+                "severity": m.level(),
+                "message": m.message,
+                "replacements": m.replacements.iter().take(REPLACEMENTS).collect::<Vec<_>>(),
+            })
+        },
+    );
+    Value::Array(findings.collect())
+}
+
+/// Converts the findings into a SARIF 2.1.0 log
+fn to_sarif(findings: &[Finding]) -> Value {
+    let mut rules = Vec::<&Match>::new();
+    for finding in findings {
+        if !rules.iter().any(|m| m.rule == finding.m.rule) {
+            rules.push(&finding.m);
+        }
+    }
+    let rules = rules.iter().map(|m| {
+        let description = if m.details.description.is_empty() {
+            &m.title
+        } else {
+            &m.details.description
+        };
+        let mut rule = json!({
+            "id": m.rule,
+            "shortDescription": { "text": description },
+            "properties": { "category": m.category },
+        });
+        if let Some(url) = m.details.page.as_ref().or(m.details.urls.first()) {
+            rule["helpUri"] = json!(url);
+        }
+        rule
+    });
+
+    let results = findings.iter().map(
+        |Finding {
+             file,
+             start,
+             end,
+             m,
+         }| {
+            let location = json!({ "uri": file });
+            let region = json!({
+                "startLine": start.0,
+                "startColumn": start.1,
+                "endLine": end.0,
+                "endColumn": end.1,
+            });
+            let fixes = m.replacements.iter().take(REPLACEMENTS).map(|replacement| {
+                json!({
+                    "description": { "text": format!("Replace with \"{replacement}\"") },
+                    "artifactChanges": [{
+                        "artifactLocation": location,
+                        "replacements": [{
+                            "deletedRegion": region,
+                            "insertedContent": { "text": replacement },
+                        }],
+                    }],
+                })
+            });
+            json!({
+                "ruleId": m.rule,
+                "level": match m.level() {
+                    Severity::Error => "error",
+                    Severity::Warning => "warning",
+                    Severity::Information | Severity::Hint => "note",
+                },
+                "message": { "text": m.message },
+                "locations": [{
+                    "physicalLocation": {
+                        "artifactLocation": location,
+                        "region": region,
+                    },
+                }],
+                "fixes": fixes.collect::<Vec<_>>(),
+            })
+        },
+    );
+
+    json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": env!("CARGO_PKG_NAME"),
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "informationUri": "https://github.com/wrenger/languagetool-lsp",
+                    "rules": rules.collect::<Vec<_>>(),
+                },
+            },
+            "columnKind": "unicodeCodePoints",
+            "results": results.collect::<Vec<_>>(),
+        }],
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn finding() -> Finding {
+        let text = "First line\nSecönd lne";
+        let m = Match {
+            range: 19..22,
+            title: String::new(),
+            message: "Possible spelling mistake".into(),
+            replacements: vec!["line".into()],
+            category: "TYPOS".into(),
+            rule: "SPELLING".into(),
+            severity: None,
+            details: Default::default(),
+        };
+        Finding {
+            file: "a.md".into(),
+            start: position(text, m.range.start),
+            end: position(text, m.range.end),
+            m,
+        }
+    }
+
+    #[test]
+    fn format() {
+        assert_eq!(
+            format_text(&finding()),
+            "a.md:2:8: Possible spelling mistake [SPELLING] -> [\"line\"]"
+        );
+
+        let json = to_json(&[finding()]);
+        assert_eq!(json[0]["line"], 2);
+        assert_eq!(json[0]["endColumn"], 11);
+        assert_eq!(json[0]["severity"], "warning");
+
+        let sarif = to_sarif(&[finding(), finding()]);
+        let run = &sarif["runs"][0];
+        assert_eq!(run["tool"]["driver"]["rules"].as_array().unwrap().len(), 1);
+        let result = &run["results"][1];
+        assert_eq!(result["ruleId"], "SPELLING");
+        assert_eq!(result["level"], "warning");
+        let fix = &result["fixes"][0]["artifactChanges"][0]["replacements"][0];
+        assert_eq!(fix["deletedRegion"]["startColumn"], 8);
+        assert_eq!(fix["insertedContent"]["text"], "line");
+    }
+}
@@ -0,0 +1,175 @@
+//! Runs the LanguageTool server in a Docker container, which is restarted if
+//! it exits and removed together with the language server.
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::{Result, anyhow};
+use languagetool_lsp_core::api;
+use languagetool_lsp_core::settings::{DockerSettings, Settings};
+use tokio::process::Command;
+use tokio::sync::watch;
+use tracing::{error, info, warn};
+
+/// Port of the server inside of the container
+const CONTAINER_PORT: u16 = 8010;
+/// Interval of the readiness polling
+const POLL: Duration = Duration::from_secs(1);
+
+pub struct Docker {
+    /// Settings of the running container
+    settings: Mutex<Option<DockerSettings>>,
+    /// The server of the container answers requests
+    ready: watch::Sender<bool>,
+}
+
+impl Default for Docker {
+    fn default() -> Self {
+        Self {
+            settings: Mutex::new(None),
+            ready: watch::Sender::new(false),
+        }
+    }
+}
+
+impl Docker {
+    /// Starts the container in the background, unless it already runs with
+    /// the same settings
+    pub fn start(self: &Arc<Self>, settings: &Settings) {
+        let mut current = self.settings.lock().unwrap();
+        if current.as_ref() == Some(&settings.docker) {
+            return;
+        }
+        *current = Some(settings.docker.clone());
+        self.ready.send_replace(false);
+        tokio::spawn(supervise(self.clone(), settings.clone()));
+    }
+
+    /// Stops and removes the container
+    pub async fn stop(&self) {
+        if self.settings.lock().unwrap().take().is_none() {
+            return;
+        }
+        self.ready.send_replace(false);
+        info!("Stop the LanguageTool container");
+        if let Err(err) = remove().await {
+            warn!("Failed to remove the container: {err}");
+        }
+    }
+
+    /// Waits until the server of the container is ready
+    pub async fn wait_ready(&self, timeout: Duration) -> Result<()> {
+        let mut ready = self.ready.subscribe();
+        tokio::time::timeout(timeout, ready.wait_for(|ready| *ready))
+            .await
+            .map_err(|_| anyhow!("The LanguageTool container is not ready"))??;
+        Ok(())
+    }
+
+    fn is_current(&self, settings: &DockerSettings) -> bool {
+        self.settings.lock().unwrap().as_ref() == Some(settings)
+    }
+}
+
+/// Runs the container, polls the server until it is ready and restarts the
+/// container if it exits, until the settings change
+async fn supervise(docker: Arc<Docker>, settings: Settings) {
+    // Fail fast while polling
+    let mut poll = settings.clone();
+    poll.max_retries = 0;
+    poll.connect_timeout = poll.connect_timeout.min(1.0);
+
+    loop {
+        if let Err(err) = run(&settings.docker).await {
+            error!("Failed to start the LanguageTool container: {err}");
+            if docker.is_current(&settings.docker) {
+                docker.settings.lock().unwrap().take();
+            }
+            return;
+        }
+        let poll_ready = async {
+            while api::languages::list(&poll).await.is_err() {
+                tokio::time::sleep(POLL).await;
+            }
+        };
+        let ready = tokio::select! {
+            () = poll_ready => true,
+            () = wait() => false,
+        };
+        if ready {
+            info!("LanguageTool container ready");
+            docker.ready.send_replace(true);
+            wait().await;
+        }
+
+        // Stopped or replaced by a container with other settings
+        if !docker.is_current(&settings.docker) {
+            return;
+        }
+        docker.ready.send_replace(false);
+        if !ready {
+            error!("The LanguageTool container exited during the startup");
+            docker.settings.lock().unwrap().take();
+            return;
+        }
+        warn!("The LanguageTool container exited, restart it");
+    }
+}
+
+/// Name of the container of this language server
+fn name() -> String {
+    format!("languagetool-lsp-{}", std::process::id())
+}
+
+async fn run(settings: &DockerSettings) -> Result<()> {
+    // Replace a previous container of this process
+    remove().await?;
+    let image = format!("{}:{}", settings.image, settings.tag);
+    info!("Start the LanguageTool container {image}");
+    let publish = format!("127.0.0.1:{}:{CONTAINER_PORT}", settings.port);
+    docker(&[
+        "run",
+        "--detach",
+        "--rm",
+        "--name",
+        &name(),
+        "--publish",
+        &publish,
+        &image,
+    ])
+    .await
+}
+
+/// Waits until the container exits
+async fn wait() {
+    if let Err(err) = docker(&["wait", &name()]).await {
+        warn!("Failed to wait for the container: {err}");
+    }
+}
+
+async fn remove() -> Result<()> {
+    let name = name();
+    let output = Command::new("docker")
+        .args([
+            "ps",
+            "--all",
+            "--quiet",
+            "--filter",
+            &format!("name=^{name}$"),
+        ])
+        .output()
+        .await?;
+    if output.stdout.is_empty() {
+        return Ok(());
+    }
+    docker(&["rm", "--force", &name]).await
+}
+
+async fn docker(args: &[&str]) -> Result<()> {
+    let output = Command::new("docker").args(args).output().await?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(anyhow!("docker {}: {}", args[0], stderr.trim()));
+    }
+    Ok(())
+}
@@ -0,0 +1,246 @@
+//! Localized user-facing messages.
+//!
+//! The language is selected by the client's locale. Messages without a
+//! translation fall back to English.
+
+use std::fmt::Display;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Lang {
+    #[default]
+    En,
+    De,
+}
+
+impl Lang {
+    /// Parses locales like "de", "de-AT" or "de_DE.UTF-8"
+    pub fn from_locale(locale: &str) -> Self {
+        let tag = locale.split(['-', '_', '.']).next().unwrap_or_default();
+        match tag.to_lowercase().as_str() {
+            "de" => Self::De,
+            _ => Self::En,
+        }
+    }
+}
+
+pub enum Msg<'a> {
+    Accept,
+    ReplaceWith(&'a str),
+    AddToDictionary,
+    AddWordToDictionary(&'a str),
+    IgnoreLints,
+    IgnorePermanently,
+    FixAll,
+    DisableNextLine(&'a str),
+    DisableRule(&'a str),
+    DisableCategory(&'a str),
+    CheckSpelling,
+    Checking,
+    Synonyms,
+    Suggestions,
+    MoreInformation,
+    RulePage,
+    SynonymsFor(&'a str),
+    NoSynonyms(&'a str),
+    NoSynonymsFor(&'a str),
+    RephraseSentence,
+    NoRephrasings,
+    Autocorrected(&'a str),
+    Offline,
+    Online,
+    FailedOver(&'a str),
+    ServerRestored(&'a str),
+    RuleIgnored(&'a str),
+    RulesUpdated,
+    RulesRequireWorkspace,
+    DictionaryRequiresWorkspace,
+    IgnoreRequiresWorkspace,
+    CheckRequiresWorkspace,
+    InvalidSettings(&'a dyn Display),
+    UnsupportedLanguages(&'a str),
+    ServerUnreachable(&'a str),
+    FileTooLarge(&'a str),
+    MoreIssues(usize),
+    ServerVersion(&'a str, &'a str, bool),
+    DictionaryStatus(usize, usize, usize),
+    PremiumOnly,
+    PremiumNotActive,
+    AddedRemote(&'a str),
+    AddedLocal(&'a str),
+    WordsSynced(usize, usize),
+}
+
+impl Msg<'_> {
+    pub fn text(&self, lang: Lang) -> String {
+        match lang {
+            Lang::En => None,
+            Lang::De => self.de(),
+        }
+        .unwrap_or_else(|| self.en())
+    }
+
+    fn en(&self) -> String {
+        match self {
+            Self::Accept => "Accept".into(),
+            Self::ReplaceWith(text) => format!("Replace with “{text}”"),
+            Self::AddToDictionary => "Add to Dictionary".into(),
+            Self::AddWordToDictionary(word) => format!("Add {word:?} to Dictionary"),
+            Self::IgnoreLints => "Ignore Lints".into(),
+            Self::IgnorePermanently => "Ignore this False Positive Permanently".into(),
+            Self::FixAll => "Fix All".into(),
+            Self::DisableNextLine(rule) => format!("Disable {rule} for this Line"),
+            Self::DisableRule(rule) => format!("Disable {rule}"),
+            Self::DisableCategory(category) => format!("Disable Category {category}"),
+            Self::CheckSpelling => "Check Spelling".into(),
+            Self::Checking => "Checking".into(),
+            Self::Synonyms => "Synonyms".into(),
+            Self::Suggestions => "Suggestions".into(),
+            Self::MoreInformation => "More information".into(),
+            Self::RulePage => "Rule details".into(),
+            Self::SynonymsFor(word) => format!("Synonyms for {word:?}"),
+            Self::NoSynonyms(language) => format!("No synonym service for {language}"),
+            Self::NoSynonymsFor(word) => format!("No synonyms for {word:?}"),
+            Self::RephraseSentence => "Rephrase sentence".into(),
+            Self::NoRephrasings => "No alternative formulations found".into(),
+            Self::Autocorrected(summary) => format!("Autocorrected: {summary}"),
+            Self::Offline => "LanguageTool is unreachable, pending checks are queued".into(),
+            Self::Online => "LanguageTool is reachable again".into(),
+            Self::FailedOver(server) => {
+                format!("The server is unavailable, {server} is used until it is reachable again")
+            }
+            Self::ServerRestored(server) => format!("The server {server} is used again"),
+            Self::RuleIgnored(rule) => {
+                format!("{rule} was ignored repeatedly, consider disabling it")
+            }
+            Self::RulesUpdated => "Updated the project rules".into(),
+            Self::RulesRequireWorkspace => "The rule browser requires a workspace".into(),
+            Self::DictionaryRequiresWorkspace => "The dictionary file requires a workspace".into(),
+            Self::IgnoreRequiresWorkspace => {
+                "Ignoring false positives permanently requires a workspace".into()
+            }
+            Self::CheckRequiresWorkspace => "The workspace check requires a workspace".into(),
+            Self::InvalidSettings(err) => format!("Invalid settings: {err}"),
+            Self::UnsupportedLanguages(codes) => {
+                format!("Languages not supported by the server: {codes}")
+            }
+            Self::ServerUnreachable(server) => format!("The server {server} is not reachable"),
+            Self::FileTooLarge(name) => format!("{name} is too large and is not checked"),
+            Self::MoreIssues(count) => format!("{count} more issues are not shown"),
+            Self::ServerVersion(name, version, premium) => {
+                let premium = if *premium { " (premium)" } else { "" };
+                format!("Connected to {name} {version}{premium}")
+            }
+            Self::DictionaryStatus(words, known, ignored) => {
+                format!("{words} dictionary words, {known} known words, {ignored} ignored matches")
+            }
+            Self::PremiumOnly => "Syncing words is only supported for premium users".into(),
+            Self::PremiumNotActive => "The server did not accept the username and API key \
+                as premium account, only the free rules are used. Check the credentials and \
+                the server (https://api.languagetoolplus.com)."
+                .into(),
+            Self::AddedRemote(word) => format!("Added {word:?} to remote dictionary"),
+            Self::AddedLocal(word) => format!("Added {word:?} to local dictionary"),
+            Self::WordsSynced(pushed, pulled) => {
+                format!("Synchronized the dictionary: {pushed} words uploaded, {pulled} downloaded")
+            }
+        }
+    }
+
+    fn de(&self) -> Option<String> {
+        Some(match self {
+            Self::Accept => "Übernehmen".into(),
+            Self::ReplaceWith(text) => format!("Durch „{text}“ ersetzen"),
+            Self::AddToDictionary => "Zum Wörterbuch hinzufügen".into(),
+            Self::AddWordToDictionary(word) => format!("{word:?} zum Wörterbuch hinzufügen"),
+            Self::IgnoreLints => "Hinweise ignorieren".into(),
+            Self::IgnorePermanently => "Diesen Fehlalarm dauerhaft ignorieren".into(),
+            Self::FixAll => "Alle beheben".into(),
+            Self::DisableNextLine(rule) => format!("{rule} für diese Zeile deaktivieren"),
+            Self::DisableRule(rule) => format!("{rule} deaktivieren"),
+            Self::DisableCategory(category) => format!("Kategorie {category} deaktivieren"),
+            Self::CheckSpelling => "Rechtschreibung prüfen".into(),
+            Self::Checking => "Überprüfung".into(),
+            Self::Synonyms => "Synonyme".into(),
+            Self::Suggestions => "Vorschläge".into(),
+            Self::MoreInformation => "Weitere Informationen".into(),
+            Self::RulePage => "Regeldetails".into(),
+            Self::SynonymsFor(word) => format!("Synonyme für {word:?}"),
+            Self::NoSynonyms(language) => format!("Kein Synonymdienst für {language}"),
+            Self::NoSynonymsFor(word) => format!("Keine Synonyme für {word:?}"),
+            Self::RephraseSentence => "Satz umformulieren".into(),
+            Self::NoRephrasings => "Keine alternativen Formulierungen gefunden".into(),
+            Self::Autocorrected(summary) => format!("Automatisch korrigiert: {summary}"),
+            Self::Offline => {
+                "LanguageTool ist nicht erreichbar, ausstehende Prüfungen werden nachgeholt".into()
+            }
+            Self::Online => "LanguageTool ist wieder erreichbar".into(),
+            Self::FailedOver(server) => format!(
+                "Der Server ist nicht verfügbar, bis er wieder erreichbar ist wird {server} verwendet"
+            ),
+            Self::ServerRestored(server) => format!("Der Server {server} wird wieder verwendet"),
+            Self::RuleIgnored(rule) => {
+                format!(
+                    "{rule} wurde wiederholt ignoriert, eventuell sollte die Regel deaktiviert werden"
+                )
+            }
+            Self::RulesUpdated => "Die Projektregeln wurden aktualisiert".into(),
+            Self::RulesRequireWorkspace => {
+                "Die Regelübersicht benötigt einen Arbeitsbereich".into()
+            }
+            Self::DictionaryRequiresWorkspace => {
+                "Die Wörterbuchdatei benötigt einen Arbeitsbereich".into()
+            }
+            Self::IgnoreRequiresWorkspace => {
+                "Das dauerhafte Ignorieren von Fehlalarmen benötigt einen Arbeitsbereich".into()
+            }
+            Self::CheckRequiresWorkspace => {
+                "Die Prüfung des Arbeitsbereichs benötigt einen Arbeitsbereich".into()
+            }
+            Self::InvalidSettings(err) => format!("Ungültige Einstellungen: {err}"),
+            Self::UnsupportedLanguages(codes) => {
+                format!("Vom Server nicht unterstützte Sprachen: {codes}")
+            }
+            Self::ServerUnreachable(server) => format!("Der Server {server} ist nicht erreichbar"),
+            Self::FileTooLarge(name) => format!("{name} ist zu groß und wird nicht überprüft"),
+            Self::MoreIssues(count) => format!("{count} weitere Probleme werden nicht angezeigt"),
+            Self::ServerVersion(name, version, premium) => {
+                let premium = if *premium { " (Premium)" } else { "" };
+                format!("Verbunden mit {name} {version}{premium}")
+            }
+            Self::DictionaryStatus(words, known, ignored) => format!(
+                "{words} Wörter im Wörterbuch, {known} bekannte Wörter, {ignored} ignorierte Treffer"
+            ),
+            Self::PremiumOnly => {
+                "Das Synchronisieren von Wörtern ist nur für Premium-Nutzer verfügbar".into()
+            }
+            Self::PremiumNotActive => "Der Server hat Benutzername und API-Schlüssel nicht \
+                als Premium-Konto akzeptiert, es werden nur die freien Regeln verwendet. \
+                Überprüfe die Zugangsdaten und den Server (https://api.languagetoolplus.com)."
+                .into(),
+            Self::AddedRemote(word) => format!("{word:?} zum Online-Wörterbuch hinzugefügt"),
+            Self::AddedLocal(word) => format!("{word:?} zum lokalen Wörterbuch hinzugefügt"),
+            Self::WordsSynced(pushed, pulled) => format!(
+                "Wörterbuch synchronisiert: {pushed} Wörter hochgeladen, {pulled} heruntergeladen"
+            ),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn locale() {
+        assert_eq!(Lang::from_locale("de"), Lang::De);
+        assert_eq!(Lang::from_locale("de_AT.UTF-8"), Lang::De);
+        assert_eq!(Lang::from_locale("en-GB"), Lang::En);
+        assert_eq!(Lang::from_locale("fr"), Lang::En);
+
+        assert_eq!(Msg::CheckSpelling.text(Lang::De), "Rechtschreibung prüfen");
+        assert_eq!(
+            Msg::SynonymsFor("Haus").text(Lang::En),
+            "Synonyms for \"Haus\""
+        );
+    }
+}
@@ -0,0 +1,130 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use anyhow::Result;
+use languagetool_lsp_core::api::Match;
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+/// Number of rejections after which disabling a rule is suggested
+const REJECT_THRESHOLD: usize = 5;
+
+/// Locally persisted statistics about accepted and rejected suggestions.
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct Learning {
+    rules: HashMap<String, RuleStats>,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+struct RuleStats {
+    accepted: usize,
+    rejected: usize,
+    /// Number of times each replacement has been applied
+    replacements: HashMap<String, usize>,
+    /// Whether disabling this rule has already been suggested
+    suggested: bool,
+}
+
+impl Learning {
+    fn path() -> Option<PathBuf> {
+        Some(
+            dirs::data_dir()?
+                .join("languagetool-lsp")
+                .join("learning.json"),
+        )
+    }
+
+    pub fn load() -> Self {
+        let Some(path) = Self::path() else {
+            return Self::default();
+        };
+        match std::fs::read_to_string(&path) {
+            Ok(data) => serde_json::from_str(&data).unwrap_or_else(|e| {
+                warn!("Invalid learning data {}: {e}", path.display());
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let Some(path) = Self::path() else {
+            return Ok(());
+        };
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_string(self)?)?;
+        Ok(())
+    }
+
+    /// Record that a replacement of the given rule has been applied.
+    pub fn accept(&mut self, rule: &str, replacement: &str) {
+        let stats = self.rules.entry(rule.to_string()).or_default();
+        stats.accepted += 1;
+        *stats
+            .replacements
+            .entry(replacement.to_string())
+            .or_default() += 1;
+    }
+
+    /// Record that a match of the given rule has been ignored.
+    ///
+    /// Returns true if the rule is rejected repeatedly and disabling it
+    /// should be suggested to the user (only once per rule).
+    pub fn reject(&mut self, rule: &str) -> bool {
+        let stats = self.rules.entry(rule.to_string()).or_default();
+        stats.rejected += 1;
+        if !stats.suggested
+            && stats.rejected >= REJECT_THRESHOLD
+            && stats.rejected > 2 * stats.accepted
+        {
+            stats.suggested = true;
+            return true;
+        }
+        false
+    }
+
+    /// Order the replacements by how often they have been applied before.
+    pub fn rank(&self, m: &mut Match) {
+        if let Some(stats) = self.rules.get(&m.rule) {
+            m.replacements.sort_by_key(|r| {
+                std::cmp::Reverse(stats.replacements.get(r).copied().unwrap_or(0))
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn rank_and_reject() {
+        let mut learning = Learning::default();
+        learning.accept("RULE", "b");
+        learning.accept("RULE", "b");
+        learning.accept("RULE", "c");
+
+        let mut m = Match {
+            range: 0..1,
+            title: String::new(),
+            message: String::new(),
+            replacements: vec!["a".into(), "c".into(), "b".into()],
+            category: String::new(),
+            rule: "RULE".into(),
+            severity: None,
+            details: Default::default(),
+        };
+        learning.rank(&mut m);
+        assert_eq!(m.replacements, vec!["b", "c", "a"]);
+
+        let suggested = (0..REJECT_THRESHOLD + 3)
+            .filter(|_| learning.reject("RULE"))
+            .count();
+        assert_eq!(suggested, 1);
+        assert!(!learning.reject("OTHER"));
+    }
+}
@@ -0,0 +1,73 @@
+//! Logging to stderr or a file, with a level that the settings can change at
+//! runtime.
+
+use std::fs::OpenOptions;
+use std::path::Path;
+use std::sync::Mutex;
+
+use anyhow::Result;
+use languagetool_lsp_core::settings::LogLevel;
+use tracing::level_filters::LevelFilter;
+use tracing_subscriber::fmt::writer::BoxMakeWriter;
+use tracing_subscriber::prelude::*;
+use tracing_subscriber::{Registry, reload};
+
+#[derive(Clone)]
+pub struct Logger {
+    handle: reload::Handle<LevelFilter, Registry>,
+    /// Level of the command line, used if the settings have none
+    default: LevelFilter,
+}
+
+impl Logger {
+    /// Installs the global logger, appending to the file if given
+    pub fn init(level: LevelFilter, file: Option<&Path>) -> Result<Self> {
+        let writer = match file {
+            Some(path) => {
+                let file = OpenOptions::new().create(true).append(true).open(path)?;
+                BoxMakeWriter::new(Mutex::new(file))
+            }
+            None => BoxMakeWriter::new(std::io::stderr),
+        };
+        let (filter, handle) = reload::Layer::new(level);
+        tracing_subscriber::registry()
+            .with(filter)
+            .with(
+                tracing_subscriber::fmt::layer()
+                    .with_writer(writer)
+                    .with_ansi(false)
+                    .without_time(),
+            )
+            .init();
+        Ok(Self {
+            handle,
+            default: level,
+        })
+    }
+
+    /// Logger that is not installed, e.g. for tests
+    #[cfg(test)]
+    pub fn detached() -> Self {
+        let (_, handle) = reload::Layer::new(LevelFilter::OFF);
+        Self {
+            handle,
+            default: LevelFilter::OFF,
+        }
+    }
+
+    /// Uses the level of the settings or the one of the command line
+    pub fn set_level(&self, level: Option<LogLevel>) {
+        let level = match level {
+            None => self.default,
+            Some(LogLevel::Off) => LevelFilter::OFF,
+            Some(LogLevel::Error) => LevelFilter::ERROR,
+            Some(LogLevel::Warn) => LevelFilter::WARN,
+            Some(LogLevel::Info) => LevelFilter::INFO,
+            Some(LogLevel::Debug) => LevelFilter::DEBUG,
+            Some(LogLevel::Trace) => LevelFilter::TRACE,
+        };
+        if let Err(err) = self.handle.reload(level) {
+            eprintln!("Failed to change the log level: {err}");
+        }
+    }
+}
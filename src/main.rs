@@ -1,68 +1,271 @@
 use std::collections::{HashMap, HashSet};
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::ops::Range;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
 
 use anyhow::{Result, anyhow};
-use api::Match;
 use changes::Changes;
-use serde::{Deserialize, Serialize};
-use tokio::sync::RwLock;
+use clap::{Parser, Subcommand};
+use docker::Docker;
+use futures::{StreamExt, stream};
+use languagetool_lsp_core::annotated::{self, magic};
+use languagetool_lsp_core::api::languages::Language;
+use languagetool_lsp_core::api::{self, Match, Mode, Severity, Software, Tag, synonyms};
+use languagetool_lsp_core::dictionary::{self, Dictionary, DictionaryFile, SyncPlan};
+use languagetool_lsp_core::false_positives::{self, FalsePositives};
+use languagetool_lsp_core::local::{
+    self,
+    known_words::{self, KnownWords},
+};
+use languagetool_lsp_core::settings::{Scope, Settings};
+use languagetool_lsp_core::source::{Encoding, SourceFile};
+use languagetool_lsp_core::util::{self, RangeExt};
+use learning::Learning;
+use logging::Logger;
+use matches::Matches;
+use progress::Progress;
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use tokio::sync::{Notify, RwLock};
 use tower_lsp_server::lsp_types::{
-    self, CodeAction, CodeActionKind, CodeActionParams, CodeActionProviderCapability,
-    CodeActionResponse, Diagnostic, DiagnosticOptions, DiagnosticServerCapabilities,
-    DiagnosticSeverity, DidChangeConfigurationParams, DidChangeTextDocumentParams,
-    DidCloseTextDocumentParams, DidOpenTextDocumentParams, DidSaveTextDocumentParams,
-    ExecuteCommandOptions, ExecuteCommandParams, InitializeParams, InitializeResult, MessageType,
-    Range as DocRange, ServerCapabilities, ServerInfo, TextDocumentSyncCapability,
-    TextDocumentSyncKind, TextEdit, Uri, WorkspaceEdit,
+    self, CodeAction, CodeActionKind, CodeActionOptions, CodeActionParams,
+    CodeActionProviderCapability, CodeActionResponse, CodeDescription, CompletionItem,
+    CompletionItemKind, CompletionOptions, CompletionParams, CompletionResponse,
+    CompletionTextEdit, CompletionTriggerKind, ConfigurationItem, Diagnostic, DiagnosticOptions,
+    DiagnosticRelatedInformation, DiagnosticServerCapabilities, DiagnosticSeverity, DiagnosticTag,
+    DidChangeConfigurationParams, DidChangeTextDocumentParams, DidChangeWatchedFilesParams,
+    DidChangeWatchedFilesRegistrationOptions, DidCloseTextDocumentParams,
+    DidOpenTextDocumentParams, DidSaveTextDocumentParams, DocumentChanges,
+    DocumentDiagnosticParams, DocumentDiagnosticReport, DocumentDiagnosticReportResult,
+    ExecuteCommandOptions, ExecuteCommandParams, FileSystemWatcher, FullDocumentDiagnosticReport,
+    GlobPattern, Hover, HoverContents, HoverParams, HoverProviderCapability, InitializeParams,
+    InitializeResult, InitializedParams, Location, MarkupContent, MarkupKind, MessageActionItem,
+    MessageType, NumberOrString, OneOf, OptionalVersionedTextDocumentIdentifier, Position,
+    PositionEncodingKind, Range as DocRange, Registration, RelatedFullDocumentDiagnosticReport,
+    RelatedUnchangedDocumentDiagnosticReport, ServerCapabilities, ServerInfo, ShowDocumentParams,
+    TextDocumentEdit, TextDocumentPositionParams, TextDocumentSyncCapability, TextDocumentSyncKind,
+    TextEdit, UnchangedDocumentDiagnosticReport, Uri, WorkspaceDiagnosticParams,
+    WorkspaceDiagnosticReport, WorkspaceDiagnosticReportResult, WorkspaceDocumentDiagnosticReport,
+    WorkspaceEdit, WorkspaceFullDocumentDiagnosticReport,
+    WorkspaceUnchangedDocumentDiagnosticReport,
 };
-use tower_lsp_server::{Client, LanguageServer, LspService, Server, jsonrpc};
-use tracing::{error, info, warn};
+use tower_lsp_server::{Client, LanguageServer, LspService, Server, UriExt, jsonrpc};
+use tracing::level_filters::LevelFilter;
+use tracing::{debug, error, info, warn};
+use url::Url;
 
-mod annotated;
-mod api;
 mod changes;
-mod settings;
-mod source;
-mod util;
+mod cli;
+mod docker;
+mod i18n;
+mod learning;
+mod logging;
+mod matches;
+mod pipeline;
+mod progress;
+mod project;
+mod rule_browser;
+
+use i18n::{Lang, Msg};
 
-use annotated::plaintext;
-use settings::Settings;
-use source::SourceFile;
-use util::RangeExt;
+/// Delay between attempts to reach the server while offline
+const OFFLINE_RETRY: Duration = Duration::from_secs(30);
+/// Interval of probing the configured server while a fallback is used
+const FAILOVER_PROBE: Duration = Duration::from_secs(60);
 
+#[derive(Clone)]
 struct Backend {
     client: Client,
-    settings: RwLock<Settings>,
+    /// Effective settings (client settings overridden by the project settings)
+    settings: Arc<RwLock<Settings>>,
+    /// Settings as received from the client
+    client_settings: Arc<RwLock<serde_json::Value>>,
+    /// Root directory of the workspace
+    root: Arc<RwLock<Option<PathBuf>>>,
     /// Currently open documents
-    documents: RwLock<HashMap<Uri, Document>>,
-    dictionary: RwLock<HashSet<String>>,
+    documents: Arc<RwLock<HashMap<Uri, Document>>>,
+    dictionary: Arc<RwLock<Dictionary>>,
+    learning: Arc<RwLock<Learning>>,
+    /// Matches that are ignored permanently
+    false_positives: Arc<RwLock<FalsePositives>>,
+    /// Words of the configured word lists
+    known_words: Arc<RwLock<Option<KnownWords>>>,
+    /// Languages supported by each server
+    languages: Arc<RwLock<HashMap<String, Vec<Language>>>>,
+    /// The server is currently unreachable
+    offline: Arc<AtomicBool>,
+    /// Configured server while a fallback server is used
+    primary_server: Arc<RwLock<Option<Url>>>,
+    /// Last error shown to the user, repeated errors are not shown again
+    last_error: Arc<RwLock<Option<String>>>,
+    /// Earliest start of the next check of an opened document
+    next_open_check: Arc<RwLock<Option<Instant>>>,
+    /// Language of the user interface
+    lang: Arc<OnceLock<Lang>>,
+    /// Encoding of the positions negotiated with the client
+    encoding: Arc<OnceLock<Encoding>>,
+    /// The client pulls the diagnostics instead of receiving them
+    pull_diagnostics: Arc<AtomicBool>,
+    /// The client shows the progress of long running checks
+    work_done_progress: Arc<AtomicBool>,
+    /// The client resolves the edits of code actions when they are applied
+    resolve_edits: Arc<AtomicBool>,
+    /// The client supports watching the project files
+    watch_files: Arc<AtomicBool>,
+    /// The client provides the settings on request
+    pull_configuration: Arc<AtomicBool>,
+    /// The client shows message requests with actions, e.g. for picking
+    /// synonyms
+    message_actions: Arc<AtomicBool>,
+    /// The user was warned that the premium account is not used
+    premium_warned: Arc<AtomicBool>,
+    /// Check with the built-in stub regardless of the settings
+    offline_stub: bool,
+    logger: Logger,
+    /// LanguageTool server in a Docker container
+    docker: Arc<Docker>,
 }
 
 impl LanguageServer for Backend {
     async fn initialize(&self, params: InitializeParams) -> jsonrpc::Result<InitializeResult> {
         info!("Init {:?}", params.initialization_options);
         info!("{:?}", params.capabilities.general);
-        info!(
-            "{:?}",
-            params.capabilities.text_document.and_then(|d| d.diagnostic)
-        );
+        let pull = params
+            .capabilities
+            .text_document
+            .as_ref()
+            .is_some_and(|d| d.diagnostic.is_some());
+        let refresh = params
+            .capabilities
+            .workspace
+            .as_ref()
+            .and_then(|w| w.diagnostic.as_ref())
+            .and_then(|d| d.refresh_support)
+            .unwrap_or(false);
+        info!("Pull diagnostics: {pull} (refresh: {refresh})");
+        // Without refresh the client would not know about finished checks
+        self.pull_diagnostics
+            .store(pull && refresh, Ordering::Release);
+        let work_done_progress = params
+            .capabilities
+            .window
+            .as_ref()
+            .and_then(|w| w.work_done_progress)
+            .unwrap_or(false);
+        self.work_done_progress
+            .store(work_done_progress, Ordering::Release);
+        let resolve_edits = params
+            .capabilities
+            .text_document
+            .as_ref()
+            .and_then(|d| d.code_action.as_ref())
+            .and_then(|c| c.resolve_support.as_ref())
+            .is_some_and(|r| r.properties.iter().any(|p| p == "edit"));
+        self.resolve_edits.store(resolve_edits, Ordering::Release);
+        let watch_files = params
+            .capabilities
+            .workspace
+            .as_ref()
+            .and_then(|w| w.did_change_watched_files.as_ref())
+            .and_then(|w| w.dynamic_registration)
+            .unwrap_or(false);
+        self.watch_files.store(watch_files, Ordering::Release);
+        let pull_configuration = params
+            .capabilities
+            .workspace
+            .as_ref()
+            .and_then(|w| w.configuration)
+            .unwrap_or(false);
+        self.pull_configuration
+            .store(pull_configuration, Ordering::Release);
+        let message_actions = params
+            .capabilities
+            .window
+            .as_ref()
+            .and_then(|w| w.show_message.as_ref())
+            .is_some_and(|m| m.message_action_item.is_some());
+        self.message_actions
+            .store(message_actions, Ordering::Release);
+        // Used until the client sends or provides its settings
+        if let Some(options @ serde_json::Value::Object(_)) = params.initialization_options {
+            *self.client_settings.write().await = options;
+        }
+
+        #[allow(deprecated)]
+        let root = params
+            .workspace_folders
+            .and_then(|f| f.into_iter().next())
+            .map(|f| f.uri)
+            .or(params.root_uri)
+            .and_then(|uri| uri.to_file_path().map(|p| p.into_owned()));
+        info!("Root: {root:?}");
+        *self.root.write().await = root;
+
+        let lang = params.locale.as_deref().map(Lang::from_locale);
+        self.lang.set(lang.unwrap_or_default()).ok();
+        // UTF-8 positions do not need to be converted
+        let utf8 = params
+            .capabilities
+            .general
+            .as_ref()
+            .and_then(|g| g.position_encodings.as_ref())
+            .is_some_and(|e| e.contains(&PositionEncodingKind::UTF8));
+        let encoding = if utf8 {
+            Encoding::Utf8
+        } else {
+            Encoding::Utf16
+        };
+        info!("Position encoding: {encoding:?}");
+        self.encoding.set(encoding).ok();
+
         Ok(InitializeResult {
             capabilities: ServerCapabilities {
+                position_encoding: Some(match encoding {
+                    Encoding::Utf8 => PositionEncodingKind::UTF8,
+                    Encoding::Utf16 => PositionEncodingKind::UTF16,
+                }),
                 text_document_sync: Some(TextDocumentSyncCapability::Kind(
                     TextDocumentSyncKind::INCREMENTAL,
                 )),
                 diagnostic_provider: Some(DiagnosticServerCapabilities::Options(
                     DiagnosticOptions {
                         identifier: Some("languagetool-lsp".to_string()),
+                        workspace_diagnostics: true,
+                        ..Default::default()
+                    },
+                )),
+                code_action_provider: Some(CodeActionProviderCapability::Options(
+                    CodeActionOptions {
+                        resolve_provider: Some(true),
                         ..Default::default()
                     },
                 )),
-                code_action_provider: Some(CodeActionProviderCapability::Simple(true)),
+                completion_provider: Some(CompletionOptions::default()),
+                hover_provider: Some(HoverProviderCapability::Simple(true)),
                 execute_command_provider: Some(ExecuteCommandOptions {
                     commands: vec![
                         "languagetool-lsp.check".to_string(),
+                        "languagetool-lsp.check-workspace".to_string(),
                         "languagetool-lsp.synonyms".to_string(),
+                        "languagetool-lsp.rephrase".to_string(),
                         "languagetool-lsp.ignore".to_string(),
+                        "languagetool-lsp.ignore-permanently".to_string(),
+                        "languagetool-lsp.fix-all".to_string(),
                         "languagetool-lsp.words-add".to_string(),
+                        "languagetool-lsp.words-list".to_string(),
+                        "languagetool-lsp.words-bulk-add".to_string(),
+                        "languagetool-lsp.words-bulk-remove".to_string(),
+                        "languagetool-lsp.words-sync".to_string(),
+                        "languagetool-lsp.languages".to_string(),
+                        "languagetool-lsp.set-language".to_string(),
+                        "languagetool-lsp.accept".to_string(),
+                        "languagetool-lsp.disable-rule".to_string(),
+                        "languagetool-lsp.disable-category".to_string(),
+                        "languagetool-lsp.rules-browse".to_string(),
+                        "languagetool-lsp.dictionary-normalize".to_string(),
+                        "languagetool-lsp.status".to_string(),
                     ],
                     ..Default::default()
                 }),
@@ -75,9 +278,86 @@ impl LanguageServer for Backend {
         })
     }
 
+    async fn initialized(&self, _: InitializedParams) {
+        if self.pull_configuration.load(Ordering::Acquire) {
+            self.pull_settings().await;
+            // Receive notifications about changes of the settings
+            let registration = Registration {
+                id: "languagetool-lsp/configuration".into(),
+                method: "workspace/didChangeConfiguration".into(),
+                register_options: None,
+            };
+            if let Err(err) = self.client.register_capability(vec![registration]).await {
+                warn!("Failed to register for configuration changes: {err}");
+            }
+        }
+        self.load_settings().await;
+        self.load_false_positives().await;
+        self.validate_languages();
+
+        if self.settings.read().await.sync_dictionary {
+            let backend = self.clone();
+            tokio::spawn(async move {
+                let res = backend.command_words_sync(false).await;
+                backend.command_result(res).await.ok();
+            });
+        }
+
+        // Watch the project files for changes by other tools
+        if self.watch_files.load(Ordering::Acquire) {
+            let options = DidChangeWatchedFilesRegistrationOptions {
+                watchers: vec![FileSystemWatcher {
+                    glob_pattern: GlobPattern::String(format!("**/{}/*", project::DIR)),
+                    kind: None,
+                }],
+            };
+            let registration = Registration {
+                id: "languagetool-lsp/watch".into(),
+                method: "workspace/didChangeWatchedFiles".into(),
+                register_options: Some(serde_json::to_value(options).unwrap()),
+            };
+            if let Err(err) = self.client.register_capability(vec![registration]).await {
+                warn!("Failed to watch project files: {err}");
+            }
+        }
+    }
+
+    async fn did_change_watched_files(&self, params: DidChangeWatchedFilesParams) {
+        // Our own changes to the project files are already applied
+        let mut changed = false;
+        for change in &params.changes {
+            let uri = &change.uri;
+            info!("DidChangeWatchedFiles: {} {:?}", uri.as_str(), change.typ);
+            if self.is_project_file(uri, project::SETTINGS).await
+                || self.is_project_file(uri, local::terminology::FILE).await
+            {
+                let settings = serde_json::to_value(&*self.settings.read().await).ok();
+                self.load_settings().await;
+                changed |= serde_json::to_value(&*self.settings.read().await).ok() != settings;
+            } else if self.is_project_file(uri, dictionary::FILE).await {
+                self.load_dictionary().await;
+                changed = true;
+            } else if self.is_project_file(uri, false_positives::FILE).await {
+                changed |= self.load_false_positives().await;
+            }
+        }
+        if changed {
+            let backend = self.clone();
+            tokio::spawn(async move { backend.check_all().await });
+        }
+    }
+
     async fn did_change_configuration(&self, params: DidChangeConfigurationParams) {
         info!("Settings: {:?}", params.settings);
-        *self.settings.write().await = serde_json::from_value(params.settings).unwrap();
+        // Clients that provide the settings on request might only notify
+        // about the change
+        if self.pull_configuration.load(Ordering::Acquire) {
+            self.pull_settings().await;
+        } else {
+            *self.client_settings.write().await = params.settings;
+        }
+        self.load_settings().await;
+        self.validate_languages();
     }
 
     async fn did_open(&self, params: DidOpenTextDocumentParams) {
@@ -87,13 +367,39 @@ impl LanguageServer for Backend {
             params.text_document.uri.as_str()
         );
 
+        // Notebooks are usually opened as plain json
+        let mut language_id = params.text_document.language_id;
+        let path = params.text_document.uri.path().as_str();
+        if path.ends_with(".ipynb") {
+            language_id = "ipynb".into();
+        } else if language_id.is_empty() {
+            // Some clients omit the language of scratch buffers
+            language_id = path
+                .rsplit_once('.')
+                .and_then(|(_, ext)| annotated::language_id(ext))
+                .unwrap_or("plaintext")
+                .into();
+        }
         self.documents.write().await.insert(
-            params.text_document.uri,
+            params.text_document.uri.clone(),
             Document::new(
-                SourceFile::new(params.text_document.text),
+                SourceFile::new(params.text_document.text).with_encoding(self.encoding()),
                 Some(params.text_document.version),
+                language_id,
             ),
         );
+
+        let settings = self.settings.read().await;
+        if settings.check_on_open {
+            // Space out the checks if many documents are opened at once
+            let min_delay = Duration::from_secs_f64(settings.endpoint().min_delay() / 1000.0);
+            drop(settings);
+            let now = Instant::now();
+            let mut next = self.next_open_check.write().await;
+            let start = next.map_or(now, |next| next.max(now));
+            *next = Some(start + min_delay);
+            self.schedule_check(params.text_document.uri, 0, start - now);
+        }
     }
 
     async fn did_change(&self, params: DidChangeTextDocumentParams) {
@@ -110,35 +416,60 @@ impl LanguageServer for Backend {
 
         for change in params.content_changes {
             if let Some(range) = change.range {
-                doc.changed_lines.add_change(
-                    range.start.line as usize..range.end.line as usize + 1,
-                    change.text.split('\n').count(),
-                );
+                let lines = range.start.line as usize..range.end.line as usize + 1;
+                let len = change.text.split('\n').count();
+                doc.changed_lines.add_change(lines.clone(), len);
+                if !doc.checking.changes().is_empty() {
+                    doc.checking.add_change(lines, len);
+                }
 
                 let start = doc.source.to_offset(range.start).unwrap();
                 let end = doc.source.to_offset(range.end).unwrap();
+                doc.changed_bytes.add_change(start..end, change.text.len());
 
                 doc.source.replace(start..end, &change.text);
                 doc.version = Some(params.text_document.version);
 
                 // Update positions for matches behind the change
                 let shift = change.text.len() as isize - (end as isize - start as isize);
-                for m in &mut doc.matches {
-                    if m.range.start >= end {
-                        m.range.start = (m.range.start as isize + shift) as usize;
-                    }
-                    if m.range.end >= end {
-                        m.range.end = (m.range.end as isize + shift) as usize;
-                    }
+                if !doc.checking.changes().is_empty() {
+                    doc.edit_log.push((doc.edits + 1, start..end, shift));
                 }
+                doc.matches.shift(start..end, shift);
             } else {
                 // No range means replace the whole document
-                doc.source = SourceFile::new(change.text);
+                doc.source = SourceFile::new(change.text).with_encoding(self.encoding());
                 doc.version = Some(params.text_document.version);
                 doc.matches.clear();
                 doc.changed_lines.clear();
+                doc.changed_bytes.clear();
+                if !doc.checking.changes().is_empty() {
+                    doc.edit_log.push((doc.edits + 1, 0..usize::MAX, 0));
+                }
+                doc.checking.clear();
             }
         }
+
+        doc.edits += 1;
+        let edits = doc.edits;
+        // Cancel the running checks of invalidated lines
+        doc.edited.notify_waiters();
+
+        if self.known_words.read().await.is_some()
+            && self
+                .is_checked(&params.text_document.uri, &doc.language_id)
+                .await
+        {
+            self.provisional_matches(doc).await;
+            self.show_diagnostics(&params.text_document.uri, doc).await;
+        }
+        drop(open_docs);
+
+        let settings = self.settings.read().await;
+        if settings.auto_check {
+            let delay = Duration::from_secs_f64(settings.auto_check_delay.max(0.0) / 1000.0);
+            self.schedule_check(params.text_document.uri, edits, delay);
+        }
     }
 
     async fn did_save(&self, params: DidSaveTextDocumentParams) {
@@ -149,6 +480,15 @@ impl LanguageServer for Backend {
 
         info!("DidSave: {}", text_document.uri.as_str());
 
+        if self
+            .is_project_file(&text_document.uri, rule_browser::FILE)
+            .await
+        {
+            let res = self.apply_rule_browser(&text_document.uri).await;
+            self.command_result(res).await.ok();
+            return;
+        }
+
         let mut open_docs = self.documents.write().await;
         let Some(doc) = open_docs.get_mut(&text_document.uri) else {
             return;
@@ -157,19 +497,36 @@ impl LanguageServer for Backend {
         if let Some(text) = text {
             if text != doc.source.text() {
                 warn!("Document has dirty changes! {}", text_document.uri.as_str());
-                doc.source = SourceFile::new(text);
+                doc.source = SourceFile::new(text).with_encoding(self.encoding());
                 doc.changed_lines
                     .add_change(0..doc.source.lines().len(), doc.source.lines().len());
+                doc.edits += 1;
             }
         };
+        drop(open_docs);
 
-        if let Err(err) = self.update_matches(doc).await {
-            error!("Failed diagnostics: {err}\n{}", err.backtrace());
-            self.client
-                .show_message(MessageType::ERROR, format!("{err}"))
-                .await;
+        if !self.check_document(&text_document.uri).await {
+            return;
+        }
+
+        let settings = self.settings.read().await.clone();
+        let mut open_docs = self.documents.write().await;
+        let Some(doc) = open_docs.get_mut(&text_document.uri) else {
+            return;
+        };
+        let corrections = if settings.autocorrect {
+            doc.corrections(&settings.autocorrect_rules)
         } else {
-            self.show_diagnostics(&text_document.uri, doc).await;
+            Vec::new()
+        };
+        let version = doc.version;
+        self.show_diagnostics(&text_document.uri, doc).await;
+        // The client modifies the document when applying the edits
+        drop(open_docs);
+
+        if !corrections.is_empty() {
+            self.autocorrect(text_document.uri, version, corrections)
+                .await;
         }
     }
 
@@ -187,6 +544,7 @@ impl LanguageServer for Backend {
 
     async fn shutdown(&self) -> jsonrpc::Result<()> {
         info!("Shutdown");
+        self.docker.stop().await;
         Ok(())
     }
 
@@ -194,12 +552,19 @@ impl LanguageServer for Backend {
         &self,
         params: CodeActionParams,
     ) -> jsonrpc::Result<Option<CodeActionResponse>> {
+        let rephrase = {
+            let settings = self.settings.read().await;
+            settings.rephrase_url.is_some()
+                && !settings.username.is_empty()
+                && !settings.api_key.is_empty()
+        };
         let open_docs = self.documents.read().await;
         let Some(doc) = open_docs.get(&params.text_document.uri) else {
             return Ok(None);
         };
 
         let mut actions = Vec::new();
+        let resolve_edits = self.resolve_edits.load(Ordering::Acquire);
 
         let lt_diags = params
             .context
@@ -210,34 +575,47 @@ impl LanguageServer for Backend {
             .collect::<Vec<_>>();
 
         for diag in &lt_diags {
-            // Replacements
-            if let Some(data) = &diag.data {
-                let data: Vec<String> = serde_json::from_value(data.clone()).unwrap();
-                for replacement in data {
-                    actions.push(CodeAction {
-                        title: format!("{replacement:?}"),
-                        kind: Some(CodeActionKind::QUICKFIX),
-                        edit: Some(WorkspaceEdit {
-                            changes: Some(
-                                [(
-                                    params.text_document.uri.clone(),
-                                    vec![TextEdit {
-                                        range: diag.range,
-                                        new_text: replacement,
-                                    }],
-                                )]
-                                .into(),
-                            ),
-                            ..Default::default()
-                        }),
-                        diagnostics: Some(vec![diag.clone()]),
-                        ..Default::default()
-                    });
-                }
+            let Some(data) = diag
+                .data
+                .clone()
+                .and_then(|d| serde_json::from_value::<DiagnosticData>(d).ok())
+            else {
+                continue;
+            };
+
+            // Replacements, the edits are built on resolve if supported
+            for (i, replacement) in data.replacements.iter().enumerate() {
+                let replace = ReplaceData {
+                    uri: params.text_document.uri.clone(),
+                    range: diag.range,
+                    replacement: replacement.clone(),
+                };
+                actions.push(CodeAction {
+                    title: self.tr(Msg::ReplaceWith(replacement)),
+                    kind: Some(CodeActionKind::QUICKFIX),
+                    // The top suggestion can be applied directly
+                    is_preferred: Some(i == 0),
+                    edit: (!resolve_edits).then(|| replace.edit()),
+                    data: resolve_edits.then(|| serde_json::to_value(replace).unwrap()),
+                    // Notify the server about the applied replacement
+                    command: Some(lsp_types::Command {
+                        title: self.tr(Msg::Accept),
+                        command: "languagetool-lsp.accept".to_string(),
+                        arguments: Some(vec![
+                            serde_json::to_value(AcceptParams {
+                                rule: data.rule.clone(),
+                                replacement: replacement.clone(),
+                            })
+                            .unwrap(),
+                        ]),
+                    }),
+                    diagnostics: Some(vec![diag.clone()]),
+                    ..Default::default()
+                });
             }
 
             // Add to dictionary
-            if diag.severity == Some(DiagnosticSeverity::WARNING) {
+            if data.category == "TYPOS" {
                 if let (Some(start), Some(end)) = (
                     doc.source.to_offset(diag.range.start),
                     doc.source.to_offset(diag.range.end),
@@ -245,10 +623,10 @@ impl LanguageServer for Backend {
                     info!("Add to dictionary {start}..{end}");
                     let selection = &doc.source.text()[start..end];
                     actions.push(CodeAction {
-                        title: format!("Add {selection:?} to Dictionary"),
+                        title: self.tr(Msg::AddWordToDictionary(selection)),
                         kind: Some(CodeActionKind::QUICKFIX),
                         command: Some(lsp_types::Command {
-                            title: "Add to Dictionary".to_string(),
+                            title: self.tr(Msg::AddToDictionary),
                             command: "languagetool-lsp.words-add".to_string(),
                             arguments: Some(vec![
                                 serde_json::to_value(LTCommandParams {
@@ -263,15 +641,99 @@ impl LanguageServer for Backend {
                     });
                 }
             }
+
+            // Suppress the rule with a comment on the previous line
+            if let Some((open, close)) = magic::comment(&doc.language_id)
+                && let Some(start) = doc.source.line_start(diag.range.start.line as _)
+            {
+                let line = &doc.source.text()[start.byte..];
+                let indent = &line[..line.len() - line.trim_start_matches([' ', '\t']).len()];
+                let position = Position::new(diag.range.start.line, 0);
+                actions.push(CodeAction {
+                    title: self.tr(Msg::DisableNextLine(&data.rule)),
+                    kind: Some(CodeActionKind::QUICKFIX),
+                    edit: Some(WorkspaceEdit {
+                        changes: Some(
+                            [(
+                                params.text_document.uri.clone(),
+                                vec![TextEdit {
+                                    range: DocRange::new(position, position),
+                                    new_text: format!(
+                                        "{indent}{open} lt-disable-next-line {}{close}\n",
+                                        data.rule
+                                    ),
+                                }],
+                            )]
+                            .into(),
+                        ),
+                        ..Default::default()
+                    }),
+                    diagnostics: Some(vec![diag.clone()]),
+                    ..Default::default()
+                });
+            }
+
+            // Disable the rule for the project
+            actions.push(CodeAction {
+                title: self.tr(Msg::DisableRule(&data.rule)),
+                kind: Some(CodeActionKind::QUICKFIX),
+                command: Some(lsp_types::Command {
+                    title: self.tr(Msg::DisableRule(&data.rule)),
+                    command: "languagetool-lsp.disable-rule".to_string(),
+                    arguments: Some(vec![
+                        serde_json::to_value(DisableRuleParams {
+                            rule: data.rule.clone(),
+                        })
+                        .unwrap(),
+                    ]),
+                }),
+                diagnostics: Some(vec![diag.clone()]),
+                ..Default::default()
+            });
+            if !data.category.is_empty() {
+                actions.push(CodeAction {
+                    title: self.tr(Msg::DisableCategory(&data.category)),
+                    kind: Some(CodeActionKind::QUICKFIX),
+                    command: Some(lsp_types::Command {
+                        title: self.tr(Msg::DisableCategory(&data.category)),
+                        command: "languagetool-lsp.disable-category".to_string(),
+                        arguments: Some(vec![
+                            serde_json::to_value(DisableCategoryParams {
+                                category: data.category.clone(),
+                            })
+                            .unwrap(),
+                        ]),
+                    }),
+                    diagnostics: Some(vec![diag.clone()]),
+                    ..Default::default()
+                });
+            }
         }
 
         // Ignore diagnostics
         if !lt_diags.is_empty() {
             actions.push(CodeAction {
-                title: "Ignore Lints".to_string(),
+                title: self.tr(Msg::IgnorePermanently),
+                kind: Some(CodeActionKind::QUICKFIX),
+                command: Some(lsp_types::Command {
+                    title: self.tr(Msg::IgnorePermanently),
+                    command: "languagetool-lsp.ignore-permanently".to_string(),
+                    arguments: Some(vec![
+                        serde_json::to_value(LTCommandParams {
+                            text_document: params.text_document.clone(),
+                            range: params.range,
+                        })
+                        .unwrap(),
+                    ]),
+                }),
+                diagnostics: Some(lt_diags.clone()),
+                ..Default::default()
+            });
+            actions.push(CodeAction {
+                title: self.tr(Msg::IgnoreLints),
                 kind: Some(CodeActionKind::QUICKFIX),
                 command: Some(lsp_types::Command {
-                    title: "Ignore Lints".to_string(),
+                    title: self.tr(Msg::IgnoreLints),
                     command: "languagetool-lsp.ignore".to_string(),
                     arguments: Some(vec![
                         serde_json::to_value(LTCommandParams {
@@ -286,12 +748,26 @@ impl LanguageServer for Backend {
             })
         }
 
+        // Apply the unambiguous replacements
+        let edits = doc.fix_all();
+        if !edits.is_empty() {
+            actions.push(CodeAction {
+                title: self.tr(Msg::FixAll),
+                kind: Some(CodeActionKind::new("source.fixAll.languagetool-lsp")),
+                edit: Some(WorkspaceEdit {
+                    changes: Some([(params.text_document.uri.clone(), edits)].into()),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            });
+        }
+
         // Check spelling
         actions.push(CodeAction {
-            title: "Check Spelling".to_string(),
+            title: self.tr(Msg::CheckSpelling),
             kind: Some(CodeActionKind::SOURCE),
             command: Some(lsp_types::Command {
-                title: "Check Spelling".to_string(),
+                title: self.tr(Msg::CheckSpelling),
                 command: "languagetool-lsp.check".to_string(),
                 arguments: Some(vec![
                     serde_json::to_value(LTCommandParams {
@@ -313,15 +789,16 @@ impl LanguageServer for Backend {
             if !selection.is_empty() && !selection.contains(char::is_whitespace) {
                 info!("add synonyms {start}..{end} {selection:?}");
                 actions.push(CodeAction {
-                    title: format!("Synonyms for {selection:?}"),
+                    title: self.tr(Msg::SynonymsFor(selection)),
                     kind: Some(CodeActionKind::SOURCE),
                     command: Some(lsp_types::Command {
-                        title: "Synonyms".to_string(),
+                        title: self.tr(Msg::Synonyms),
                         command: "languagetool-lsp.synonyms".to_string(),
                         arguments: Some(vec![
-                            serde_json::to_value(LTCommandParams {
+                            serde_json::to_value(PickParams {
                                 text_document: params.text_document.clone(),
                                 range: params.range,
+                                pick: true,
                             })
                             .unwrap(),
                         ]),
@@ -331,58 +808,364 @@ impl LanguageServer for Backend {
             }
         }
 
+        if rephrase {
+            actions.push(CodeAction {
+                title: self.tr(Msg::RephraseSentence),
+                kind: Some(CodeActionKind::REFACTOR_REWRITE),
+                command: Some(lsp_types::Command {
+                    title: self.tr(Msg::RephraseSentence),
+                    command: "languagetool-lsp.rephrase".to_string(),
+                    arguments: Some(vec![
+                        serde_json::to_value(PickParams {
+                            text_document: params.text_document.clone(),
+                            range: params.range,
+                            pick: true,
+                        })
+                        .unwrap(),
+                    ]),
+                }),
+                ..Default::default()
+            });
+        }
+
         Ok((!actions.is_empty()).then_some(actions.into_iter().map(|a| a.into()).collect()))
     }
 
+    async fn code_action_resolve(&self, mut action: CodeAction) -> jsonrpc::Result<CodeAction> {
+        if action.edit.is_none()
+            && let Some(replace) = action
+                .data
+                .take()
+                .and_then(|d| serde_json::from_value::<ReplaceData>(d).ok())
+        {
+            action.edit = Some(replace.edit());
+        }
+        Ok(action)
+    }
+
+    async fn hover(&self, params: HoverParams) -> jsonrpc::Result<Option<Hover>> {
+        let TextDocumentPositionParams {
+            text_document,
+            position,
+        } = params.text_document_position_params;
+
+        let open_docs = self.documents.read().await;
+        let Some(doc) = open_docs.get(&text_document.uri) else {
+            return Ok(None);
+        };
+        let Some(offset) = doc.source.to_offset(position) else {
+            return Ok(None);
+        };
+        let matches = doc.matches.touching(offset..offset).collect::<Vec<_>>();
+        let Some(first) = matches.first() else {
+            return Ok(None);
+        };
+
+        let value = matches
+            .iter()
+            .map(|m| self.hover_text(m))
+            .collect::<Vec<_>>()
+            .join("\n\n---\n\n");
+        let range = DocRange {
+            start: doc.source.to_position(first.range.start).unwrap(),
+            end: doc.source.to_position(first.range.end).unwrap(),
+        };
+        Ok(Some(Hover {
+            contents: HoverContents::Markup(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value,
+            }),
+            range: Some(range),
+        }))
+    }
+
+    async fn completion(
+        &self,
+        params: CompletionParams,
+    ) -> jsonrpc::Result<Option<CompletionResponse>> {
+        // Only offer synonyms if explicitly requested, not while typing
+        if params
+            .context
+            .is_some_and(|c| c.trigger_kind != CompletionTriggerKind::INVOKED)
+        {
+            return Ok(None);
+        }
+        let pos = params.text_document_position;
+        let open_docs = self.documents.read().await;
+        let Some(doc) = open_docs.get(&pos.text_document.uri) else {
+            return Ok(None);
+        };
+        let line_idx = pos.position.line as usize;
+        let (Some(offset), Some(((start, _), line))) = (
+            doc.source.to_offset(pos.position),
+            doc.source.line_range(line_idx..line_idx + 1),
+        ) else {
+            return Ok(None);
+        };
+        let Some(word) = util::word_at(&line, offset - start.byte) else {
+            return Ok(None);
+        };
+        let line = line.to_string();
+        let line_start = start.byte;
+        let encoding = doc.source.encoding();
+        let language_id = doc.language_id.clone();
+        drop(open_docs);
+
+        // Do not interfere with the completions of other servers, e.g. for code
+        if !self.is_checked(&pos.text_document.uri, &language_id).await {
+            return Ok(None);
+        }
+        info!("Synonyms for {:?}", &line[word.clone()]);
+        let selection = line_start + word.start..line_start + word.end;
+        let synonyms = match self.query_synonyms(&pos.text_document.uri, selection).await {
+            Ok(synonyms) => synonyms,
+            Err(err) => {
+                warn!("{err}");
+                return Ok(None);
+            }
+        };
+
+        let character = |i: usize| encoding.len(&line[..i]) as u32;
+        let range = DocRange {
+            start: Position::new(pos.position.line, character(word.start)),
+            end: Position::new(pos.position.line, character(word.end)),
+        };
+        let items = synonyms
+            .into_iter()
+            .enumerate()
+            .map(|(i, synonym)| CompletionItem {
+                label: synonym.clone(),
+                kind: Some(CompletionItemKind::TEXT),
+                detail: Some(self.tr(Msg::Synonyms)),
+                // Keep the order of the provider and do not filter by the word
+                sort_text: Some(format!("{i:04}")),
+                filter_text: Some(line[word.clone()].to_string()),
+                text_edit: Some(CompletionTextEdit::Edit(TextEdit {
+                    range,
+                    new_text: synonym,
+                })),
+                ..Default::default()
+            })
+            .collect();
+        Ok(Some(CompletionResponse::Array(items)))
+    }
+
+    async fn diagnostic(
+        &self,
+        params: DocumentDiagnosticParams,
+    ) -> jsonrpc::Result<DocumentDiagnosticReportResult> {
+        let uri = params.text_document.uri;
+        info!("Diagnostic: {}", uri.as_str());
+        // The documents are locked before the settings elsewhere
+        let settings = self.settings.read().await.clone();
+        let items = self
+            .documents
+            .read()
+            .await
+            .get(&uri)
+            .map(|doc| doc.diagnostics(&uri, &settings, self.lang()))
+            .unwrap_or_default();
+
+        let result_id = result_id(&items);
+        let report = if params.previous_result_id.as_ref() == Some(&result_id) {
+            DocumentDiagnosticReport::Unchanged(RelatedUnchangedDocumentDiagnosticReport {
+                related_documents: None,
+                unchanged_document_diagnostic_report: UnchangedDocumentDiagnosticReport {
+                    result_id,
+                },
+            })
+        } else {
+            DocumentDiagnosticReport::Full(RelatedFullDocumentDiagnosticReport {
+                related_documents: None,
+                full_document_diagnostic_report: FullDocumentDiagnosticReport {
+                    result_id: Some(result_id),
+                    items,
+                },
+            })
+        };
+        Ok(DocumentDiagnosticReportResult::Report(report))
+    }
+
+    async fn workspace_diagnostic(
+        &self,
+        params: WorkspaceDiagnosticParams,
+    ) -> jsonrpc::Result<WorkspaceDiagnosticReportResult> {
+        info!("WorkspaceDiagnostic");
+        let settings = self.settings.read().await.clone();
+        let open_docs = self.documents.read().await;
+        let items = open_docs
+            .iter()
+            .map(|(uri, doc)| {
+                let items = doc.diagnostics(uri, &settings, self.lang());
+                let result_id = result_id(&items);
+                let version = doc.version.map(i64::from);
+                let previous = params
+                    .previous_result_ids
+                    .iter()
+                    .find(|p| &p.uri == uri)
+                    .map(|p| &p.value);
+                if previous == Some(&result_id) {
+                    WorkspaceDocumentDiagnosticReport::Unchanged(
+                        WorkspaceUnchangedDocumentDiagnosticReport {
+                            uri: uri.clone(),
+                            version,
+                            unchanged_document_diagnostic_report:
+                                UnchangedDocumentDiagnosticReport { result_id },
+                        },
+                    )
+                } else {
+                    WorkspaceDocumentDiagnosticReport::Full(WorkspaceFullDocumentDiagnosticReport {
+                        uri: uri.clone(),
+                        version,
+                        full_document_diagnostic_report: FullDocumentDiagnosticReport {
+                            result_id: Some(result_id),
+                            items,
+                        },
+                    })
+                }
+            })
+            .collect();
+        Ok(WorkspaceDiagnosticReportResult::Report(
+            WorkspaceDiagnosticReport { items },
+        ))
+    }
+
     async fn execute_command(
         &self,
         params: ExecuteCommandParams,
     ) -> jsonrpc::Result<Option<lsp_types::LSPAny>> {
         info!("ExecuteCommand: {:?}", params.command);
         let ExecuteCommandParams {
-            command,
-            mut arguments,
-            ..
+            command, arguments, ..
         } = params;
 
-        if arguments.len() != 1 {
-            error!("Invalid arguments: {arguments:?}");
-            return Err(jsonrpc::Error::invalid_params(
-                "Invalid number of arguments".to_string(),
-            ));
+        match command.as_str() {
+            "languagetool-lsp.accept" => {
+                let params = command_argument::<AcceptParams>(arguments)?;
+                self.command_accept(params).await;
+                return Ok(None);
+            }
+            "languagetool-lsp.disable-rule" => {
+                let params = command_argument::<DisableRuleParams>(arguments)?;
+                let res = self.command_disable_rule(params).await;
+                self.command_result(res).await?;
+                return Ok(None);
+            }
+            "languagetool-lsp.disable-category" => {
+                let params = command_argument::<DisableCategoryParams>(arguments)?;
+                let res = self.command_disable_category(params).await;
+                self.command_result(res).await?;
+                return Ok(None);
+            }
+            "languagetool-lsp.rules-browse" => {
+                let params = if arguments.is_empty() {
+                    RulesBrowseParams::default()
+                } else {
+                    command_argument(arguments)?
+                };
+                let res = self.command_rules_browse(params).await;
+                self.command_result(res).await?;
+                return Ok(None);
+            }
+            "languagetool-lsp.check-workspace" => {
+                let params = if arguments.is_empty() {
+                    WorkspaceCheckParams::default()
+                } else {
+                    command_argument(arguments)?
+                };
+                // Checking the workspace takes a while
+                let backend = self.clone();
+                tokio::spawn(async move {
+                    let res = backend.command_check_workspace(params).await;
+                    backend.command_result(res).await.ok();
+                });
+                return Ok(None);
+            }
+            "languagetool-lsp.words-list" => {
+                let res = self.command_words_list().await;
+                let words = self.command_result(res).await?;
+                return Ok(Some(serde_json::json!({ "words": words })));
+            }
+            "languagetool-lsp.words-bulk-add" => {
+                let params = command_argument::<WordsParams>(arguments)?;
+                let res = self.add_words(&params.words, params.dict.as_deref()).await;
+                self.command_result(res).await?;
+                return Ok(None);
+            }
+            "languagetool-lsp.words-bulk-remove" => {
+                let params = command_argument::<WordsParams>(arguments)?;
+                let res = self.command_words_remove(params).await;
+                self.command_result(res).await?;
+                return Ok(None);
+            }
+            "languagetool-lsp.set-language" => {
+                let params = command_argument::<SetLanguageParams>(arguments)?;
+                let res = self.command_set_language(params).await;
+                self.command_result(res).await?;
+                return Ok(None);
+            }
+            "languagetool-lsp.languages" => {
+                let res = self.supported_languages().await;
+                let languages = self.command_result(res).await?;
+                return Ok(Some(serde_json::json!({ "languages": languages })));
+            }
+            "languagetool-lsp.words-sync" => {
+                let res = self.command_words_sync(true).await;
+                self.command_result(res).await?;
+                return Ok(None);
+            }
+            "languagetool-lsp.dictionary-normalize" => {
+                let res = self.command_dictionary_normalize().await;
+                self.command_result(res).await?;
+                return Ok(None);
+            }
+            "languagetool-lsp.status" => {
+                let status = self.command_status().await;
+                return Ok(Some(serde_json::json!(status)));
+            }
+            "languagetool-lsp.synonyms" => {
+                let params = command_argument::<PickParams>(arguments)?;
+                let res = self.command_synonyms(&params).await;
+                let result = self.command_result(res).await?;
+                return Ok(Some(serde_json::json!(result)));
+            }
+            "languagetool-lsp.rephrase" => {
+                let params = command_argument::<PickParams>(arguments)?;
+                let res = self.command_rephrase(&params).await;
+                let result = self.command_result(res).await?;
+                return Ok(Some(serde_json::json!(result)));
+            }
+            _ => {}
         }
 
-        let first = arguments.remove(0);
-        let params = serde_json::from_value::<LTCommandParams>(first)
-            .map_err(|e| jsonrpc::Error::invalid_params(format!("Invalid params: {e}")))?;
+        let params = command_argument::<LTCommandParams>(arguments)?;
 
-        let mut open_docs = self.documents.write().await;
-        let Some(doc) = open_docs.get_mut(&params.text_document.uri) else {
-            error!("No document found: {}", params.text_document.uri.as_str());
+        let uri = &params.text_document.uri;
+        if !self.documents.read().await.contains_key(uri) {
+            error!("No document found: {}", uri.as_str());
             return Ok(None);
-        };
+        }
 
+        // The commands only lock the document while they are not waiting for the server
         let res = match command.as_str() {
-            "languagetool-lsp.check" => self.command_check(params.range, doc).await,
-            "languagetool-lsp.synonyms" => self.command_synonyms(params.range, doc).await,
-            "languagetool-lsp.ignore" => self.command_ignore(params.range, doc).await,
-            "languagetool-lsp.words-add" => self.command_words_add(params.range, doc).await,
+            "languagetool-lsp.check" => self.command_check(uri, params.range).await,
+            "languagetool-lsp.ignore" => self.command_ignore(uri, params.range).await,
+            "languagetool-lsp.ignore-permanently" => {
+                self.command_ignore_permanently(uri, params.range).await
+            }
+            "languagetool-lsp.fix-all" => self.command_fix_all(uri).await,
+            "languagetool-lsp.words-add" => self.command_words_add(uri, params.range).await,
             _ => {
                 error!("Unknown command: {command:?}");
                 return Err(jsonrpc::Error::method_not_found());
             }
         };
 
-        if let Err(err) = res {
-            error!("Command failed: {err}\n{}", err.backtrace());
-            self.client
-                .show_message(MessageType::ERROR, format!("{err}"))
-                .await;
-            return Err(jsonrpc::Error::internal_error());
-        } else {
-            self.show_diagnostics(&params.text_document.uri, doc).await;
+        self.command_result(res).await?;
+        let mut open_docs = self.documents.write().await;
+        if let Some(doc) = open_docs.get_mut(uri) {
+            self.show_diagnostics(uri, doc).await;
         }
-
         Ok(None)
     }
 }
@@ -393,235 +1176,2493 @@ struct LTCommandParams {
     range: lsp_types::Range,
 }
 
-impl Backend {
-    async fn show_diagnostics(&self, uri: &Uri, doc: &mut Document) {
-        let diags = doc.diagnostics();
-        self.client
-            .publish_diagnostics(uri.clone(), diags, doc.version)
-            .await
-    }
+/// Parameters of the commands that offer replacements of the selection
+#[derive(Serialize, Deserialize)]
+struct PickParams {
+    text_document: lsp_types::TextDocumentIdentifier,
+    range: lsp_types::Range,
+    /// Show the replacements as message actions and apply the chosen one,
+    /// otherwise they are only returned
+    #[serde(default)]
+    pick: bool,
+}
 
-    async fn update_matches(&self, doc: &mut Document) -> Result<()> {
-        let changes = doc.changed_lines.changes().clone();
-        doc.changed_lines.clear();
+/// Synonyms of the selected word, e.g. for a picker of the client
+#[derive(Serialize)]
+struct SynonymsResult {
+    word: String,
+    /// Range of the word without the surrounding whitespace
+    range: lsp_types::Range,
+    synonyms: Vec<String>,
+}
 
-        for lines in changes {
-            info!("Check lines: {lines:?}");
+/// Alternative formulations of the sentence
+#[derive(Serialize)]
+struct RephraseResult {
+    sentence: String,
+    range: lsp_types::Range,
+    rephrasings: Vec<String>,
+}
 
-            // TODO: Parse markdown/latex/typst
-            let (mut range, mut annot) = plaintext::annotate(&doc.source, lines)?;
-            range.start += annot.optimize();
-            if annot.len() == 0 {
-                info!("Skip empty annotation");
-                continue;
-            }
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+struct RulesBrowseParams {
+    /// Language of the rules, defaults to the static language
+    language: Option<String>,
+}
 
-            info!("Check {range:?} ({})", annot.len());
-            let settings = self.settings.read().await.clone();
-            let mut matches = api::check(annot, range.start, &settings, None).await?;
-            info!("Matches: {}", matches.len());
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+struct WorkspaceCheckParams {
+    /// Glob patterns of the checked files, defaults to the workspace files
+    /// of the settings
+    include: Vec<String>,
+}
 
-            for m in &matches {
-                info!(
+#[derive(Serialize, Deserialize)]
+struct AcceptParams {
+    rule: String,
+    replacement: String,
+}
+
+/// Replacement of a code action, resolved into an edit when applied
+#[derive(Serialize, Deserialize)]
+struct ReplaceData {
+    uri: Uri,
+    range: DocRange,
+    replacement: String,
+}
+
+impl ReplaceData {
+    fn edit(&self) -> WorkspaceEdit {
+        WorkspaceEdit {
+            changes: Some(
+                [(
+                    self.uri.clone(),
+                    vec![TextEdit {
+                        range: self.range,
+                        new_text: self.replacement.clone(),
+                    }],
+                )]
+                .into(),
+            ),
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct SetLanguageParams {
+    text_document: lsp_types::TextDocumentIdentifier,
+    /// Language code, or none to use the settings again
+    language: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct WordsParams {
+    words: Vec<String>,
+    /// Named dictionary of the premium account, the default dictionary if
+    /// not specified
+    #[serde(default)]
+    dict: Option<String>,
+}
+
+/// Word of the dictionary listing
+#[derive(Serialize)]
+struct DictionaryWord {
+    word: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    language: Option<String>,
+    /// One of "settings", "user", "workspace" or "remote"
+    source: &'static str,
+}
+
+/// Result of the status command
+#[derive(Serialize)]
+struct ServerStatus {
+    server: String,
+    /// Software of the server, missing if it is not reachable
+    software: Option<Software>,
+    error: Option<String>,
+    offline: bool,
+    /// Request limits of the server
+    requests_per_minute: f64,
+    max_request_size: usize,
+    dictionary_words: usize,
+    known_words: usize,
+    false_positives: usize,
+}
+
+#[derive(Serialize, Deserialize)]
+struct DisableRuleParams {
+    rule: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct DisableCategoryParams {
+    category: String,
+}
+
+/// Additional data attached to the published diagnostics
+#[derive(Serialize, Deserialize)]
+struct DiagnosticData {
+    rule: String,
+    category: String,
+    replacements: Vec<String>,
+}
+
+/// Byte range and text of the paragraph around the selection, with the
+/// markup of the annotation replaced by whitespace
+fn plain_paragraph(
+    language_id: &str,
+    source: &SourceFile,
+    settings: &Settings,
+    selection: &Range<usize>,
+) -> Result<(Range<usize>, String)> {
+    let (Some(first), Some(last)) = (
+        source.to_position(selection.start),
+        source.to_position(selection.end),
+    ) else {
+        return Err(anyhow!("Invalid range: {selection:?}"));
+    };
+    let lines = first.line as usize..last.line as usize + 1;
+    let (range, annot) = annotated::annotate(language_id, source, lines.clone(), settings)?;
+    if range.start <= selection.start && selection.end <= range.end && annot.len() == range.len() {
+        return Ok((range, annot.plain()));
+    }
+    // Outside of the checked text, e.g. in code
+    let (range, text) = annotated::paragraph(source, lines)?;
+    Ok((range, text.into_owned()))
+}
+
+fn command_argument<T: DeserializeOwned>(
+    mut arguments: Vec<lsp_types::LSPAny>,
+) -> jsonrpc::Result<T> {
+    if arguments.len() != 1 {
+        error!("Invalid arguments: {arguments:?}");
+        return Err(jsonrpc::Error::invalid_params(
+            "Invalid number of arguments".to_string(),
+        ));
+    }
+    serde_json::from_value::<T>(arguments.remove(0))
+        .map_err(|e| jsonrpc::Error::invalid_params(format!("Invalid params: {e}")))
+}
+
+impl Backend {
+    fn new(client: Client, offline_stub: bool, logger: Logger, docker: Arc<Docker>) -> Self {
+        Self {
+            client,
+            settings: Default::default(),
+            client_settings: Default::default(),
+            root: Default::default(),
+            documents: Default::default(),
+            dictionary: Default::default(),
+            learning: Arc::new(RwLock::new(Learning::load())),
+            false_positives: Default::default(),
+            known_words: Default::default(),
+            languages: Default::default(),
+            offline: Default::default(),
+            primary_server: Default::default(),
+            last_error: Default::default(),
+            next_open_check: Default::default(),
+            lang: Default::default(),
+            encoding: Default::default(),
+            pull_diagnostics: Default::default(),
+            work_done_progress: Default::default(),
+            resolve_edits: Default::default(),
+            watch_files: Default::default(),
+            pull_configuration: Default::default(),
+            message_actions: Default::default(),
+            premium_warned: Default::default(),
+            offline_stub,
+            logger,
+            docker,
+        }
+    }
+
+    /// Localize the message for the client
+    fn tr(&self, msg: Msg) -> String {
+        msg.text(self.lang())
+    }
+
+    fn lang(&self) -> Lang {
+        self.lang.get().copied().unwrap_or_default()
+    }
+
+    fn encoding(&self) -> Encoding {
+        self.encoding.get().copied().unwrap_or_default()
+    }
+
+    /// Request the settings from the client
+    async fn pull_settings(&self) {
+        let item = ConfigurationItem {
+            scope_uri: None,
+            section: None,
+        };
+        match self.client.configuration(vec![item]).await {
+            Ok(values) => {
+                if let Some(settings @ serde_json::Value::Object(_)) = values.into_iter().next() {
+                    *self.client_settings.write().await = settings;
+                }
+            }
+            Err(err) => warn!("Failed to request the settings: {err}"),
+        }
+    }
+
+    /// Merge the client settings with the project settings
+    async fn load_settings(&self) {
+        let mut settings = match self.client_settings.read().await.clone() {
+            serde_json::Value::Object(settings) => settings,
+            _ => Default::default(),
+        };
+        if let Some(root) = self.root.read().await.as_deref() {
+            match project::load_settings(root) {
+                Ok(project) => settings.extend(project),
+                Err(err) => error!("Invalid project settings: {err}"),
+            }
+        }
+        let previous = self.settings.read().await.clone();
+        let (mut settings, mut errors) = Settings::parse(settings, &previous);
+        errors.extend(settings.validate());
+        if !errors.is_empty() {
+            let errors = errors.join("; ");
+            error!("Invalid settings: {errors}");
+            self.client
+                .show_message(MessageType::ERROR, self.tr(Msg::InvalidSettings(&errors)))
+                .await;
+        }
+        if let Some(root) = self.root.read().await.as_deref() {
+            let path = project::path(root, local::terminology::FILE);
+            match local::terminology::load(&path) {
+                Ok(terms) => settings.terminology.extend(terms),
+                Err(err) => error!("Invalid terminology {path:?}: {err}"),
+            }
+        }
+        self.logger.set_level(settings.log_level);
+        settings.offline_stub |= self.offline_stub;
+        // Start again with the configured server
+        self.primary_server.write().await.take();
+        if settings.docker.enabled && !settings.offline_stub {
+            settings.server = settings.docker.url();
+            self.docker.start(&settings);
+        } else {
+            self.docker.stop().await;
+        }
+        self.load_known_words(&settings).await;
+        *self.settings.write().await = settings;
+        self.load_dictionary().await;
+    }
+
+    /// Build the set of known words from the configured word lists
+    async fn load_known_words(&self, settings: &Settings) {
+        let mut known_words = self.known_words.write().await;
+        *known_words = None;
+        if settings.word_lists.is_empty() {
+            return;
+        }
+        let root = self.root.read().await.clone().unwrap_or_default();
+        let paths = settings
+            .word_lists
+            .iter()
+            .map(|path| root.join(path))
+            .collect::<Vec<_>>();
+        match KnownWords::load(&paths) {
+            Ok(known) => {
+                info!("Loaded {} known words", known.len());
+                *known_words = Some(known);
+            }
+            Err(err) => error!("Invalid word lists {paths:?}: {err}"),
+        }
+    }
+
+    /// Highlight unknown words of the changed lines until they are checked
+    async fn provisional_matches(&self, doc: &mut Document) {
+        let known_words = self.known_words.read().await;
+        let Some(known_words) = known_words.as_ref() else {
+            return;
+        };
+        let dict = self.dictionary.read().await;
+        let settings = self.settings.read().await;
+        if settings.scope(&doc.language_id) == Scope::Grammar {
+            return;
+        }
+        // The language of the last check applies to language-tagged entries
+        let language = doc
+            .language
+            .clone()
+            .or_else(|| settings.static_language.clone())
+            .or_else(|| doc.detected_language.clone())
+            .unwrap_or_default();
+        for lines in doc.changed_lines.changes() {
+            let Ok((range, annot)) =
+                annotated::annotate(&doc.language_id, &doc.source, lines.clone(), &settings)
+            else {
+                continue;
+            };
+            doc.matches
+                .retain(|m| !(m.rule == known_words::RULE && m.range.touches(&range)));
+            for (start, text) in annot.text_parts() {
+                let unknown = known_words.check(text, range.start + start);
+                doc.matches.extend(unknown.into_iter().filter(|m| {
+                    let word = doc.source.get(m.range.clone()).unwrap_or_default();
+                    !dict.contains(&word, &language)
+                }));
+            }
+        }
+    }
+
+    /// Build the dictionary from the configured words, the user dictionary
+    /// and the project dictionary file
+    async fn load_dictionary(&self) {
+        let mut dict = Dictionary::default();
+        for word in &self.settings.read().await.dictionary {
+            dict.insert(word, None);
+        }
+        let root = self.root.read().await.clone();
+        let files = user_dictionary()
+            .into_iter()
+            .chain(root.map(|root| project::path(&root, dictionary::FILE)));
+        for path in files {
+            match DictionaryFile::load(&path) {
+                Ok(file) => {
+                    info!("Loaded {} words from {path:?}", file.entries.len());
+                    dict.extend(&file);
+                }
+                Err(err) => error!("Failed to load dictionary {path:?}: {err}"),
+            }
+        }
+        *self.dictionary.write().await = dict;
+    }
+
+    /// Load the false positives file, returns true if they have changed
+    async fn load_false_positives(&self) -> bool {
+        let Some(root) = self.root.read().await.clone() else {
+            return false;
+        };
+        let path = project::path(&root, false_positives::FILE);
+        match FalsePositives::load(&path) {
+            Ok(file) => {
+                info!(
+                    "Loaded {} false positives from {path:?}",
+                    file.entries.len()
+                );
+                let mut false_positives = self.false_positives.write().await;
+                let changed = *false_positives != file;
+                *false_positives = file;
+                changed
+            }
+            Err(err) => {
+                error!("Failed to load false positives {path:?}: {err}");
+                false
+            }
+        }
+    }
+
+    /// Languages supported by the server, fetched once per server
+    async fn supported_languages(&self) -> Result<Vec<Language>> {
+        let settings = self.settings.read().await.clone();
+        let server = settings.server.to_string();
+        if let Some(languages) = self.languages.read().await.get(&server) {
+            return Ok(languages.clone());
+        }
+        let languages = api::languages::list(&settings).await?;
+        info!("Supported languages: {}", languages.len());
+        self.languages
+            .write()
+            .await
+            .insert(server, languages.clone());
+        Ok(languages)
+    }
+
+    /// Waits until the server of the Docker container is ready, if enabled
+    async fn wait_for_server(&self) -> Result<()> {
+        let docker = self.settings.read().await.docker.clone();
+        if !docker.enabled {
+            return Ok(());
+        }
+        // Invalid values are reported by the validation but still applied
+        let timeout =
+            Duration::try_from_secs_f64(docker.startup_timeout.max(0.0)).unwrap_or(Duration::MAX);
+        self.docker.wait_ready(timeout).await
+    }
+
+    /// Report configured languages that are not supported by the server
+    fn validate_languages(&self) {
+        let backend = self.clone();
+        tokio::spawn(async move {
+            if let Err(err) = backend.wait_for_server().await {
+                warn!("{err}");
+                return;
+            }
+            let languages = match backend.supported_languages().await {
+                Ok(languages) => languages,
+                Err(err) => {
+                    warn!("Failed to fetch the languages: {err}");
+                    let server = backend.settings.read().await.server.to_string();
+                    let message = backend.tr(Msg::ServerUnreachable(&server));
+                    backend
+                        .client
+                        .show_message(MessageType::WARNING, message)
+                        .await;
+                    return;
+                }
+            };
+            let settings = backend.settings.read().await.clone();
+            let invalid = api::languages::invalid(&settings, &languages);
+            if !invalid.is_empty() {
+                let message = backend.tr(Msg::UnsupportedLanguages(&invalid.join(", ")));
+                backend
+                    .client
+                    .show_message(MessageType::WARNING, message)
+                    .await;
+            }
+        });
+    }
+
+    /// Checks if the document is included in the checked files
+    async fn is_checked(&self, uri: &Uri, language_id: &str) -> bool {
+        // Untitled and other non-file documents are not part of the
+        // workspace, so the path patterns do not apply
+        if uri.to_file_path().is_none() {
+            return self.settings.read().await.is_language_checked(language_id);
+        }
+        let path = self.relative_path(uri).await;
+        self.settings.read().await.is_checked(language_id, &path)
+    }
+
+    /// Path of the document relative to the workspace, with `/` as separator
+    async fn relative_path(&self, uri: &Uri) -> String {
+        let Some(path) = uri.to_file_path() else {
+            return uri.path().to_string();
+        };
+        let root = self.root.read().await;
+        let path = root
+            .as_deref()
+            .and_then(|root| path.strip_prefix(root).ok())
+            .unwrap_or(&path);
+        path.to_string_lossy().replace('\\', "/")
+    }
+
+    /// Checks if the uri refers to the given file in the project directory
+    async fn is_project_file(&self, uri: &Uri, file: &str) -> bool {
+        let Some(root) = self.root.read().await.clone() else {
+            return false;
+        };
+        uri.to_file_path()
+            .is_some_and(|path| path == project::path(&root, file))
+    }
+
+    /// Markdown description of the match and its rule
+    fn hover_text(&self, m: &Match) -> String {
+        let details = &m.details;
+        let mut text = format!("**{}**\n\n{}\n\n", m.title, m.message);
+        let category = if details.category_name.is_empty() {
+            &m.category
+        } else {
+            &details.category_name
+        };
+        text += &format!("*{category}* · `{}`", m.rule);
+        if !details.description.is_empty() {
+            text += &format!(": {}", details.description);
+        }
+        if !m.replacements.is_empty() {
+            let replacements = m
+                .replacements
+                .iter()
+                .map(|r| format!("`{r}`"))
+                .collect::<Vec<_>>()
+                .join(", ");
+            text += &format!("\n\n{}: {replacements}", self.tr(Msg::Suggestions));
+        }
+        let links = details
+            .urls
+            .iter()
+            .map(|url| format!("[{}]({url})", self.tr(Msg::MoreInformation)))
+            .chain(
+                details
+                    .page
+                    .iter()
+                    .map(|url| format!("[{}]({url})", self.tr(Msg::RulePage))),
+            )
+            .collect::<Vec<_>>();
+        if !links.is_empty() {
+            text += &format!("\n\n{}", links.join(" · "));
+        }
+        text
+    }
+
+    /// Report command errors to the user
+    async fn command_result<T>(&self, res: Result<T>) -> jsonrpc::Result<T> {
+        match res {
+            Ok(val) => Ok(val),
+            Err(err) => {
+                error!("Command failed: {err}\n{}", err.backtrace());
+                self.client
+                    .show_message(MessageType::ERROR, format!("{err}"))
+                    .await;
+                Err(jsonrpc::Error::internal_error())
+            }
+        }
+    }
+
+    async fn show_diagnostics(&self, uri: &Uri, doc: &mut Document) {
+        if self.pull_diagnostics.load(Ordering::Acquire) {
+            self.refresh_diagnostics();
+            return;
+        }
+        let diags = doc.diagnostics(uri, &*self.settings.read().await, self.lang());
+        self.client
+            .publish_diagnostics(uri.clone(), diags, doc.version)
+            .await
+    }
+
+    /// Ask the client to pull the new diagnostics, without waiting for the
+    /// response while the document is locked
+    fn refresh_diagnostics(&self) {
+        let client = self.client.clone();
+        tokio::spawn(async move {
+            if let Err(err) = client.workspace_diagnostic_refresh().await {
+                warn!("Diagnostic refresh failed: {err}");
+            }
+        });
+    }
+
+    /// Check the changed lines of the document, returns false on failure
+    async fn check_document(&self, uri: &Uri) -> bool {
+        let Err(err) = self.update_matches(uri).await else {
+            self.last_error.write().await.take();
+            self.schedule_text_check(uri.clone());
+            return true;
+        };
+        if api::is_unavailable(&err) && self.fail_over().await {
+            if let Some(doc) = self.documents.write().await.get_mut(uri) {
+                doc.queued = true;
+            }
+            // Check again with the fallback server
+            let backend = self.clone();
+            tokio::spawn(async move {
+                if !backend.flush_queued().await {
+                    backend.set_offline().await;
+                }
+            });
+        } else if api::is_offline(&err) {
+            let mut open_docs = self.documents.write().await;
+            if let Some(doc) = open_docs.get_mut(uri) {
+                doc.queued = true;
+                // Show the local matches
+                self.show_diagnostics(uri, doc).await;
+            }
+            drop(open_docs);
+            self.set_offline().await;
+        } else {
+            error!("Failed diagnostics: {err}\n{}", err.backtrace());
+            // Show repeated errors only once until a check succeeds
+            let message = format!("{err}");
+            let mut last_error = self.last_error.write().await;
+            if last_error.as_ref() != Some(&message) {
+                self.client.show_message(MessageType::ERROR, &message).await;
+                *last_error = Some(message);
+            }
+        }
+        false
+    }
+
+    /// Check the document after it has not been changed for the delay
+    fn schedule_check(&self, uri: Uri, edits: u64, delay: Duration) {
+        let backend = self.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(delay).await;
+            {
+                let open_docs = backend.documents.read().await;
+                let Some(doc) = open_docs.get(&uri) else {
+                    return;
+                };
+                // Superseded by a later change
+                if doc.edits != edits || doc.changed_lines.changes().is_empty() {
+                    return;
+                }
+            }
+            info!("AutoCheck: {}", uri.as_str());
+            if backend.check_document(&uri).await {
+                let mut open_docs = backend.documents.write().await;
+                if let Some(doc) = open_docs.get_mut(&uri) {
+                    backend.show_diagnostics(&uri, doc).await;
+                }
+            }
+        });
+    }
+
+    /// Returns the settings for checking the document, including the
+    /// language overrides
+    async fn document_settings(
+        &self,
+        uri: &Uri,
+        source: &SourceFile,
+        language_id: &str,
+        language: Option<String>,
+    ) -> Settings {
+        let mut settings = self.settings.read().await.clone();
+        let path = self.relative_path(uri).await;
+        if let Some(language) = settings.document_language(language_id, &path) {
+            settings.static_language = Some(language.to_string());
+        }
+        let mut settings = magic::overrides(source.text(), &settings);
+        if language.is_some() {
+            settings.static_language = language;
+        }
+        settings.scope(language_id).apply(&mut settings);
+        settings
+    }
+
+    /// Check the whole document with the text level rules after it has not
+    /// been changed for the delay
+    fn schedule_text_check(&self, uri: Uri) {
+        let backend = self.clone();
+        tokio::spawn(async move {
+            let settings = backend.settings.read().await;
+            if !settings.text_level_check {
+                return;
+            }
+            let delay = Duration::from_secs_f64(settings.text_level_delay.max(0.0) / 1000.0);
+            drop(settings);
+            let Some(edits) = backend.documents.read().await.get(&uri).map(|d| d.edits) else {
+                return;
+            };
+            tokio::time::sleep(delay).await;
+            if backend.documents.read().await.get(&uri).map(|d| d.edits) != Some(edits) {
+                return;
+            }
+            info!("TextLevelCheck: {}", uri.as_str());
+            match backend.check_text_level(&uri).await {
+                Ok(true) => {
+                    let mut open_docs = backend.documents.write().await;
+                    if let Some(doc) = open_docs.get_mut(&uri) {
+                        backend.show_diagnostics(&uri, doc).await;
+                    }
+                }
+                Ok(false) => {}
+                Err(err) => warn!("Failed text level check: {err}"),
+            }
+        });
+    }
+
+    /// Check the whole document with only the text level rules, which the
+    /// checks of the changed paragraphs skip.
+    ///
+    /// Returns false if the document was changed in the meantime.
+    async fn check_text_level(&self, uri: &Uri) -> Result<bool> {
+        let (source, language_id, language, edits) = {
+            let open_docs = self.documents.read().await;
+            let Some(doc) = open_docs.get(uri) else {
+                return Ok(false);
+            };
+            (
+                doc.source.clone(),
+                doc.language_id.clone(),
+                doc.language.clone(),
+                doc.edits,
+            )
+        };
+        let max_file_size = self.settings.read().await.max_file_size;
+        if !self.is_checked(uri, &language_id).await
+            || (max_file_size > 0 && source.len() > max_file_size)
+        {
+            return Ok(false);
+        }
+        let mut settings = self
+            .document_settings(uri, &source, &language_id, language)
+            .await;
+        settings.mode = Mode::TextLevelOnly;
+
+        let lines = 0..source.lines().len();
+        let (range, annot) = pipeline::annotate(&language_id, &source, lines, &settings)?;
+        let (mut matches, language) = if annot.is_empty() {
+            (Vec::new(), String::new())
+        } else {
+            let result = api::check_auto(annot, range.start, &settings).await?;
+            (result.matches, result.language)
+        };
+        info!("Text level matches: {}", matches.len());
+        self.filter_matches(&mut matches, &source, &settings, &language_id, &language)
+            .await;
+
+        let mut open_docs = self.documents.write().await;
+        let Some(doc) = open_docs.get_mut(uri) else {
+            return Ok(false);
+        };
+        if doc.edits != edits {
+            info!("Discard outdated text level matches");
+            return Ok(false);
+        }
+        // Replace the matches of the previous text level check
+        let text_rules = &mut doc.text_rules;
+        doc.matches.retain(|m| !text_rules.contains(&m.rule));
+        text_rules.extend(matches.iter().map(|m| m.rule.clone()));
+        doc.matches.extend(matches);
+        Ok(true)
+    }
+
+    /// Removes the matches that are filtered locally and orders their
+    /// replacements, like the command line
+    async fn filter_matches(
+        &self,
+        matches: &mut Vec<Match>,
+        source: &SourceFile,
+        settings: &Settings,
+        language_id: &str,
+        language: &str,
+    ) {
+        let dictionary = self.dictionary.read().await;
+        let known_words = self.known_words.read().await;
+        let false_positives = self.false_positives.read().await;
+        let learning = self.learning.read().await;
+        let filter = pipeline::Filter {
+            dictionary: &dictionary,
+            known_words: known_words.as_ref(),
+            false_positives: &false_positives,
+            learning: settings.learning.then_some(&*learning),
+        };
+        filter.apply(matches, source, settings, language_id, language);
+    }
+
+    /// Check the changed lines of the document and report the progress
+    async fn update_matches(&self, uri: &Uri) -> Result<()> {
+        self.wait_for_server().await?;
+        let mut progress = None;
+        let res = self.check_changes(uri, &mut progress).await;
+        if let Some(progress) = progress {
+            progress.end().await;
+        }
+        res
+    }
+
+    /// Check the changed lines of the document.
+    ///
+    /// The document is only locked to take a snapshot and to merge the
+    /// results, so that other requests are not blocked by slow checks.
+    /// Checks of text that is changed in the meantime are cancelled and the
+    /// lines are checked again later, other results are moved to the current
+    /// positions.
+    async fn check_changes(&self, uri: &Uri, progress: &mut Option<Progress>) -> Result<()> {
+        let (max_file_size, max_paragraphs) = {
+            let settings = self.settings.read().await;
+            (settings.max_file_size, settings.max_paragraphs)
+        };
+        let name = uri.as_str().rsplit('/').next().unwrap_or_default();
+        let (source, language_id, language, edits, edited, changes, changed_bytes) = {
+            let mut open_docs = self.documents.write().await;
+            let Some(doc) = open_docs.get_mut(uri) else {
+                return Ok(());
+            };
+            if max_file_size > 0 && doc.source.len() > max_file_size {
+                info!("Skip large document {}", uri.as_str());
+                doc.changed_lines.clear();
+                if !std::mem::replace(&mut doc.too_large, true) {
+                    self.client
+                        .show_message(MessageType::INFO, self.tr(Msg::FileTooLarge(name)))
+                        .await;
+                }
+                return Ok(());
+            }
+            // Check the remaining paragraphs next time
+            let limit = if max_paragraphs > 0 {
+                max_paragraphs
+            } else {
+                usize::MAX
+            };
+            let mut changes = Changes::new();
+            let mut paragraphs = 0;
+            for lines in std::mem::take(&mut doc.changed_lines).changes() {
+                let (end, count) = doc
+                    .source
+                    .limit_paragraphs(lines.clone(), limit - paragraphs);
+                paragraphs += count;
+                if end > lines.start {
+                    changes.add_change(lines.start..end, end - lines.start);
+                }
+                if end < lines.end {
+                    doc.changed_lines
+                        .add_change(end..lines.end, lines.end - end);
+                }
+            }
+            for lines in changes.changes() {
+                doc.checking.add_change(lines.clone(), lines.len());
+            }
+            let source = doc.source.clone();
+            let edited = doc.edited.clone();
+            let changes = changes.changes().clone();
+            let changed_bytes = std::mem::take(&mut doc.changed_bytes).changes().clone();
+            (
+                source,
+                doc.language_id.clone(),
+                doc.language.clone(),
+                doc.edits,
+                edited,
+                changes,
+                changed_bytes,
+            )
+        };
+        if !self.is_checked(uri, &language_id).await {
+            info!("Skip excluded document {}", uri.as_str());
+            if let Some(doc) = self.documents.write().await.get_mut(uri) {
+                doc.checking.clear();
+            }
+            return Ok(());
+        }
+        let mut settings = self
+            .document_settings(uri, &source, &language_id, language)
+            .await;
+        if settings.text_level_check {
+            settings.mode = Mode::AllButTextLevelOnly;
+        }
+
+        if !changes.is_empty() && self.work_done_progress.load(Ordering::Acquire) {
+            let message = format!("{name} (1/{})", changes.len());
+            *progress = Progress::begin(&self.client, self.tr(Msg::Checking), message).await;
+        }
+
+        let mut paragraphs = Vec::new();
+        for (i, lines) in changes.iter().enumerate() {
+            info!("Check lines: {lines:?}");
+            // TODO: Parse typst
+            let (mut range, mut annot) =
+                pipeline::annotate(&language_id, &source, lines.clone(), &settings)?;
+            if annot.is_empty() {
+                info!("Skip empty annotation");
+                continue;
+            }
+            // Only check the sentences of the edits
+            let edits_in_range = changed_bytes
+                .iter()
+                .filter(|c| c.touches(&range))
+                .map(|c| {
+                    let len = annot.len();
+                    c.start.saturating_sub(range.start).min(len)
+                        ..c.end.saturating_sub(range.start).min(len)
+                })
+                .collect::<Vec<_>>();
+            if !edits_in_range.is_empty() {
+                let text = annot.parts().collect::<String>();
+                let span = util::sentence_span(&text, &edits_in_range);
+                if span.len() < annot.len() {
+                    annot = annot.slice(span.clone());
+                    range = range.start + span.start..range.start + span.end;
+                }
+            }
+            paragraphs.push((i, range, annot));
+        }
+
+        // Check multiple paragraphs at the same time, the results are merged
+        // in order
+        let ranges = paragraphs
+            .iter()
+            .map(|(_, range, _)| range.clone())
+            .collect::<Vec<_>>();
+        let concurrency = settings
+            .max_concurrent_checks
+            .clamp(1, settings.endpoint().max_concurrency());
+        let settings = &settings;
+        let language_id = language_id.as_str();
+        let mut checks = stream::iter(paragraphs)
+            .map(|(i, range, annot)| async move {
+                info!("Check {range:?} ({})", annot.len());
+                let local_matches = local::check(&annot, range.start, settings, language_id);
+                let result = api::check_auto(annot, range.start, settings).await;
+                (i, range, local_matches, result)
+            })
+            .buffered(concurrency);
+
+        for checked in 0.. {
+            let next = loop {
+                tokio::select! {
+                    next = checks.next() => break next,
+                    () = edited.notified() => {
+                        let open_docs = self.documents.read().await;
+                        if open_docs.get(uri).is_some_and(|doc| {
+                            ranges[checked..]
+                                .iter()
+                                .all(|range| doc.shift_since(edits, range).is_some())
+                        }) {
+                            continue;
+                        }
+                        info!("Cancel superseded checks {:?}", &ranges[checked..]);
+                        drop(open_docs);
+                        if let Some(doc) = self.documents.write().await.get_mut(uri) {
+                            doc.requeue();
+                        }
+                        return Ok(());
+                    }
+                }
+            };
+            let Some((i, range, local_matches, result)) = next else {
+                break;
+            };
+            if let Some(progress) = progress
+                && i + 1 < changes.len()
+            {
+                let message = format!("{name} ({}/{})", i + 2, changes.len());
+                let percentage = ((i + 1) * 100 / changes.len()) as u32;
+                progress.report(message, percentage).await;
+            }
+
+            let mut open_docs = self.documents.write().await;
+            let Some(doc) = open_docs.get_mut(uri) else {
+                return Ok(());
+            };
+            let Some(shift) = doc.shift_since(edits, &range) else {
+                info!("Discard outdated matches {range:?}");
+                doc.requeue();
+                return Ok(());
+            };
+            let mut local_matches = local_matches;
+            local_matches.retain(|m| settings.scope(language_id).includes(m));
+            let range = shift_range(&range, shift);
+            let result = match result {
+                Ok(result) => result,
+                Err(err) => {
+                    // Local matches are still shown without the server
+                    shift_matches(&mut local_matches, shift);
+                    doc.matches.replace(range, local_matches);
+                    // Keep the unchecked lines for the next attempt
+                    doc.requeue();
+                    return Err(err);
+                }
+            };
+            if !result.premium
+                && !settings.username.is_empty()
+                && !settings.api_key.is_empty()
+                && !self.premium_warned.swap(true, Ordering::AcqRel)
+            {
+                warn!("The premium account is not used");
+                self.client
+                    .show_message(MessageType::WARNING, self.tr(Msg::PremiumNotActive))
+                    .await;
+            }
+            let mut matches = result.matches;
+            matches.extend(local_matches);
+            info!("Matches: {} ({})", matches.len(), result.language);
+
+            for m in &matches {
+                debug!(
                     "Match: {} {} {}: {:?}\n-> {:?}",
                     m.range.start,
                     m.range.end,
                     m.title,
-                    &doc.source.text()[m.range.clone()],
+                    &source.text()[m.range.clone()],
                     &m.replacements
                 );
             }
 
-            // Remove spelling matches part of the dictionary
-            if !settings.sync_dictionary {
-                let dict = self.dictionary.read().await;
-                matches = matches
+            // The dictionary also applies when it is synchronized, as
+            // patterns and case-insensitive entries are only applied locally
+            self.filter_matches(
+                &mut matches,
+                &source,
+                settings,
+                language_id,
+                &result.language,
+            )
+            .await;
+
+            shift_matches(&mut matches, shift);
+            // Remove matches that overlap with the changed lines
+            doc.matches.replace(range, matches);
+            if !result.language.is_empty() {
+                doc.detected_language = Some(result.language);
+            }
+
+            // Only the remaining lines are still being checked
+            if doc.edits == edits {
+                doc.checking.clear();
+                for lines in &changes[i + 1..] {
+                    doc.checking.add_change(lines.clone(), lines.len());
+                }
+            }
+            // Show the results of large documents early, the caller publishes
+            // the final diagnostics
+            let snapshot = (checked + 1 < ranges.len())
+                .then(|| (doc.source.clone(), doc.matches.to_vec(), doc.version));
+            drop(open_docs);
+            if let Some((source, matches, version)) = snapshot {
+                if self.pull_diagnostics.load(Ordering::Acquire) {
+                    self.refresh_diagnostics();
+                } else {
+                    let diags = Document::snapshot_diagnostics(
+                        uri,
+                        &source,
+                        &matches,
+                        settings,
+                        self.lang(),
+                    );
+                    self.client
+                        .publish_diagnostics(uri.clone(), diags, version)
+                        .await;
+                }
+            }
+        }
+
+        let mut open_docs = self.documents.write().await;
+        if let Some(doc) = open_docs.get_mut(uri) {
+            doc.checking.clear();
+            doc.edit_log.clear();
+        }
+        Ok(())
+    }
+
+    /// Apply the corrections and notify the user about the changes
+    async fn autocorrect(
+        &self,
+        uri: Uri,
+        version: Option<i32>,
+        corrections: Vec<(TextEdit, String)>,
+    ) {
+        let summary = corrections
+            .iter()
+            .map(|(edit, original)| format!("{original} → {}", edit.new_text))
+            .collect::<Vec<_>>()
+            .join(", ");
+        info!("Autocorrect: {summary}");
+
+        // The client rejects the edit if the document changed in the meantime
+        let edit = WorkspaceEdit {
+            document_changes: Some(DocumentChanges::Edits(vec![TextDocumentEdit {
+                text_document: OptionalVersionedTextDocumentIdentifier {
+                    uri: uri.clone(),
+                    version,
+                },
+                edits: corrections
                     .into_iter()
-                    .filter(|m| {
-                        !(m.category == "TYPOS"
-                            && dict.contains(&doc.source.text()[m.range.clone()]))
-                    })
-                    .collect();
+                    .map(|(e, _)| OneOf::Left(e))
+                    .collect(),
+            }])),
+            ..Default::default()
+        };
+        match self.client.apply_edit(edit).await {
+            Ok(response) if response.applied => {
+                // The corrected matches were moved to their replacements
+                let rules = self.settings.read().await.autocorrect_rules.clone();
+                let mut open_docs = self.documents.write().await;
+                if let Some(doc) = open_docs.get_mut(&uri) {
+                    let text = doc.source.text();
+                    doc.matches.retain(|m| {
+                        !(rules.contains(&m.rule)
+                            && m.replacements.len() == 1
+                            && text.get(m.range.clone()) == Some(m.replacements[0].as_str()))
+                    });
+                    self.show_diagnostics(&uri, doc).await;
+                }
+                drop(open_docs);
+                self.client
+                    .show_message(MessageType::INFO, self.tr(Msg::Autocorrected(&summary)))
+                    .await;
             }
+            Ok(response) => warn!("Autocorrect rejected: {:?}", response.failure_reason),
+            Err(err) => error!("Autocorrect failed: {err}"),
+        }
+    }
 
-            // Remove matches that overlap with the changed lines
-            doc.matches.retain(|m| !m.range.touches(&range));
-            doc.matches.append(&mut matches);
-            doc.matches.sort_by_key(|m| m.range.start);
+    /// Enter the offline state and periodically retry the queued checks
+    async fn set_offline(&self) {
+        if self.offline.swap(true, Ordering::AcqRel) {
+            return;
+        }
+        warn!("Server unreachable");
+        self.client
+            .show_message(MessageType::WARNING, self.tr(Msg::Offline))
+            .await;
+
+        let backend = self.clone();
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(OFFLINE_RETRY).await;
+                // Probe the server before checking the queued documents
+                let mut settings = backend.settings.read().await.clone();
+                settings.max_retries = 0;
+                if let Err(err) = api::languages::list(&settings).await {
+                    info!("Server still unreachable: {err}");
+                    continue;
+                }
+                if backend.flush_queued().await {
+                    break;
+                }
+            }
+        });
+    }
+
+    /// Check all open documents again, e.g. after the project files changed
+    async fn check_all(&self) {
+        let uris = {
+            let mut open_docs = self.documents.write().await;
+            for doc in open_docs.values_mut() {
+                let lines = doc.source.lines().len();
+                doc.changed_lines.add_change(0..lines, lines);
+            }
+            open_docs.keys().cloned().collect::<Vec<_>>()
+        };
+        for uri in uris {
+            if !self.check_document(&uri).await {
+                continue;
+            }
+            let mut open_docs = self.documents.write().await;
+            if let Some(doc) = open_docs.get_mut(&uri) {
+                self.show_diagnostics(&uri, doc).await;
+            }
+        }
+    }
+
+    /// Check the queued documents, returns false if the server is still unreachable
+    async fn flush_queued(&self) -> bool {
+        let queued = self
+            .documents
+            .read()
+            .await
+            .iter()
+            .filter(|(_, doc)| doc.queued)
+            .map(|(uri, _)| uri.clone())
+            .collect::<Vec<_>>();
+        for uri in queued {
+            info!("Flush {}", uri.as_str());
+            let mut res = self.update_matches(&uri).await;
+            while let Err(err) = &res
+                && api::is_unavailable(err)
+                && self.fail_over().await
+            {
+                res = self.update_matches(&uri).await;
+            }
+            if let Err(err) = &res {
+                if api::is_offline(err) {
+                    return false;
+                }
+                error!("Failed diagnostics: {err}\n{}", err.backtrace());
+            }
+            let mut open_docs = self.documents.write().await;
+            if let Some(doc) = open_docs.get_mut(&uri) {
+                if res.is_ok() {
+                    self.show_diagnostics(&uri, doc).await;
+                }
+                doc.queued = false;
+            }
+        }
+
+        if self.offline.swap(false, Ordering::AcqRel) {
+            info!("Server reachable");
+            self.client
+                .show_message(MessageType::INFO, self.tr(Msg::Online))
+                .await;
+        }
+        true
+    }
+
+    /// Switch to the next fallback server, returns false if there is none
+    async fn fail_over(&self) -> bool {
+        let mut settings = self.settings.write().await;
+        let mut primary = self.primary_server.write().await;
+        let configured = primary.clone().unwrap_or_else(|| settings.server.clone());
+        let fallbacks = settings.fallbacks(&configured);
+        let next = match fallbacks.iter().position(|s| *s == settings.server) {
+            Some(i) => fallbacks.get(i + 1),
+            None => fallbacks.first(),
+        };
+        let Some(next) = next.cloned() else {
+            return false;
+        };
+        warn!(
+            "Server {} unavailable, fail over to {next}",
+            settings.server
+        );
+        settings.server = next.clone();
+        if primary.replace(configured).is_none() {
+            self.probe_primary();
+        }
+        drop(primary);
+        drop(settings);
+        self.client
+            .show_message(
+                MessageType::WARNING,
+                self.tr(Msg::FailedOver(next.as_str())),
+            )
+            .await;
+        true
+    }
+
+    /// Periodically probe the configured server and use it again as soon as
+    /// it is reachable
+    fn probe_primary(&self) {
+        let backend = self.clone();
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(FAILOVER_PROBE).await;
+                let Some(primary) = backend.primary_server.read().await.clone() else {
+                    return;
+                };
+                let mut settings = backend.settings.read().await.clone();
+                settings.server = primary.clone();
+                settings.max_retries = 0;
+                if api::languages::list(&settings).await.is_err() {
+                    continue;
+                }
+                // Still failed over, the settings could have been reloaded
+                let mut settings = backend.settings.write().await;
+                let mut current = backend.primary_server.write().await;
+                if current.as_ref() != Some(&primary) {
+                    return;
+                }
+                current.take();
+                settings.server = primary.clone();
+                drop(current);
+                drop(settings);
+                info!("Server {primary} reachable again");
+                backend
+                    .client
+                    .show_message(
+                        MessageType::INFO,
+                        backend.tr(Msg::ServerRestored(primary.as_str())),
+                    )
+                    .await;
+                return;
+            }
+        });
+    }
+
+    async fn command_check(&self, uri: &Uri, range: lsp_types::Range) -> Result<()> {
+        if let Some(doc) = self.documents.write().await.get_mut(uri) {
+            doc.changed_lines.add_change(
+                range.start.line as usize..range.end.line as usize + 1,
+                range.end.line as usize - range.start.line as usize + 1,
+            );
+        }
+        self.update_matches(uri).await
+    }
+
+    /// Check the matching files of the workspace, closed files are loaded from
+    /// the disk and kept for their diagnostics
+    async fn command_check_workspace(&self, params: WorkspaceCheckParams) -> Result<()> {
+        let Some(root) = self.root.read().await.clone() else {
+            return Err(anyhow!(self.tr(Msg::CheckRequiresWorkspace)));
+        };
+        let settings = self.settings.read().await.clone();
+        let include = if params.include.is_empty() {
+            &settings.workspace_files
+        } else {
+            &params.include
+        };
+        let globs = include
+            .iter()
+            .map(|g| util::glob(g))
+            .collect::<Result<Vec<_>, _>>()?;
+        let files = project::files(&root, |path| globs.iter().any(|g| g.is_match(path)));
+        info!("Check {} workspace files", files.len());
+
+        // Stay below the request limit of the server
+        let delay = Duration::from_secs_f64(settings.endpoint().min_delay() / 1000.0);
+        let mut first = true;
+        for path in files {
+            let Some(language_id) = path
+                .extension()
+                .and_then(|e| e.to_str())
+                .and_then(annotated::language_id)
+            else {
+                continue;
+            };
+            let Some(uri) = Uri::from_file_path(&path) else {
+                continue;
+            };
+            if !self.is_checked(&uri, language_id).await {
+                continue;
+            }
+
+            let pending = {
+                let mut open_docs = self.documents.write().await;
+                if !open_docs.contains_key(&uri) {
+                    let text = match std::fs::read_to_string(&path) {
+                        Ok(text) => text,
+                        Err(err) => {
+                            warn!("Failed to read {path:?}: {err}");
+                            continue;
+                        }
+                    };
+                    let source = SourceFile::new(text).with_encoding(self.encoding());
+                    let doc = Document::new(source, None, language_id.into());
+                    open_docs.insert(uri.clone(), doc);
+                }
+                !open_docs[&uri].changed_lines.changes().is_empty()
+            };
+            if !pending {
+                continue;
+            }
+            if !first {
+                tokio::time::sleep(delay).await;
+            }
+            first = false;
+
+            info!("Check {}", uri.as_str());
+            if !self.check_document(&uri).await {
+                // The error was already reported
+                return Ok(());
+            }
+            if let Some(doc) = self.documents.write().await.get_mut(&uri) {
+                self.show_diagnostics(&uri, doc).await;
+            }
+        }
+        Ok(())
+    }
+
+    /// Settings and language of the document for the synonyms and rephrasing
+    async fn language_context(&self, uri: &Uri) -> Result<(SourceFile, String, Settings, String)> {
+        let (source, language_id, language, detected) = {
+            let open_docs = self.documents.read().await;
+            let doc = open_docs
+                .get(uri)
+                .ok_or_else(|| anyhow!("No document found: {}", uri.as_str()))?;
+            (
+                doc.source.clone(),
+                doc.language_id.clone(),
+                doc.language.clone(),
+                doc.detected_language.clone(),
+            )
+        };
+        let settings = self
+            .document_settings(uri, &source, &language_id, language)
+            .await;
+        // English until the language is known from the first check
+        let language = settings
+            .static_language
+            .clone()
+            .or(detected)
+            .unwrap_or_else(|| "en".to_string());
+        Ok((source, language_id, settings, language))
+    }
+
+    /// Queries the synonyms of the byte range with the service for the
+    /// language of the document and its sentence as context
+    async fn query_synonyms(&self, uri: &Uri, selection: Range<usize>) -> Result<Vec<String>> {
+        let (source, language_id, settings, language) = self.language_context(uri).await?;
+        info!("Synonyms in {language}");
+        let provider = settings
+            .synonym_provider(&language)
+            .ok_or_else(|| anyhow!(self.tr(Msg::NoSynonyms(&language))))?;
+
+        let (range, text) = plain_paragraph(&language_id, &source, &settings, &selection)?;
+        let (sentence, selection) = synonyms::context(
+            &text,
+            selection.start - range.start..selection.end - range.start,
+        );
+
+        let mut synonyms = provider
+            .query(&sentence, selection)
+            .await
+            .map_err(|e| anyhow!("Synonyms: {e}"))?;
+        if settings.max_replacements > 0 {
+            synonyms.truncate(settings.max_replacements);
+        }
+        Ok(synonyms)
+    }
+
+    /// Queries the synonyms of the selected word and lets the user pick one
+    /// if requested
+    async fn command_synonyms(&self, params: &PickParams) -> Result<SynonymsResult> {
+        let uri = &params.text_document.uri;
+        let (start, end, word, edits, range) = {
+            let open_docs = self.documents.read().await;
+            let doc = open_docs
+                .get(uri)
+                .ok_or_else(|| anyhow!("No document found: {}", uri.as_str()))?;
+            let range = params.range;
+            let (Some(start), Some(end)) = (
+                doc.source.to_offset(range.start),
+                doc.source.to_offset(range.end),
+            ) else {
+                return Err(anyhow!("Invalid range: {:?}", range));
+            };
+            // Only replace the word without the surrounding whitespace
+            let selection = &doc.source.text()[start..end];
+            let end = start + selection.trim_end().len();
+            let start = end - selection.trim().len();
+            info!("Synonyms for {:?}", start..end);
+
+            let range = DocRange {
+                start: doc.source.to_position(start).unwrap(),
+                end: doc.source.to_position(end).unwrap(),
+            };
+            let word = doc.source.text()[start..end].to_string();
+            (start, end, word, doc.edits, range)
+        };
+
+        let synonyms = self.query_synonyms(uri, start..end).await?;
+        let result = SynonymsResult {
+            word,
+            range,
+            synonyms,
+        };
+
+        if params.pick && self.message_actions.load(Ordering::Acquire) {
+            if result.synonyms.is_empty() {
+                self.client
+                    .show_message(MessageType::INFO, self.tr(Msg::NoSynonymsFor(&result.word)))
+                    .await;
+            } else {
+                let message = self.tr(Msg::SynonymsFor(&result.word));
+                self.pick_replacement(uri, edits, message, result.range, &result.synonyms)
+                    .await?;
+            }
+        }
+        Ok(result)
+    }
+
+    /// Rephrases the sentence at the start of the range and lets the user
+    /// pick an alternative if requested
+    async fn command_rephrase(&self, params: &PickParams) -> Result<RephraseResult> {
+        let uri = &params.text_document.uri;
+        // Before the snapshot, so that later edits skip the replacement
+        let edits = self.documents.read().await.get(uri).map_or(0, |d| d.edits);
+        let (source, language_id, settings, language) = self.language_context(uri).await?;
+        let (Some(start), Some(end)) = (
+            source.to_offset(params.range.start),
+            source.to_offset(params.range.end),
+        ) else {
+            return Err(anyhow!("Invalid range: {:?}", params.range));
+        };
+
+        let (range, text) = plain_paragraph(&language_id, &source, &settings, &(start..end))?;
+        let selection = start - range.start..end - range.start;
+        let span = util::sentence_span(&text, &[selection]);
+        let span = range.start + span.start..range.start + span.end;
+        if span.is_empty() {
+            return Err(anyhow!("No sentence found: {:?}", params.range));
+        }
+        let sentence = source.text()[span.clone()].to_string();
+        info!("Rephrase {span:?} in {language}");
+
+        let context = text[span.start - range.start..span.end - range.start]
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ");
+        let mut rephrasings = api::rephrase::rephrase(&settings, &context, &language)
+            .await
+            .map_err(|e| anyhow!("Rephrase: {e}"))?;
+        if settings.max_replacements > 0 {
+            rephrasings.truncate(settings.max_replacements);
+        }
+        let result = RephraseResult {
+            range: DocRange {
+                start: source.to_position(span.start).unwrap(),
+                end: source.to_position(span.end).unwrap(),
+            },
+            sentence,
+            rephrasings,
+        };
+
+        if params.pick && self.message_actions.load(Ordering::Acquire) {
+            if result.rephrasings.is_empty() {
+                self.client
+                    .show_message(MessageType::INFO, self.tr(Msg::NoRephrasings))
+                    .await;
+            } else {
+                let message = self.tr(Msg::RephraseSentence);
+                self.pick_replacement(uri, edits, message, result.range, &result.rephrasings)
+                    .await?;
+            }
+        }
+        Ok(result)
+    }
+
+    /// Shows the replacements as message actions and replaces the range with
+    /// the chosen one, unless the document was changed in the meantime
+    async fn pick_replacement(
+        &self,
+        uri: &Uri,
+        edits: u64,
+        message: String,
+        range: DocRange,
+        replacements: &[String],
+    ) -> Result<()> {
+        let actions = replacements
+            .iter()
+            .map(|replacement| MessageActionItem {
+                title: replacement.clone(),
+                properties: Default::default(),
+            })
+            .collect();
+        let choice = self
+            .client
+            .show_message_request(MessageType::INFO, message, Some(actions))
+            .await?;
+        let Some(choice) = choice else {
+            return Ok(());
+        };
+        if self.documents.read().await.get(uri).map(|d| d.edits) != Some(edits) {
+            info!("Document changed, skip the replacement {:?}", choice.title);
+            return Ok(());
+        }
+        let edit = WorkspaceEdit {
+            changes: Some(
+                [(
+                    uri.clone(),
+                    vec![TextEdit {
+                        range,
+                        new_text: choice.title,
+                    }],
+                )]
+                .into(),
+            ),
+            ..Default::default()
+        };
+        let response = self.client.apply_edit(edit).await?;
+        if !response.applied {
+            warn!("Replacement was not applied: {:?}", response.failure_reason);
         }
-
         Ok(())
     }
 
-    async fn command_check(&self, range: lsp_types::Range, doc: &mut Document) -> Result<()> {
-        doc.changed_lines.add_change(
-            range.start.line as usize..range.end.line as usize + 1,
-            range.end.line as usize - range.start.line as usize + 1,
-        );
-        self.update_matches(doc).await
-    }
-
-    async fn command_synonyms(&self, range: lsp_types::Range, doc: &mut Document) -> Result<()> {
+    async fn command_ignore(&self, uri: &Uri, range: lsp_types::Range) -> Result<()> {
+        let mut open_docs = self.documents.write().await;
+        let doc = open_docs
+            .get_mut(uri)
+            .ok_or_else(|| anyhow!("No document found: {}", uri.as_str()))?;
         let (Some(start), Some(end)) = (
             doc.source.to_offset(range.start),
             doc.source.to_offset(range.end),
         ) else {
             return Err(anyhow!("Invalid range: {:?}", range));
         };
-        info!("Synonyms for {:?}", start..end);
+        info!("ignore {start}..{end}");
+        let ignored = doc.matches.remove_touching(start..end);
+        drop(open_docs);
 
-        let Some(((pos, _), line)) = doc
-            .source
-            .line_range(range.start.line as usize..range.end.line as usize + 1)
-        else {
-            return Err(anyhow!("Invalid range: {:?}", start..end));
-        };
+        if self.settings.read().await.learning {
+            let mut learning = self.learning.write().await;
+            let suggest = ignored
+                .iter()
+                .filter(|m| learning.reject(&m.rule))
+                .map(|m| m.rule.clone())
+                .collect::<Vec<_>>();
+            learning.save()?;
+            drop(learning);
 
-        let synonyms = self
-            .settings
-            .read()
-            .await
-            .synonyms
-            .query(line, start - pos.byte..end - pos.byte)
-            .await
-            .map_err(|e| jsonrpc::Error::invalid_params(format!("Synonyms: {e}")))?;
-
-        doc.matches.push(Match {
-            range: start..end,
-            title: "Synonyms".to_string(),
-            message: String::new(),
-            category: "SYNONYMS".to_string(),
-            rule: "SYNONYMS".to_string(),
-            replacements: synonyms,
-        });
+            for rule in suggest {
+                let message = self.tr(Msg::RuleIgnored(&rule));
+                self.client.show_message(MessageType::INFO, message).await;
+            }
+        }
         Ok(())
     }
 
-    async fn command_ignore(&self, range: lsp_types::Range, doc: &mut Document) -> Result<()> {
+    async fn command_ignore_permanently(&self, uri: &Uri, range: lsp_types::Range) -> Result<()> {
+        let Some(root) = self.root.read().await.clone() else {
+            return Err(anyhow!(self.tr(Msg::IgnoreRequiresWorkspace)));
+        };
+        let mut open_docs = self.documents.write().await;
+        let doc = open_docs
+            .get_mut(uri)
+            .ok_or_else(|| anyhow!("No document found: {}", uri.as_str()))?;
         let (Some(start), Some(end)) = (
             doc.source.to_offset(range.start),
             doc.source.to_offset(range.end),
         ) else {
             return Err(anyhow!("Invalid range: {:?}", range));
         };
-        info!("ignore {start}..{end}");
-        doc.matches.retain(|m| !m.range.touches(&(start..end)));
+        info!("ignore permanently {start}..{end}");
+        let ignored = doc.matches.remove_touching(start..end);
+
+        let mut false_positives = self.false_positives.write().await;
+        for m in &ignored {
+            false_positives.insert(m, doc.source.text());
+        }
+        drop(open_docs);
+        false_positives.save(&project::path(&root, false_positives::FILE))
+    }
+
+    /// Set the language of the document and check it again
+    async fn command_set_language(&self, params: SetLanguageParams) -> Result<()> {
+        let uri = &params.text_document.uri;
+        {
+            let mut open_docs = self.documents.write().await;
+            let doc = open_docs
+                .get_mut(uri)
+                .ok_or_else(|| anyhow!("No document found: {}", uri.as_str()))?;
+            info!("set language {:?} of {}", params.language, uri.as_str());
+            doc.language = params.language;
+            let lines = doc.source.lines().len();
+            doc.changed_lines.add_change(0..lines, lines);
+        }
+        if self.check_document(uri).await {
+            let mut open_docs = self.documents.write().await;
+            if let Some(doc) = open_docs.get_mut(uri) {
+                self.show_diagnostics(uri, doc).await;
+            }
+        }
         Ok(())
     }
 
-    async fn command_words_add(&self, range: lsp_types::Range, doc: &mut Document) -> Result<()> {
-        let (Some(start), Some(end)) = (
-            doc.source.to_offset(range.start),
-            doc.source.to_offset(range.end),
-        ) else {
-            return Err(anyhow!("Invalid range: {:?}", range));
+    async fn command_fix_all(&self, uri: &Uri) -> Result<()> {
+        let edits = match self.documents.read().await.get(uri) {
+            Some(doc) => doc.fix_all(),
+            None => return Err(anyhow!("No document found: {}", uri.as_str())),
         };
-        let Some(word) = doc.source.text().get(start..end) else {
-            return Err(anyhow!("Invalid range: {:?}", range));
+        info!("fix all {}", edits.len());
+        if edits.is_empty() {
+            return Ok(());
+        }
+        let edit = WorkspaceEdit {
+            changes: Some([(uri.clone(), edits)].into()),
+            ..Default::default()
+        };
+        let response = self.client.apply_edit(edit).await?;
+        if !response.applied {
+            warn!("Fix all was not applied: {:?}", response.failure_reason);
+        }
+        Ok(())
+    }
+
+    async fn command_rules_browse(&self, params: RulesBrowseParams) -> Result<()> {
+        let Some(root) = self.root.read().await.clone() else {
+            return Err(anyhow!(self.tr(Msg::RulesRequireWorkspace)));
         };
-        info!("add word {word:?}");
         let settings = self.settings.read().await.clone();
+        let language = params
+            .language
+            .or_else(|| settings.static_language.clone())
+            .or_else(|| settings.language_variety.get("en").cloned())
+            .unwrap_or_else(|| "en-US".to_string());
+        info!("Browse rules for {language}");
 
-        if settings.sync_dictionary && (settings.username.is_empty() || settings.api_key.is_empty())
-        {
-            self.client
-                .show_message(
-                    MessageType::WARNING,
-                    "Syncing words is only supported for premium users",
-                )
-                .await;
+        let rules = api::rules::list(&settings, &language).await?;
+        let text = rule_browser::render(&language, &rules, &settings);
+
+        let path = project::path(&root, rule_browser::FILE);
+        std::fs::create_dir_all(root.join(project::DIR))?;
+        std::fs::write(&path, text)?;
+
+        let uri = Uri::from_file_path(&path).ok_or_else(|| anyhow!("Invalid path: {path:?}"))?;
+        self.client
+            .show_document(ShowDocumentParams {
+                uri,
+                external: Some(false),
+                take_focus: Some(true),
+                selection: None,
+            })
+            .await?;
+        Ok(())
+    }
+
+    /// Write the rule selection of the rule browser into the project settings
+    async fn apply_rule_browser(&self, uri: &Uri) -> Result<()> {
+        let Some(root) = self.root.read().await.clone() else {
+            return Ok(());
+        };
+        let Some(path) = uri.to_file_path() else {
+            return Ok(());
+        };
+        let selection = rule_browser::parse(&std::fs::read_to_string(path)?);
+        info!("Apply rules {selection:?}");
+
+        let mut project = project::load_settings(&root)?;
+        selection.apply(&mut project);
+        project::save_settings(&root, &project)?;
+        self.load_settings().await;
+
+        self.client
+            .show_message(MessageType::INFO, self.tr(Msg::RulesUpdated))
+            .await;
+        Ok(())
+    }
+
+    /// Sort and deduplicate the project dictionary file
+    async fn command_dictionary_normalize(&self) -> Result<()> {
+        let Some(root) = self.root.read().await.clone() else {
+            return Err(anyhow!(self.tr(Msg::DictionaryRequiresWorkspace)));
+        };
+        let path = project::path(&root, dictionary::FILE);
+        let mut file = DictionaryFile::load(&path)?;
+        file.normalize();
+        file.save(&path)?;
+        info!("Normalized {path:?}");
+        Ok(())
+    }
+
+    async fn command_accept(&self, params: AcceptParams) {
+        if !self.settings.read().await.learning {
+            return;
+        }
+        info!("accept {} {:?}", params.rule, params.replacement);
+        let mut learning = self.learning.write().await;
+        learning.accept(&params.rule, &params.replacement);
+        if let Err(err) = learning.save() {
+            error!("Failed to save learning data: {err}");
         }
+    }
 
-        if settings.sync_dictionary && !settings.username.is_empty() && !settings.api_key.is_empty()
-        {
-            info!("Add {word:?} to remote dict");
-            api::words::add(&settings, word).await?;
-            self.client
-                .show_message(
-                    MessageType::INFO,
-                    format!("Added {word:?} to remote dictionary"),
-                )
-                .await;
+    async fn command_disable_rule(&self, params: DisableRuleParams) -> Result<()> {
+        let rule = params.rule;
+        info!("disable rule {rule}");
+        self.disable(
+            |project| {
+                let mut rules = project::settings_list(project, "disabled_rules");
+                if !rules.contains(&rule) {
+                    rules.push(rule.clone());
+                }
+                project.insert("disabled_rules".into(), rules.into());
+            },
+            |settings| {
+                if !settings.disabled_rules.contains(&rule) {
+                    settings.disabled_rules.push(rule.clone());
+                }
+            },
+            |m| m.rule == rule,
+        )
+        .await
+    }
+
+    async fn command_disable_category(&self, params: DisableCategoryParams) -> Result<()> {
+        let category = params.category;
+        info!("disable category {category}");
+        self.disable(
+            |project| {
+                let mut categories = project::settings_list(project, "disabled_categories");
+                if !categories.contains(&category) {
+                    categories.push(category.clone());
+                }
+                project.insert("disabled_categories".into(), categories.join(",").into());
+            },
+            |settings| {
+                let list = &mut settings.disabled_categories;
+                if !list.split(',').any(|c| c.trim() == category) {
+                    if !list.is_empty() {
+                        list.push(',');
+                    }
+                    list.push_str(&category);
+                }
+            },
+            |m| m.category == category,
+        )
+        .await
+    }
+
+    /// Update the disabled rules and categories in the project settings, or
+    /// for this session if there is no workspace, and remove the disabled
+    /// matches without checking again
+    async fn disable(
+        &self,
+        update_project: impl FnOnce(&mut serde_json::Map<String, serde_json::Value>),
+        update: impl FnOnce(&mut Settings),
+        disabled: impl Fn(&Match) -> bool,
+    ) -> Result<()> {
+        if let Some(root) = self.root.read().await.clone() {
+            // Only the project's own lists, the client settings stay private
+            let mut project = project::load_settings(&root)?;
+            update_project(&mut project);
+            project::save_settings(&root, &project)?;
+            self.load_settings().await;
+        } else {
+            update(&mut *self.settings.write().await);
+        }
+
+        let mut open_docs = self.documents.write().await;
+        for (uri, doc) in open_docs.iter_mut() {
+            let count = doc.matches.len();
+            doc.matches.retain(|m| !disabled(m));
+            if doc.matches.len() != count {
+                self.show_diagnostics(uri, doc).await;
+            }
+        }
+        Ok(())
+    }
+
+    async fn command_words_add(&self, uri: &Uri, range: lsp_types::Range) -> Result<()> {
+        let word = {
+            let open_docs = self.documents.read().await;
+            let doc = open_docs
+                .get(uri)
+                .ok_or_else(|| anyhow!("No document found: {}", uri.as_str()))?;
+            let (Some(start), Some(end)) = (
+                doc.source.to_offset(range.start),
+                doc.source.to_offset(range.end),
+            ) else {
+                return Err(anyhow!("Invalid range: {:?}", range));
+            };
+            let Some(word) = doc.source.text().get(start..end) else {
+                return Err(anyhow!("Invalid range: {:?}", range));
+            };
+            word.to_string()
+        };
+        info!("add word {word:?}");
+        let message = if self.add_words(std::slice::from_ref(&word), None).await? {
+            Msg::AddedRemote(&word)
         } else {
-            info!("Add {word:?} to local dict");
-            self.dictionary.write().await.insert(word.to_string());
+            Msg::AddedLocal(&word)
+        };
+        self.client
+            .show_message(MessageType::INFO, self.tr(message))
+            .await;
+        Ok(())
+    }
+
+    /// Returns the settings if the dictionary is synchronized with a
+    /// premium account
+    async fn sync_account(&self) -> Option<Settings> {
+        let settings = self.settings.read().await.clone();
+        if !settings.sync_dictionary {
+            return None;
+        }
+        if settings.username.is_empty() || settings.api_key.is_empty() {
             self.client
-                .show_message(
-                    MessageType::INFO,
-                    format!("Added {word:?} to local dictionary"),
-                )
+                .show_message(MessageType::WARNING, self.tr(Msg::PremiumOnly))
                 .await;
+            return None;
         }
+        Some(settings)
+    }
+
+    /// Add the words to the remote dictionary if it is synchronized,
+    /// otherwise to the dictionary file, returns true if added remotely
+    async fn add_words(&self, words: &[String], dict: Option<&str>) -> Result<bool> {
+        let remote = if let Some(settings) = self.sync_account().await {
+            for word in words {
+                info!("Add {word:?} to remote dict");
+                api::words::add(&settings, dict, word).await?;
+            }
+            true
+        } else {
+            let mut dict = self.dictionary.write().await;
+            for word in words {
+                info!("Add {word:?} to local dict");
+                dict.insert(word, None);
+            }
+            drop(dict);
+            // Persisted in the workspace or otherwise the user dictionary
+            let path = match self.root.read().await.as_deref() {
+                Some(root) => Some(project::path(root, dictionary::FILE)),
+                None => user_dictionary(),
+            };
+            if let Some(path) = path {
+                let mut file = DictionaryFile::load(&path)?;
+                let mut changed = false;
+                for word in words {
+                    changed |= file.insert(word);
+                }
+                if changed {
+                    file.save(&path)?;
+                }
+            }
+            false
+        };
 
         // Remove corresponding matches
-        doc.matches
-            .retain(|m| !(m.category == "TYPOS" && word == &doc.source.text()[m.range.clone()]));
+        let mut open_docs = self.documents.write().await;
+        for (uri, doc) in open_docs.iter_mut() {
+            let count = doc.matches.len();
+            doc.matches.retain(|m| {
+                !(m.category == "TYPOS"
+                    && doc
+                        .source
+                        .text()
+                        .get(m.range.clone())
+                        .is_some_and(|w| words.iter().any(|word| word == w)))
+            });
+            if doc.matches.len() != count {
+                self.show_diagnostics(uri, doc).await;
+            }
+        }
+        Ok(remote)
+    }
+
+    /// Remove the words from the remote dictionary and the dictionary files
+    async fn command_words_remove(&self, params: WordsParams) -> Result<()> {
+        if let Some(settings) = self.sync_account().await {
+            for word in &params.words {
+                info!("Delete {word:?} from remote dict");
+                api::words::delete(&settings, params.dict.as_deref(), word).await?;
+            }
+        }
+        let root = self.root.read().await.clone();
+        let files = user_dictionary()
+            .into_iter()
+            .chain(root.map(|root| project::path(&root, dictionary::FILE)));
+        for path in files {
+            let mut file = DictionaryFile::load(&path)?;
+            let mut changed = false;
+            for word in &params.words {
+                changed |= file.remove(word);
+            }
+            if changed {
+                info!("Remove {:?} from {path:?}", params.words);
+                file.save(&path)?;
+            }
+        }
+        self.load_dictionary().await;
+
+        // The removed words might be reported again
+        let backend = self.clone();
+        tokio::spawn(async move { backend.check_all().await });
+        Ok(())
+    }
+
+    /// Synchronize the user dictionary with the default remote dictionary of
+    /// the premium account in both directions, named dictionaries are not
+    /// synchronized
+    async fn command_words_sync(&self, notify: bool) -> Result<()> {
+        let Some(settings) = self.sync_account().await else {
+            return Ok(());
+        };
+        let (Some(path), Some(snapshot_path)) = (user_dictionary(), remote_snapshot()) else {
+            return Ok(());
+        };
+        let mut file = DictionaryFile::load(&path)?;
+        // The remote dictionary only holds plain words for all languages
+        let local = file
+            .entries
+            .iter()
+            .filter(|e| e.language.is_none() && e.is_plain())
+            .map(|e| e.word.clone())
+            .collect();
+        let snapshot = if snapshot_path.exists() {
+            let file = DictionaryFile::load(&snapshot_path)?;
+            file.entries.into_iter().map(|e| e.word).collect()
+        } else {
+            settings.remote_dictionary.iter().cloned().collect()
+        };
+        let remote = api::words::get(&settings, None)
+            .await?
+            .into_iter()
+            .collect();
+
+        let plan = SyncPlan::new(&local, &snapshot, &remote);
+        info!("Sync dictionary {plan:?}");
+        for word in &plan.push {
+            api::words::add(&settings, None, word).await?;
+        }
+        for word in &plan.delete {
+            api::words::delete(&settings, None, word).await?;
+        }
+        for word in &plan.pull {
+            file.insert(word);
+        }
+        for word in &plan.remove {
+            file.remove(word);
+        }
+        if !plan.pull.is_empty() || !plan.remove.is_empty() {
+            file.save(&path)?;
+        }
+        let mut snapshot = DictionaryFile::default();
+        for word in &plan.words {
+            snapshot.insert(word);
+        }
+        snapshot.save(&snapshot_path)?;
+        self.load_dictionary().await;
+
+        if notify {
+            let message = Msg::WordsSynced(
+                plan.push.len() + plan.delete.len(),
+                plan.pull.len() + plan.remove.len(),
+            );
+            self.client
+                .show_message(MessageType::INFO, self.tr(message))
+                .await;
+        }
         Ok(())
     }
+
+    /// Check the health of the server and report it with the state of the
+    /// dictionaries
+    async fn command_status(&self) -> ServerStatus {
+        let settings = self.settings.read().await.clone();
+        let endpoint = settings.endpoint();
+        let (software, error) = match api::software(&settings).await {
+            Ok(software) => (Some(software), None),
+            Err(err) => {
+                warn!("Failed to fetch the status: {err}");
+                (None, Some(err.to_string()))
+            }
+        };
+        let status = ServerStatus {
+            server: settings.server.to_string(),
+            software,
+            error,
+            offline: self.offline.load(Ordering::Acquire),
+            requests_per_minute: endpoint.requests_per_minute(),
+            max_request_size: endpoint.max_size(),
+            dictionary_words: self.dictionary.read().await.len(),
+            known_words: self
+                .known_words
+                .read()
+                .await
+                .as_ref()
+                .map_or(0, |k| k.len()),
+            false_positives: self.false_positives.read().await.entries.len(),
+        };
+
+        let server = match &status.software {
+            Some(s) => self.tr(Msg::ServerVersion(&s.name, &s.version, s.premium)),
+            None => self.tr(Msg::ServerUnreachable(&status.server)),
+        };
+        let dictionary = self.tr(Msg::DictionaryStatus(
+            status.dictionary_words,
+            status.known_words,
+            status.false_positives,
+        ));
+        let kind = match status.software {
+            Some(_) => MessageType::INFO,
+            None => MessageType::WARNING,
+        };
+        self.client
+            .show_message(kind, format!("{server}\n{dictionary}"))
+            .await;
+        status
+    }
+
+    /// List the words of all dictionaries with their source
+    async fn command_words_list(&self) -> Result<Vec<DictionaryWord>> {
+        let settings = self.settings.read().await.clone();
+        let word = |source, word: &str, language: Option<&str>| DictionaryWord {
+            word: word.to_string(),
+            language: language.map(str::to_string),
+            source,
+        };
+        let mut words = settings
+            .dictionary
+            .iter()
+            .map(|w| word("settings", w, None))
+            .collect::<Vec<_>>();
+        let root = self.root.read().await.clone();
+        let files = [
+            ("user", user_dictionary()),
+            (
+                "workspace",
+                root.map(|root| project::path(&root, dictionary::FILE)),
+            ),
+        ];
+        for (source, path) in files {
+            if let Some(path) = path {
+                let file = DictionaryFile::load(&path)?;
+                words.extend(
+                    file.entries
+                        .iter()
+                        .map(|e| word(source, &e.word, e.language.as_deref())),
+                );
+            }
+        }
+        words.extend(
+            settings
+                .remote_dictionary
+                .iter()
+                .map(|w| word("remote", w, None)),
+        );
+        Ok(words)
+    }
 }
 
 struct Document {
     source: SourceFile,
     version: Option<i32>,
-    matches: Vec<Match>,
+    /// Language identifier of the client, e.g. "markdown"
+    language_id: String,
+    /// Language selected by the user, overriding the settings
+    language: Option<String>,
+    /// Language of the last check, e.g. for the synonyms
+    detected_language: Option<String>,
+    matches: Matches,
+    /// Rules reported by the text level checks of the whole document
+    text_rules: HashSet<String>,
     changed_lines: Changes,
+    /// Byte ranges of the edits since the last check, only their sentences
+    /// are checked
+    changed_bytes: Changes,
+    /// A check failed because the server was unreachable
+    queued: bool,
+    /// The user was informed that the document is too large
+    too_large: bool,
+    /// Number of changes, used to debounce the automatic checks and to
+    /// detect outdated check results
+    edits: u64,
+    /// Lines that are currently checked
+    checking: Changes,
+    /// Edits during the running checks: edit count, replaced byte range and shift
+    edit_log: Vec<(u64, Range<usize>, isize)>,
+    /// Notifies the running checks about edits
+    edited: Arc<Notify>,
 }
 impl Document {
-    fn new(source: SourceFile, version: Option<i32>) -> Self {
+    fn new(source: SourceFile, version: Option<i32>, language_id: String) -> Self {
         let mut changed_lines = Changes::new();
         // Initially everyting is changed
         changed_lines.add_change(0..source.lines().len(), source.lines().len());
         Self {
             source,
             version,
-            matches: Vec::new(),
+            language_id,
+            language: None,
+            detected_language: None,
+            matches: Matches::new(),
+            text_rules: HashSet::new(),
             changed_lines,
+            changed_bytes: Changes::new(),
+            queued: false,
+            too_large: false,
+            edits: 0,
+            checking: Changes::new(),
+            edit_log: Vec::new(),
+            edited: Default::default(),
+        }
+    }
+    /// Returns how far the checked range was moved by the edits after the
+    /// given edit count, or None if it was changed
+    fn shift_since(&self, edits: u64, range: &Range<usize>) -> Option<isize> {
+        let mut range = range.clone();
+        let mut total = 0;
+        for (_, edit, shift) in self.edit_log.iter().filter(|(e, ..)| *e > edits) {
+            if edit.start <= range.end && range.start <= edit.end {
+                return None;
+            }
+            if edit.end <= range.start {
+                range = shift_range(&range, *shift);
+                total += shift;
+            }
+        }
+        Some(total)
+    }
+    /// Check the lines of an unfinished check again
+    fn requeue(&mut self) {
+        for lines in std::mem::take(&mut self.checking).changes() {
+            self.changed_lines.add_change(lines.clone(), lines.len());
         }
     }
-    fn diagnostics(&self) -> Vec<Diagnostic> {
+    /// Returns the corrections of the unambiguous typos of the given rules
+    /// together with the original text.
+    fn corrections(&self, rules: &[String]) -> Vec<(TextEdit, String)> {
         self.matches
             .iter()
+            .filter(|m| {
+                m.category == "TYPOS" && m.replacements.len() == 1 && rules.contains(&m.rule)
+            })
+            .filter_map(|m| {
+                let range = DocRange {
+                    start: self.source.to_position(m.range.start)?,
+                    end: self.source.to_position(m.range.end)?,
+                };
+                let original = self.source.text().get(m.range.clone())?.to_string();
+                let new_text = m.replacements.first()?.clone();
+                Some((TextEdit { range, new_text }, original))
+            })
+            .collect()
+    }
+
+    fn diagnostics(&self, uri: &Uri, settings: &Settings, lang: Lang) -> Vec<Diagnostic> {
+        let matches = self.matches.to_vec();
+        Self::snapshot_diagnostics(uri, &self.source, &matches, settings, lang)
+    }
+
+    /// Diagnostics of the matches in a snapshot of the source
+    fn snapshot_diagnostics(
+        uri: &Uri,
+        source: &SourceFile,
+        matches: &[Match],
+        settings: &Settings,
+        lang: Lang,
+    ) -> Vec<Diagnostic> {
+        let max = match settings.max_diagnostics_per_file {
+            0 => usize::MAX,
+            max => max,
+        };
+        let mut diagnostics = matches
+            .iter()
+            .take(max)
             .map(|m| Diagnostic {
                 range: DocRange {
-                    start: self.source.to_position(m.range.start).unwrap(),
-                    end: self.source.to_position(m.range.end).unwrap(),
+                    start: source.to_position(m.range.start).unwrap(),
+                    end: source.to_position(m.range.end).unwrap(),
                 },
-                data: Some(m.replacements.clone().into()),
-                message: format!(
-                    "{}\n\n{}\n{} > {}\n",
-                    m.title, m.message, m.category, m.rule
+                data: Some(
+                    serde_json::to_value(DiagnosticData {
+                        rule: m.rule.clone(),
+                        category: m.category.clone(),
+                        replacements: m.replacements.clone(),
+                    })
+                    .unwrap(),
                 ),
-                severity: Some(match m.category.as_str() {
-                    "COLLOQUIALISMS" | "REDUNDANCY" | "STYLE" | "SYNONYMS" => {
-                        DiagnosticSeverity::HINT
-                    }
-                    "TYPOS" => DiagnosticSeverity::WARNING,
-                    _ => DiagnosticSeverity::INFORMATION,
+                // The rule is shown as code with a link to its description
+                message: if m.title.is_empty() {
+                    m.message.clone()
+                } else {
+                    format!("{}\n\n{}", m.title, m.message)
+                },
+                code: Some(NumberOrString::String(m.rule.clone())),
+                code_description: m
+                    .details
+                    .page
+                    .iter()
+                    .chain(&m.details.urls)
+                    .find_map(|url| url.parse().ok())
+                    .map(|href| CodeDescription { href }),
+                tags: Some(
+                    settings
+                        .tags(m)
+                        .into_iter()
+                        .map(|tag| match tag {
+                            Tag::Unnecessary => DiagnosticTag::UNNECESSARY,
+                            Tag::Deprecated => DiagnosticTag::DEPRECATED,
+                        })
+                        .collect(),
+                )
+                .filter(|tags: &Vec<_>| !tags.is_empty()),
+                // Jump between repeated words or inconsistent spellings
+                related_information: Some(
+                    m.occurrences(matches, source)
+                        .map(|o| DiagnosticRelatedInformation {
+                            location: Location {
+                                uri: uri.clone(),
+                                range: DocRange {
+                                    start: source.to_position(o.range.start).unwrap(),
+                                    end: source.to_position(o.range.end).unwrap(),
+                                },
+                            },
+                            message: format!(
+                                "\"{}\"",
+                                source.text().get(o.range.clone()).unwrap_or_default()
+                            ),
+                        })
+                        .collect(),
+                )
+                .filter(|related: &Vec<_>| !related.is_empty()),
+                severity: Some(match m.level() {
+                    Severity::Error => DiagnosticSeverity::ERROR,
+                    Severity::Warning => DiagnosticSeverity::WARNING,
+                    Severity::Information => DiagnosticSeverity::INFORMATION,
+                    Severity::Hint => DiagnosticSeverity::HINT,
                 }),
                 source: Some("languagetool-lsp".into()),
-                ..Default::default()
             })
-            .collect()
+            .collect::<Vec<_>>();
+        // Tell the user that there are more issues than shown
+        if matches.len() > max
+            && let Some(last) = diagnostics.last_mut()
+        {
+            let hidden = matches.len() - max;
+            last.message.push_str("\n\n");
+            last.message.push_str(&Msg::MoreIssues(hidden).text(lang));
+        }
+        diagnostics
+    }
+
+    /// Edits applying the matches with a single replacement, overlapping
+    /// matches are skipped
+    fn fix_all(&self) -> Vec<TextEdit> {
+        let mut matches = self
+            .matches
+            .iter()
+            .filter(|m| m.replacements.len() == 1)
+            .collect::<Vec<_>>();
+        matches.sort_by_key(|m| m.range.start);
+        let mut end = 0;
+        let mut edits = Vec::new();
+        for m in matches {
+            if m.range.start < end {
+                continue;
+            }
+            end = m.range.end;
+            edits.push(TextEdit {
+                range: DocRange {
+                    start: self.source.to_position(m.range.start).unwrap(),
+                    end: self.source.to_position(m.range.end).unwrap(),
+                },
+                new_text: m.replacements[0].clone(),
+            });
+        }
+        edits
+    }
+}
+
+/// Dictionary of the user, used for documents outside of a workspace
+fn user_dictionary() -> Option<PathBuf> {
+    Some(
+        dirs::data_dir()?
+            .join("languagetool-lsp")
+            .join(dictionary::FILE),
+    )
+}
+
+/// Words of the remote dictionary at the last synchronization
+fn remote_snapshot() -> Option<PathBuf> {
+    Some(
+        dirs::data_dir()?
+            .join("languagetool-lsp")
+            .join("remote-dictionary.txt"),
+    )
+}
+
+/// Identifies the diagnostics of a document, so that unchanged diagnostics
+/// don't have to be sent again
+fn result_id(diags: &[Diagnostic]) -> String {
+    let mut hasher = DefaultHasher::new();
+    serde_json::to_string(diags).unwrap().hash(&mut hasher);
+    format!("{:x}", hasher.finish())
+}
+
+fn shift_range(range: &Range<usize>, shift: isize) -> Range<usize> {
+    (range.start as isize + shift) as usize..(range.end as isize + shift) as usize
+}
+
+fn shift_matches(matches: &mut [Match], shift: isize) {
+    for m in matches {
+        m.range = shift_range(&m.range, shift);
     }
 }
 
+#[derive(Parser)]
+#[command(version, about)]
+struct Args {
+    /// Communicate over stdin and stdout (the default)
+    #[arg(long, hide = true)]
+    stdio: bool,
+    /// Level of the log messages (off, error, warn, info, debug, trace)
+    #[arg(long, global = true)]
+    log_level: Option<LevelFilter>,
+    /// Append the log messages to the file instead of stderr
+    #[arg(long, global = true)]
+    log_file: Option<PathBuf>,
+    /// Check with a built-in stub instead of a server, for tests and demos
+    #[arg(long, global = true)]
+    offline_stub: bool,
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Check the files and print the issues, exits with 1 if there are any
+    Check {
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+        /// Output format of the issues
+        #[arg(long, value_enum, default_value_t)]
+        format: cli::Format,
+    },
+    /// Print the JSON schema of the settings
+    Schema,
+}
+
 #[tokio::main]
 async fn main() {
-    tracing_subscriber::fmt()
-        .with_max_level(tracing::Level::INFO)
-        .with_writer(std::io::stderr)
-        .with_ansi(false)
-        .without_time()
-        .init();
-
-    let (service, socket) = LspService::new(|client| Backend {
-        client,
-        settings: Default::default(),
-        documents: Default::default(),
-        dictionary: Default::default(),
+    let args = Args::parse();
+    let level = args.log_level.unwrap_or(match args.command {
+        Some(_) => LevelFilter::WARN,
+        None => LevelFilter::INFO,
     });
+    let logger = match Logger::init(level, args.log_file.as_deref()) {
+        Ok(logger) => logger,
+        Err(err) => {
+            eprintln!("Error: Failed to open the log file: {err}");
+            std::process::exit(2);
+        }
+    };
+
+    match args.command {
+        Some(Command::Check { files, format }) => {
+            let code = match cli::check(&files, format, args.offline_stub).await {
+                Ok(0) => 0,
+                Ok(_) => 1,
+                Err(err) => {
+                    eprintln!("Error: {err:#}");
+                    2
+                }
+            };
+            std::process::exit(code);
+        }
+        Some(Command::Schema) => {
+            let schema = Settings::schema();
+            println!("{}", serde_json::to_string_pretty(&schema).unwrap());
+            return;
+        }
+        None => {}
+    }
+
+    let docker = Arc::new(Docker::default());
+    let (service, socket) =
+        LspService::new(|client| Backend::new(client, args.offline_stub, logger, docker.clone()));
 
     Server::new(tokio::io::stdin(), tokio::io::stdout(), socket)
         .serve(service)
         .await;
+    docker.stop().await;
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use languagetool_lsp_core::local::terminology::Term;
+    use tower_lsp_server::lsp_types::{
+        TextDocumentContentChangeEvent, VersionedTextDocumentIdentifier,
+    };
+
+    fn backend() -> Backend {
+        let (service, _) = LspService::new(|client| {
+            Backend::new(client, true, Logger::detached(), Default::default())
+        });
+        service.inner().clone()
+    }
+
+    #[tokio::test]
+    async fn check_changes_edited_before() {
+        let backend = backend();
+        {
+            let mut settings = backend.settings.write().await;
+            settings.offline_stub = true;
+            settings.auto_check = false;
+            settings.terminology = vec![Term {
+                preferred: Some("GitHub".into()),
+                ..Default::default()
+            }];
+        }
+        let uri = Uri::from_file_path("/tmp/edited.md").unwrap();
+        let mut doc = Document::new(
+            SourceFile::new("Title\n\nUse github and teh tool.\n".into()),
+            Some(1),
+            "markdown".into(),
+        );
+        doc.changed_lines = Changes::new();
+        doc.changed_lines.add_change(2..3, 1);
+        backend.documents.write().await.insert(uri.clone(), doc);
+
+        // The check waits for the workspace root after taking the snapshot
+        let root = backend.root.write().await;
+        let edit = async {
+            while backend.documents.read().await[&uri]
+                .checking
+                .changes()
+                .is_empty()
+            {
+                tokio::task::yield_now().await;
+            }
+            backend
+                .did_change(DidChangeTextDocumentParams {
+                    text_document: VersionedTextDocumentIdentifier {
+                        uri: uri.clone(),
+                        version: 2,
+                    },
+                    content_changes: vec![TextDocumentContentChangeEvent {
+                        range: Some(DocRange {
+                            start: Position::new(0, 0),
+                            end: Position::new(0, 0),
+                        }),
+                        range_length: None,
+                        text: "New ".into(),
+                    }],
+                })
+                .await;
+            drop(root);
+        };
+        let mut progress = None;
+        let (result, ()) = tokio::join!(backend.check_changes(&uri, &mut progress), edit);
+        result.unwrap();
+
+        let open_docs = backend.documents.read().await;
+        let doc = &open_docs[&uri];
+        let text = doc.source.text();
+        let mut texts = doc
+            .matches
+            .iter()
+            .map(|m| &text[m.range.clone()])
+            .collect::<Vec<_>>();
+        texts.sort();
+        assert_eq!(texts, ["github", "teh"]);
+    }
 }
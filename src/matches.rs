@@ -0,0 +1,303 @@
+use std::borrow::Cow;
+use std::ops::Range;
+
+use languagetool_lsp_core::api::Match;
+
+use crate::shift_range;
+
+/// Maximum number of matches per block, larger blocks are split
+const BLOCK_SIZE: usize = 64;
+
+/// Matches of a document in blocks of consecutive matches, sorted by their
+/// start.
+///
+/// Edits only move the offset of the blocks behind them, the offset is
+/// applied to the matches when their block is modified. Lookups,
+/// invalidation and merging only visit the matches of the blocks that
+/// overlap with the range, which matters for documents with thousands of
+/// matches.
+#[derive(Default)]
+pub struct Matches {
+    blocks: Vec<Block>,
+}
+
+#[derive(Default)]
+struct Block {
+    /// Shift of the matches that is not yet applied to their ranges
+    offset: isize,
+    /// Start of the first match, with the offset
+    start: usize,
+    /// Maximum end of the matches, with the offset
+    end: usize,
+    matches: Vec<Match>,
+}
+
+impl Block {
+    fn touches(&self, range: &Range<usize>) -> bool {
+        self.start <= range.end && self.end >= range.start
+    }
+
+    /// The match with the offset applied
+    fn get<'a>(&self, m: &'a Match) -> Cow<'a, Match> {
+        if self.offset == 0 {
+            Cow::Borrowed(m)
+        } else {
+            let mut m = m.clone();
+            m.range = shift_range(&m.range, self.offset);
+            Cow::Owned(m)
+        }
+    }
+
+    /// Applies the offset to the matches before modifying them
+    fn apply(&mut self) {
+        if self.offset != 0 {
+            for m in &mut self.matches {
+                m.range = shift_range(&m.range, self.offset);
+            }
+            self.offset = 0;
+        }
+    }
+
+    /// Updates the bounds after modifying the matches
+    fn update(&mut self) {
+        self.start = self.matches.first().map_or(0, |m| m.range.start);
+        self.end = self.matches.iter().map(|m| m.range.end).max().unwrap_or(0);
+    }
+}
+
+impl Matches {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.blocks.iter().map(|b| b.matches.len()).sum()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Cow<'_, Match>> {
+        self.blocks
+            .iter()
+            .flat_map(|b| b.matches.iter().map(|m| b.get(m)))
+    }
+
+    pub fn to_vec(&self) -> Vec<Match> {
+        self.iter().map(Cow::into_owned).collect()
+    }
+
+    /// Matches that touch the byte range
+    pub fn touching(&self, range: Range<usize>) -> impl Iterator<Item = Cow<'_, Match>> {
+        self.blocks
+            .iter()
+            .take_while(move |b| b.start <= range.end)
+            .filter(move |b| b.end >= range.start)
+            .flat_map(move |b| {
+                let (start, end) = (range.start as isize, range.end as isize);
+                b.matches
+                    .iter()
+                    .take_while(move |m| m.range.start as isize + b.offset <= end)
+                    .filter(move |m| m.range.end as isize + b.offset >= start)
+                    .map(|m| b.get(m))
+            })
+    }
+
+    pub fn push(&mut self, m: Match) {
+        let i = self
+            .blocks
+            .partition_point(|b| b.start <= m.range.start)
+            .saturating_sub(1);
+        if self.blocks.is_empty() {
+            self.blocks.push(Block::default());
+        }
+        let block = &mut self.blocks[i];
+        block.apply();
+        let j = block
+            .matches
+            .partition_point(|n| n.range.start <= m.range.start);
+        block.matches.insert(j, m);
+        block.update();
+        if block.matches.len() > BLOCK_SIZE {
+            let mut next = Block {
+                matches: block.matches.split_off(BLOCK_SIZE / 2),
+                ..Default::default()
+            };
+            block.update();
+            next.update();
+            self.blocks.insert(i + 1, next);
+        }
+    }
+
+    pub fn extend(&mut self, matches: impl IntoIterator<Item = Match>) {
+        for m in matches {
+            self.push(m);
+        }
+    }
+
+    /// Removes and returns the matches that touch the byte range
+    pub fn remove_touching(&mut self, range: Range<usize>) -> Vec<Match> {
+        let mut removed = Vec::new();
+        for block in &mut self.blocks {
+            if block.start > range.end {
+                break;
+            }
+            if block.touches(&range) {
+                block.apply();
+                let (touching, kept) = std::mem::take(&mut block.matches)
+                    .into_iter()
+                    .partition::<Vec<_>, _>(|m| {
+                        m.range.start <= range.end && m.range.end >= range.start
+                    });
+                removed.extend(touching);
+                block.matches = kept;
+                block.update();
+            }
+        }
+        self.compact();
+        removed
+    }
+
+    /// Replaces the matches that touch the byte range
+    pub fn replace(&mut self, range: Range<usize>, matches: Vec<Match>) {
+        self.remove_touching(range);
+        self.extend(matches);
+    }
+
+    pub fn retain(&mut self, mut f: impl FnMut(&Match) -> bool) {
+        for block in &mut self.blocks {
+            block.apply();
+            block.matches.retain(&mut f);
+            block.update();
+        }
+        self.compact();
+    }
+
+    pub fn clear(&mut self) {
+        self.blocks.clear();
+    }
+
+    /// Moves the matches behind the edit of the byte range by the shift,
+    /// matches starting in the edited range are moved to its new end
+    pub fn shift(&mut self, edit: Range<usize>, shift: isize) {
+        let new_end = (edit.end as isize + shift) as usize;
+        for block in &mut self.blocks {
+            if block.start >= edit.end {
+                // Only the offset of the following blocks changes
+                block.offset += shift;
+                block.start = (block.start as isize + shift) as usize;
+                block.end = (block.end as isize + shift) as usize;
+            } else if block.end >= edit.start {
+                block.apply();
+                for m in &mut block.matches {
+                    if m.range.start >= edit.end {
+                        m.range = shift_range(&m.range, shift);
+                        continue;
+                    }
+                    if m.range.start > edit.start {
+                        m.range.start = m.range.start.min(new_end);
+                    }
+                    if m.range.end >= edit.end {
+                        m.range.end = (m.range.end as isize + shift) as usize;
+                    } else if m.range.end > edit.start {
+                        m.range.end = m.range.end.min(new_end);
+                    }
+                    m.range.end = m.range.end.max(m.range.start);
+                }
+                block.update();
+            }
+        }
+    }
+
+    /// Removes empty blocks and merges small neighbours
+    fn compact(&mut self) {
+        let mut blocks: Vec<Block> = Vec::with_capacity(self.blocks.len());
+        for mut block in self.blocks.drain(..) {
+            if block.matches.is_empty() {
+                continue;
+            }
+            match blocks.last_mut() {
+                Some(last) if last.matches.len() + block.matches.len() <= BLOCK_SIZE / 2 => {
+                    last.apply();
+                    block.apply();
+                    last.matches.append(&mut block.matches);
+                    last.update();
+                }
+                _ => blocks.push(block),
+            }
+        }
+        self.blocks = blocks;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn m(range: Range<usize>) -> Match {
+        Match {
+            range,
+            title: String::new(),
+            message: String::new(),
+            replacements: Vec::new(),
+            category: String::new(),
+            rule: String::new(),
+            severity: None,
+            details: Default::default(),
+        }
+    }
+
+    fn ranges(matches: &Matches) -> Vec<Range<usize>> {
+        matches.iter().map(|m| m.range.clone()).collect()
+    }
+
+    #[test]
+    fn matches() {
+        let mut matches = Matches::new();
+        matches.extend([m(20..25), m(0..4), m(10..30), m(40..42)]);
+        assert_eq!(ranges(&matches), [0..4, 10..30, 20..25, 40..42]);
+
+        let touching = matches.touching(26..28).map(|m| m.range.start);
+        assert_eq!(touching.collect::<Vec<_>>(), [10]);
+
+        matches.replace(18..21, vec![m(19..20)]);
+        assert_eq!(ranges(&matches), [0..4, 19..20, 40..42]);
+
+        // Replace 2..18 with two bytes
+        matches.shift(2..18, -14);
+        assert_eq!(ranges(&matches), [0..4, 5..6, 26..28]);
+
+        let removed = matches.remove_touching(0..1);
+        assert_eq!(removed.len(), 1);
+        assert_eq!(ranges(&matches), [5..6, 26..28]);
+    }
+
+    #[test]
+    fn blocks() {
+        let mut matches = Matches::new();
+        matches.extend((0..500).rev().map(|i| m(i * 10..i * 10 + 3)));
+        matches.push(m(0..4000));
+        assert!(matches.blocks.len() > 1);
+        assert_eq!(matches.len(), 501);
+
+        // Insert 4 bytes at the start, the later blocks are shifted lazily
+        matches.shift(1..1, 4);
+        assert!(matches.blocks.iter().skip(1).all(|b| b.offset == 4));
+        let expected = [0..7, 0..4004]
+            .into_iter()
+            .chain((1..500).map(|i| i * 10 + 4..i * 10 + 7));
+        assert_eq!(ranges(&matches), expected.collect::<Vec<_>>());
+
+        let touching = matches.touching(3005..3005).map(|m| m.range.clone());
+        assert_eq!(touching.collect::<Vec<_>>(), [0..4004, 3004..3007]);
+
+        let removed = matches.remove_touching(2000..3000);
+        assert_eq!(removed.len(), 101);
+        matches.replace(2001..2002, vec![m(2001..2002)]);
+        matches.retain(|m| m.range.start % 20 != 4);
+        let expected = std::iter::once(0..7)
+            .chain((1..200).map(|i| i * 10 + 4..i * 10 + 7))
+            .chain(std::iter::once(2001..2002))
+            .chain((300..500).map(|i| i * 10 + 4..i * 10 + 7))
+            .filter(|r| r.start % 20 != 4);
+        assert_eq!(ranges(&matches), expected.collect::<Vec<_>>());
+        assert!(matches.blocks.iter().all(|b| !b.matches.is_empty()));
+    }
+}
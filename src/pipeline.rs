@@ -0,0 +1,81 @@
+//! Preparation of the checked text and filtering of the matches, shared by
+//! the server and the command line so that both report the same issues.
+
+use std::ops::Range;
+
+use anyhow::Result;
+use languagetool_lsp_core::annotated::{
+    self, AnnotatedText, magic, patterns, quotes, templates, tokens,
+};
+use languagetool_lsp_core::api::Match;
+use languagetool_lsp_core::dictionary::Dictionary;
+use languagetool_lsp_core::false_positives::FalsePositives;
+use languagetool_lsp_core::local::known_words::KnownWords;
+use languagetool_lsp_core::settings::Settings;
+use languagetool_lsp_core::source::SourceFile;
+use languagetool_lsp_core::util;
+
+use crate::learning::Learning;
+
+/// Annotates the lines and skips the ignored parts, returns the byte range
+/// of the annotation in the source
+pub fn annotate(
+    language_id: &str,
+    source: &SourceFile,
+    lines: Range<usize>,
+    settings: &Settings,
+) -> Result<(Range<usize>, AnnotatedText)> {
+    let (mut range, mut annot) = annotated::annotate(language_id, source, lines, settings)?;
+    quotes::skip(&mut annot, &settings.skip_quotes(language_id));
+    templates::skip(&mut annot, &settings.templates);
+    patterns::skip(&mut annot, &settings.ignore_patterns);
+    tokens::skip(&mut annot, &settings.tokens);
+    range.start += annot.optimize();
+    Ok((range, annot))
+}
+
+/// Word lists and ignored matches that filter the results
+pub struct Filter<'a> {
+    pub dictionary: &'a Dictionary,
+    pub known_words: Option<&'a KnownWords>,
+    pub false_positives: &'a FalsePositives,
+    /// Ranks the replacements by previously accepted suggestions
+    pub learning: Option<&'a Learning>,
+}
+
+impl Filter<'_> {
+    /// Removes the matches outside of the scope, of known words, ignored
+    /// permanently or suppressed by comments, and orders the replacements
+    pub fn apply(
+        &self,
+        matches: &mut Vec<Match>,
+        source: &SourceFile,
+        settings: &Settings,
+        language_id: &str,
+        language: &str,
+    ) {
+        let text = source.text();
+        let scope = settings.scope(language_id);
+        let suppressions = magic::suppressions(source);
+        matches.retain(|m| {
+            let word = text.get(m.range.clone()).unwrap_or_default();
+            let known = m.category == "TYPOS"
+                && (self.dictionary.contains(word, language)
+                    || self.known_words.is_some_and(|k| k.contains(word)));
+            scope.includes(m)
+                && !known
+                && !self.false_positives.contains(m, text)
+                && !suppressions.iter().any(|s| s.suppresses(m))
+        });
+
+        for m in matches {
+            // Prefer replacements with the same capitalization
+            if let Some(original) = text.get(m.range.clone()) {
+                util::sort_by_case(original, &mut m.replacements);
+            }
+            if let Some(learning) = self.learning {
+                learning.rank(m);
+            }
+        }
+    }
+}
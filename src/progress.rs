@@ -0,0 +1,72 @@
+//! Work done progress of long running checks, shown by the client as a
+//! spinner or progress bar.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use tower_lsp_server::Client;
+use tower_lsp_server::lsp_types::notification::Progress as ProgressNotification;
+use tower_lsp_server::lsp_types::request::WorkDoneProgressCreate;
+use tower_lsp_server::lsp_types::{
+    NumberOrString, ProgressParams, ProgressParamsValue, ProgressToken, WorkDoneProgress,
+    WorkDoneProgressBegin, WorkDoneProgressCreateParams, WorkDoneProgressEnd,
+    WorkDoneProgressReport,
+};
+use tracing::warn;
+
+pub struct Progress {
+    client: Client,
+    token: ProgressToken,
+}
+
+impl Progress {
+    /// Creates the progress on the client and starts it
+    pub async fn begin(client: &Client, title: String, message: String) -> Option<Self> {
+        static NEXT: AtomicU64 = AtomicU64::new(0);
+        let id = NEXT.fetch_add(1, Ordering::Relaxed);
+        let token = NumberOrString::String(format!("languagetool-lsp/{id}"));
+
+        let params = WorkDoneProgressCreateParams {
+            token: token.clone(),
+        };
+        if let Err(err) = client.send_request::<WorkDoneProgressCreate>(params).await {
+            warn!("Failed to create progress: {err}");
+            return None;
+        }
+        let progress = Self {
+            client: client.clone(),
+            token,
+        };
+        progress
+            .send(WorkDoneProgress::Begin(WorkDoneProgressBegin {
+                title,
+                cancellable: Some(false),
+                message: Some(message),
+                percentage: Some(0),
+            }))
+            .await;
+        Some(progress)
+    }
+
+    pub async fn report(&self, message: String, percentage: u32) {
+        self.send(WorkDoneProgress::Report(WorkDoneProgressReport {
+            cancellable: Some(false),
+            message: Some(message),
+            percentage: Some(percentage.min(100)),
+        }))
+        .await;
+    }
+
+    pub async fn end(self) {
+        self.send(WorkDoneProgress::End(WorkDoneProgressEnd { message: None }))
+            .await;
+    }
+
+    async fn send(&self, value: WorkDoneProgress) {
+        self.client
+            .send_notification::<ProgressNotification>(ProgressParams {
+                token: self.token.clone(),
+                value: ProgressParamsValue::WorkDone(value),
+            })
+            .await;
+    }
+}
@@ -0,0 +1,85 @@
+//! Project configuration stored in the `.languagetool` directory of the workspace.
+
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use serde_json::{Map, Value};
+
+pub const DIR: &str = ".languagetool";
+/// Settings that override the client configuration
+pub const SETTINGS: &str = "settings.json";
+/// Directories of dependencies and build outputs
+const SKIPPED_DIRS: &[&str] = &["node_modules", "target", "build", "dist", "vendor"];
+
+pub fn path(root: &Path, file: &str) -> PathBuf {
+    root.join(DIR).join(file)
+}
+
+/// Returns the files of the workspace whose relative path (with `/` as
+/// separator) is accepted by the filter, hidden directories and dependencies
+/// are skipped
+pub fn files(root: &Path, filter: impl Fn(&str) -> bool) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let mut dirs = vec![root.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            let Ok(file_type) = entry.file_type() else {
+                continue;
+            };
+            if file_type.is_dir() {
+                if !name.starts_with('.') && !SKIPPED_DIRS.contains(&name.as_ref()) {
+                    dirs.push(path);
+                }
+            } else if let Ok(relative) = path.strip_prefix(root) {
+                let relative = relative.to_string_lossy().replace('\\', "/");
+                if filter(&relative) {
+                    files.push(path);
+                }
+            }
+        }
+    }
+    files.sort();
+    files
+}
+
+pub fn load_settings(root: &Path) -> Result<Map<String, Value>> {
+    match std::fs::read_to_string(path(root, SETTINGS)) {
+        Ok(data) => Ok(serde_json::from_str(&data)?),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(Map::new()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Entries of a list in the project settings, either an array or a comma
+/// separated string
+pub fn settings_list(settings: &Map<String, Value>, key: &str) -> Vec<String> {
+    match settings.get(key) {
+        Some(Value::Array(values)) => values
+            .iter()
+            .filter_map(Value::as_str)
+            .map(str::to_string)
+            .collect(),
+        Some(Value::String(list)) => list
+            .split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(str::to_string)
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+pub fn save_settings(root: &Path, settings: &Map<String, Value>) -> Result<()> {
+    std::fs::create_dir_all(root.join(DIR))?;
+    let mut data = serde_json::to_string_pretty(settings)?;
+    data.push('\n');
+    std::fs::write(path(root, SETTINGS), data)?;
+    Ok(())
+}
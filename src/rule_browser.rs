@@ -0,0 +1,206 @@
+//! Markdown document for browsing and toggling the LanguageTool rules.
+//!
+//! Rules and categories are rendered as task lists, the user toggles the
+//! checkboxes and saves the document to apply the selection.
+
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::Write;
+
+use languagetool_lsp_core::api::rules::RuleInfo;
+use languagetool_lsp_core::settings::Settings;
+use serde_json::{Map, Value};
+
+use crate::project;
+
+pub const FILE: &str = "rules.md";
+
+const DEFAULT_OFF: &str = "*(default off)*";
+
+pub fn render(language: &str, rules: &[RuleInfo], settings: &Settings) -> String {
+    let disabled_categories = split_list(&settings.disabled_categories);
+
+    let mut categories = BTreeMap::<(&str, &str), Vec<&RuleInfo>>::new();
+    for rule in rules {
+        categories
+            .entry((&rule.category_name, &rule.category_id))
+            .or_default()
+            .push(rule);
+    }
+
+    let mut out = format!(
+        "# LanguageTool Rules ({language})\n\n\
+        Toggle rules and categories and save this file to apply the changes.\n"
+    );
+    for ((name, id), rules) in categories {
+        let checked = !disabled_categories.contains(id);
+        write!(out, "\n## {} {name} `{id}`\n\n", checkbox(checked)).unwrap();
+        for rule in rules {
+            let checked = if settings.enabled_rules.contains(&rule.rule_id) {
+                true
+            } else if settings.disabled_rules.contains(&rule.rule_id) {
+                false
+            } else {
+                !rule.is_default_off
+            };
+            write!(
+                out,
+                "- {} `{}` {}",
+                checkbox(checked),
+                rule.rule_id,
+                rule.description.replace('\n', " ")
+            )
+            .unwrap();
+            if rule.is_default_off {
+                write!(out, " {DEFAULT_OFF}").unwrap();
+            }
+            out.push('\n');
+        }
+    }
+    out
+}
+
+fn checkbox(checked: bool) -> &'static str {
+    if checked { "[x]" } else { "[ ]" }
+}
+
+fn split_list(list: &str) -> HashSet<&str> {
+    list.split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
+/// Rules and categories selected in the rendered document
+#[derive(Debug, Default)]
+pub struct Selection {
+    /// Rule id, checked, default off
+    rules: Vec<(String, bool, bool)>,
+    /// Category id, checked
+    categories: Vec<(String, bool)>,
+}
+
+pub fn parse(text: &str) -> Selection {
+    let mut selection = Selection::default();
+    for line in text.lines() {
+        if let Some(line) = line.strip_prefix("## ")
+            && let Some((checked, id)) = parse_entry(line)
+        {
+            selection.categories.push((id, checked));
+        } else if let Some(line) = line.strip_prefix("- ")
+            && let Some((checked, id)) = parse_entry(line)
+        {
+            let default_off = line.trim_end().ends_with(DEFAULT_OFF);
+            selection.rules.push((id, checked, default_off));
+        }
+    }
+    selection
+}
+
+/// Parses "[x] ... `ID`" into the checkbox state and the first quoted id
+fn parse_entry(line: &str) -> Option<(bool, String)> {
+    let checked = match line.get(..3)? {
+        "[x]" | "[X]" => true,
+        "[ ]" => false,
+        _ => return None,
+    };
+    let (_, rest) = line.split_once('`')?;
+    let (id, _) = rest.split_once('`')?;
+    Some((checked, id.to_string()))
+}
+
+impl Selection {
+    /// Write the selection into the project settings, keeping their entries
+    /// of rules and categories that are not part of the selection. The
+    /// client settings are not copied into the shared project file.
+    pub fn apply(&self, project: &mut Map<String, Value>) {
+        let ids = self
+            .rules
+            .iter()
+            .map(|(id, ..)| id.as_str())
+            .collect::<HashSet<_>>();
+
+        let mut enabled = project::settings_list(project, "enabled_rules")
+            .into_iter()
+            .filter(|r| !ids.contains(r.as_str()))
+            .collect::<Vec<_>>();
+        let mut disabled = project::settings_list(project, "disabled_rules")
+            .into_iter()
+            .filter(|r| !ids.contains(r.as_str()))
+            .collect::<Vec<_>>();
+        for (id, checked, default_off) in &self.rules {
+            match (checked, default_off) {
+                (true, true) => enabled.push(id.clone()),
+                (false, false) => disabled.push(id.clone()),
+                _ => {}
+            }
+        }
+
+        let categories = self
+            .categories
+            .iter()
+            .map(|(id, _)| id.as_str())
+            .collect::<HashSet<_>>();
+        let mut disabled_categories = project::settings_list(project, "disabled_categories")
+            .into_iter()
+            .filter(|c| !categories.contains(c.as_str()))
+            .collect::<Vec<_>>();
+        disabled_categories.extend(
+            self.categories
+                .iter()
+                .filter(|(_, checked)| !checked)
+                .map(|(id, _)| id.clone()),
+        );
+
+        project.insert("enabled_rules".into(), enabled.into());
+        project.insert("disabled_rules".into(), disabled.into());
+        project.insert(
+            "disabled_categories".into(),
+            disabled_categories.join(",").into(),
+        );
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn rule(id: &str, category: &str, default_off: bool) -> RuleInfo {
+        RuleInfo {
+            rule_id: id.into(),
+            description: format!("Rule {id}"),
+            is_default_off: default_off,
+            category_id: category.into(),
+            category_name: category.to_lowercase(),
+        }
+    }
+
+    #[test]
+    fn toggle() {
+        let rules = [
+            rule("A", "GRAMMAR", false),
+            rule("B", "GRAMMAR", true),
+            rule("C", "STYLE", false),
+        ];
+        let settings = Settings {
+            disabled_rules: vec!["A".into(), "OTHER".into(), "CLIENT".into()],
+            ..Default::default()
+        };
+        let text = render("en-US", &rules, &settings);
+        assert!(text.contains("- [ ] `A` Rule A\n"));
+        assert!(text.contains("- [ ] `B` Rule B *(default off)*\n"));
+        assert!(text.contains("## [x] style `STYLE`\n"));
+
+        let text = text
+            .replace("[ ] `A`", "[x] `A`")
+            .replace("[ ] `B`", "[x] `B`")
+            .replace("[x] style", "[ ] style");
+
+        // Only the project settings are written, not the client settings
+        let mut project = Map::new();
+        project.insert("disabled_rules".into(), serde_json::json!(["A", "OTHER"]));
+        parse(&text).apply(&mut project);
+        assert_eq!(project["enabled_rules"], serde_json::json!(["B"]));
+        assert_eq!(project["disabled_rules"], serde_json::json!(["OTHER"]));
+        assert_eq!(project["disabled_categories"], "STYLE");
+    }
+}
@@ -1,9 +1,124 @@
 use std::fs;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 use zed::settings::LspSettings;
 use zed_extension_api::{self as zed, serde_json};
 
 const NAME: &str = "languagetool-lsp";
+/// Metadata of the installed release in the working directory
+const RELEASE_FILE: &str = "release.json";
+/// Minimum time between the checks for updates in seconds
+const UPDATE_INTERVAL: u64 = 24 * 60 * 60;
+
+/// Settings of the extension in `lsp.languagetool-lsp.settings.download`,
+/// which are not passed to the language server
+#[derive(Debug, serde::Deserialize)]
+#[serde(default)]
+struct DownloadSettings {
+    /// Name of the release asset, e.g. for platforms without a default one
+    asset: Option<String>,
+    /// GitHub repository of the releases, e.g. of a fork
+    repository: String,
+    /// Include pre-releases, e.g. nightly builds
+    pre_release: bool,
+    /// Tag of the release instead of the latest one
+    version: Option<String>,
+    /// Download url of the assets instead of GitHub, where `{version}` and
+    /// `{asset}` are replaced, requires the version
+    mirror: Option<String>,
+}
+
+impl Default for DownloadSettings {
+    fn default() -> Self {
+        Self {
+            asset: None,
+            repository: format!("wrenger/{NAME}"),
+            pre_release: false,
+            version: None,
+            mirror: None,
+        }
+    }
+}
+
+impl DownloadSettings {
+    /// Where the releases are downloaded from
+    fn source(&self) -> String {
+        match &self.mirror {
+            Some(mirror) => mirror.clone(),
+            None if self.pre_release => format!("{} (pre-release)", self.repository),
+            None => self.repository.clone(),
+        }
+    }
+
+    fn from_lsp(settings: Option<&LspSettings>) -> Self {
+        settings
+            .and_then(|s| s.settings.as_ref())
+            .and_then(|s| s.get("download"))
+            .and_then(|d| serde_json::from_value(d.clone()).ok())
+            .unwrap_or_default()
+    }
+}
+
+/// Defaults of the workspace configuration, overridden by the user settings
+fn default_settings() -> serde_json::Value {
+    serde_json::json!({
+        "server": "https://api.languagetool.org",
+        "picky": false,
+        "language_variety": {
+            "en": "en-US",
+            "de": "de-DE",
+            "pt": "pt-PT",
+            "ca": "ca-ES",
+        },
+    })
+}
+
+/// Merges the values into the base, objects are merged recursively
+fn merge(base: &mut serde_json::Value, values: serde_json::Value) {
+    match (base, values) {
+        (serde_json::Value::Object(base), serde_json::Value::Object(values)) => {
+            for (key, value) in values {
+                match base.get_mut(&key) {
+                    Some(base) => merge(base, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, value) => *base = value,
+    }
+}
+
+/// Names of the release assets for the platform, in order of preference
+fn asset_names(platform: zed::Os, arch: zed::Architecture) -> Vec<String> {
+    let arch = match arch {
+        zed::Architecture::Aarch64 => "aarch64",
+        zed::Architecture::X86 => "x86",
+        zed::Architecture::X8664 => "x86_64",
+    };
+    let targets: &[&str] = match platform {
+        zed::Os::Mac => &["apple-darwin"],
+        // The statically linked musl build also runs on glibc systems
+        zed::Os::Linux => &["unknown-linux-gnu", "unknown-linux-musl"],
+        zed::Os::Windows => &["pc-windows-msvc"],
+    };
+    targets
+        .iter()
+        .map(|target| format!("{NAME}-{arch}-{target}.zip"))
+        .collect()
+}
+
+/// Archive type of the release asset
+fn file_type(asset_name: &str) -> zed::Result<zed::DownloadedFileType> {
+    if asset_name.ends_with(".zip") {
+        Ok(zed::DownloadedFileType::Zip)
+    } else if asset_name.ends_with(".tar.gz") || asset_name.ends_with(".tgz") {
+        Ok(zed::DownloadedFileType::GzipTar)
+    } else {
+        Err(format!("unsupported archive {asset_name:?}"))
+    }
+}
 
 struct Extension {
     cached_binary: Option<String>,
@@ -25,8 +140,10 @@ impl zed::Extension for Extension {
 
         let settings = LspSettings::for_worktree(NAME, worktree);
         println!("Settings: {settings:?}");
+        let settings = settings.ok();
+        let download = DownloadSettings::from_lsp(settings.as_ref());
 
-        let binary_settings = settings.ok().and_then(|lsp_settings| lsp_settings.binary);
+        let binary_settings = settings.and_then(|lsp_settings| lsp_settings.binary);
         let args = binary_settings
             .as_ref()
             .and_then(|settings| settings.arguments.clone())
@@ -54,72 +171,7 @@ impl zed::Extension for Extension {
             });
         }
 
-        zed::set_language_server_installation_status(
-            language_server_id,
-            &zed::LanguageServerInstallationStatus::CheckingForUpdate,
-        );
-        let release = zed::latest_github_release(
-            &format!("wrenger/{NAME}"),
-            zed::GithubReleaseOptions {
-                require_assets: true,
-                pre_release: false,
-            },
-        )?;
-
-        let (platform, arch) = zed::current_platform();
-        let asset_name = format!(
-            "{NAME}-{arch}-{target}.zip",
-            arch = match arch {
-                zed::Architecture::Aarch64 => "aarch64",
-                zed::Architecture::X86 => "x86",
-                zed::Architecture::X8664 => "x86_64",
-            },
-            target = match platform {
-                zed::Os::Mac => "apple-darwin",
-                zed::Os::Linux => "unknown-linux-gnu",
-                zed::Os::Windows => "pc-windows-msvc",
-            }
-        );
-
-        let asset = release
-            .assets
-            .iter()
-            .find(|asset| asset.name == asset_name)
-            .ok_or_else(|| format!("no asset found matching {:?}", asset_name))?;
-
-        let version_dir = format!("{NAME}-{}", release.version);
-        let binary_path = if platform == zed::Os::Windows {
-            format!("{version_dir}/{NAME}.exe")
-        } else {
-            format!("{version_dir}/{NAME}")
-        };
-
-        if !fs::metadata(&binary_path).map_or(false, |stat| stat.is_file()) {
-            zed::set_language_server_installation_status(
-                language_server_id,
-                &zed::LanguageServerInstallationStatus::Downloading,
-            );
-            zed::download_file(
-                &asset.download_url,
-                &version_dir,
-                zed::DownloadedFileType::Zip,
-            )
-            .map_err(|e| format!("failed to download file: {e}"))?;
-
-            zed::make_file_executable(&binary_path)
-                .map_err(|e| format!("failed to make lsp executable {e}"))?;
-
-            // Cleanup old versions
-            let entries =
-                fs::read_dir(".").map_err(|e| format!("failed to list working directory {e}"))?;
-            for entry in entries {
-                let entry = entry.map_err(|e| format!("failed to load directory entry {e}"))?;
-                if entry.file_name().to_str() != Some(&version_dir) {
-                    fs::remove_dir_all(entry.path()).ok();
-                }
-            }
-        }
-
+        let binary_path = binary(language_server_id, &download)?;
         self.cached_binary = Some(binary_path.clone());
         Ok(zed::Command {
             command: binary_path,
@@ -128,6 +180,18 @@ impl zed::Extension for Extension {
         })
     }
 
+    fn run_slash_command(
+        &self,
+        command: zed::SlashCommand,
+        args: Vec<String>,
+        worktree: Option<&zed::Worktree>,
+    ) -> zed::Result<zed::SlashCommandOutput> {
+        match command.name.as_str() {
+            "lt-check" => self.check_files(args, worktree),
+            name => Err(format!("unknown slash command {name:?}")),
+        }
+    }
+
     fn language_server_workspace_configuration(
         &mut self,
         language_server_id: &zed::LanguageServerId,
@@ -135,7 +199,229 @@ impl zed::Extension for Extension {
     ) -> zed::Result<Option<serde_json::Value>> {
         println!("Workspace configuration called for {language_server_id}");
         let lsp_settings = LspSettings::for_worktree(NAME, worktree)?;
-        Ok(lsp_settings.settings)
+        let mut settings = default_settings();
+        if let Some(user) = lsp_settings.settings {
+            merge(&mut settings, user);
+        }
+        if let serde_json::Value::Object(settings) = &mut settings {
+            settings.remove("download");
+        }
+        Ok(Some(settings))
+    }
+}
+
+/// Installed release in the working directory of the extension
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+struct InstalledRelease {
+    version: String,
+    /// Repository or mirror of the release
+    source: String,
+    binary: String,
+    /// Unix time of the last check for updates
+    checked: u64,
+}
+
+impl InstalledRelease {
+    fn load() -> Option<Self> {
+        let release: Self = serde_json::from_str(&fs::read_to_string(RELEASE_FILE).ok()?).ok()?;
+        fs::metadata(&release.binary)
+            .is_ok_and(|stat| stat.is_file())
+            .then_some(release)
+    }
+
+    fn save(&self) {
+        let json = serde_json::to_string(self).unwrap();
+        if let Err(err) = fs::write(RELEASE_FILE, json) {
+            println!("Failed to save the release: {err}");
+        }
+    }
+
+    /// The release matches the settings and was checked recently
+    fn is_current(&self, download: &DownloadSettings) -> bool {
+        self.source == download.source()
+            && download.version.as_ref().is_none_or(|v| *v == self.version)
+            && now().saturating_sub(self.checked) < UPDATE_INTERVAL
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+/// Path of the language server binary, which is downloaded if there is a
+/// newer release.
+///
+/// The release API is only queried once per update interval, and if it
+/// fails (e.g. due to GitHub's rate limits) the installed binary is used.
+fn binary(
+    language_server_id: &zed::LanguageServerId,
+    download: &DownloadSettings,
+) -> zed::Result<String> {
+    let installed = InstalledRelease::load();
+    if let Some(installed) = &installed
+        && installed.is_current(download)
+    {
+        return Ok(installed.binary.clone());
+    }
+    match install(language_server_id, download) {
+        Ok(release) => {
+            release.save();
+            Ok(release.binary)
+        }
+        Err(err) => match installed {
+            Some(installed) => {
+                println!("Failed to update, use version {}: {err}", installed.version);
+                Ok(installed.binary)
+            }
+            None => Err(err),
+        },
+    }
+}
+
+/// Downloads the release, unless it is already installed
+fn install(
+    language_server_id: &zed::LanguageServerId,
+    download: &DownloadSettings,
+) -> zed::Result<InstalledRelease> {
+    zed::set_language_server_installation_status(
+        language_server_id,
+        &zed::LanguageServerInstallationStatus::CheckingForUpdate,
+    );
+    let (platform, arch) = zed::current_platform();
+    let asset_names = match &download.asset {
+        Some(asset) => vec![asset.clone()],
+        None => asset_names(platform, arch),
+    };
+    // Names and download urls of the matching assets
+    let (version, assets) = match &download.mirror {
+        Some(mirror) => {
+            let version = download
+                .version
+                .clone()
+                .ok_or("the download mirror requires a version")?;
+            let assets = asset_names
+                .iter()
+                .map(|name| {
+                    let url = mirror
+                        .replace("{version}", &version)
+                        .replace("{asset}", name);
+                    (name.clone(), url)
+                })
+                .collect::<Vec<_>>();
+            (version, assets)
+        }
+        None => {
+            let release = match &download.version {
+                Some(tag) => zed::github_release_by_tag_name(&download.repository, tag)?,
+                None => zed::latest_github_release(
+                    &download.repository,
+                    zed::GithubReleaseOptions {
+                        require_assets: true,
+                        pre_release: download.pre_release,
+                    },
+                )?,
+            };
+            let assets = asset_names
+                .iter()
+                .filter_map(|name| release.assets.iter().find(|asset| &asset.name == name))
+                .map(|asset| (asset.name.clone(), asset.download_url.clone()))
+                .collect::<Vec<_>>();
+            if assets.is_empty() {
+                return Err(format!("no asset found matching {asset_names:?}"));
+            }
+            (release.version, assets)
+        }
+    };
+
+    let version_dir = format!("{NAME}-{version}");
+    let binary_path = if platform == zed::Os::Windows {
+        format!("{version_dir}/{NAME}.exe")
+    } else {
+        format!("{version_dir}/{NAME}")
+    };
+
+    if !fs::metadata(&binary_path).is_ok_and(|stat| stat.is_file()) {
+        zed::set_language_server_installation_status(
+            language_server_id,
+            &zed::LanguageServerInstallationStatus::Downloading,
+        );
+        // Mirrors may not provide all assets, so try them in order
+        let mut downloaded = Err(format!("no asset found matching {asset_names:?}"));
+        for (name, url) in &assets {
+            downloaded = file_type(name).and_then(|t| zed::download_file(url, &version_dir, t));
+            if downloaded.is_ok() {
+                break;
+            }
+        }
+        downloaded.map_err(|e| format!("failed to download file: {e}"))?;
+
+        zed::make_file_executable(&binary_path)
+            .map_err(|e| format!("failed to make lsp executable {e}"))?;
+
+        // Cleanup old versions
+        let entries =
+            fs::read_dir(".").map_err(|e| format!("failed to list working directory {e}"))?;
+        for entry in entries {
+            let entry = entry.map_err(|e| format!("failed to load directory entry {e}"))?;
+            let name = entry.file_name();
+            if name.to_str() != Some(&version_dir) && name.to_str() != Some(RELEASE_FILE) {
+                fs::remove_dir_all(entry.path()).ok();
+            }
+        }
+    }
+
+    Ok(InstalledRelease {
+        version,
+        source: download.source(),
+        binary: binary_path,
+        checked: now(),
+    })
+}
+
+impl Extension {
+    /// Checks the files of the worktree with the command line of the
+    /// language server, which has to be on the `PATH` of the worktree as
+    /// the extension is only allowed to execute it by name
+    fn check_files(
+        &self,
+        files: Vec<String>,
+        worktree: Option<&zed::Worktree>,
+    ) -> zed::Result<zed::SlashCommandOutput> {
+        let worktree = worktree.ok_or("no worktree")?;
+        if files.is_empty() {
+            return Err("no files given".into());
+        }
+        if worktree.which(NAME).is_none() {
+            return Err(format!(
+                "{NAME} is not on the PATH, /lt-check cannot use the downloaded language server"
+            ));
+        }
+
+        let root = worktree.root_path();
+        let paths = files.iter().map(|file| format!("{root}/{file}"));
+        let output = zed::process::Command::new(NAME)
+            .arg("check")
+            .args(paths)
+            .envs(worktree.shell_env())
+            .output()?;
+        // Exits with 1 if there are issues and with 2 on errors
+        if !matches!(output.status, Some(0 | 1)) {
+            return Err(String::from_utf8_lossy(&output.stderr).into_owned());
+        }
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let issues = stdout.lines().count();
+        let text = stdout.replace(&format!("{root}/"), "");
+        let label = format!("LanguageTool: {issues} issues in {}", files.join(", "));
+        Ok(zed::SlashCommandOutput {
+            sections: vec![zed::SlashCommandOutputSection {
+                range: (0..text.len()).into(),
+                label,
+            }],
+            text,
+        })
     }
 }
 